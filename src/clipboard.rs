@@ -0,0 +1,66 @@
+/// Return the content of the primary selection (the text currently selected in any
+/// X11/Wayland application), if a clipboard backend is available.
+#[cfg(all(feature = "clipboard", unix, not(target_os = "macos")))]
+#[must_use]
+pub fn get_primary_selection() -> Option<String> {
+    use crate::utils;
+    use copypasta::x11_clipboard::{Primary, X11ClipboardContext};
+    use copypasta::ClipboardProvider;
+
+    match X11ClipboardContext::<Primary>::new().and_then(|mut ctx| ctx.get_contents()) {
+        Ok(contents) => Some(contents),
+        Err(e) => {
+            utils::log(&format!("Couldn't read the primary selection: {e}"));
+            None
+        }
+    }
+}
+
+/// Return the content of the primary selection. As bo was built without clipboard
+/// support (or the platform has no primary selection), there's nothing to return.
+#[cfg(not(all(feature = "clipboard", unix, not(target_os = "macos"))))]
+#[must_use]
+pub fn get_primary_selection() -> Option<String> {
+    None
+}
+
+/// Return the content of the system clipboard, or None (after logging the reason) if
+/// no clipboard is available, eg: over SSH.
+#[cfg(feature = "clipboard")]
+#[must_use]
+pub fn get_clipboard() -> Option<String> {
+    use crate::utils;
+    use copypasta::{ClipboardContext, ClipboardProvider};
+
+    match ClipboardContext::new().and_then(|mut ctx| ctx.get_contents()) {
+        Ok(contents) => Some(contents),
+        Err(e) => {
+            utils::log(&format!("Couldn't read the clipboard: {e}"));
+            None
+        }
+    }
+}
+
+/// Copy the text into the system clipboard, if one is available
+#[cfg(feature = "clipboard")]
+pub fn set_clipboard(text: &str) {
+    use crate::utils;
+    use copypasta::{ClipboardContext, ClipboardProvider};
+
+    if let Err(e) = ClipboardContext::new().and_then(|mut ctx| ctx.set_contents(text.to_string())) {
+        utils::log(&format!("Couldn't write to the clipboard: {e}"));
+    }
+}
+
+/// Return the content of the system clipboard. As bo was built without clipboard
+/// support, there's nothing to return.
+#[cfg(not(feature = "clipboard"))]
+#[must_use]
+pub fn get_clipboard() -> Option<String> {
+    None
+}
+
+/// Copy the text into the system clipboard. As bo was built without clipboard support,
+/// the text only goes into the `+` register.
+#[cfg(not(feature = "clipboard"))]
+pub fn set_clipboard(_text: &str) {}
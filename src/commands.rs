@@ -1,5 +1,6 @@
 pub const QUIT: &str = "q";
 pub const FORCE_QUIT: &str = "q!";
+pub const QUIT_ALL: &str = "qa";
 pub const LINE_NUMBERS: &str = "ln";
 pub const STATS: &str = "stats";
 pub const HELP: &str = "help";
@@ -9,3 +10,72 @@ pub const NEW: &str = "new";
 pub const SAVE: &str = "w";
 pub const SAVE_AND_QUIT: &str = "wq";
 pub const DEBUG: &str = "debug";
+pub const SET: &str = "set";
+pub const NO_HIGHLIGHT_SEARCH: &str = "noh";
+pub const HEX: &str = "hex";
+pub const JUMPS: &str = "jumps";
+pub const MARKS: &str = "marks";
+pub const REGISTERS: &str = "registers";
+pub const REGISTERS_SHORT: &str = "reg";
+pub const SOURCE: &str = "source";
+pub const NEXT_BUFFER: &str = "bn";
+pub const PREVIOUS_BUFFER: &str = "bp";
+pub const LIST_BUFFERS: &str = "ls";
+pub const SPLIT: &str = "split";
+pub const SPLIT_SHORT: &str = "sp";
+pub const DATE: &str = "date";
+pub const DELETE: &str = "d";
+pub const SORT: &str = "sort";
+pub const NORMALIZE_INDENT: &str = "normalize-indent";
+pub const TAG: &str = "tag";
+pub const MAKE_SESSION: &str = "mksession";
+pub const SOURCE_SESSION: &str = "source-session";
+pub const RECOVER: &str = "recover";
+pub const DISCARD: &str = "discard";
+pub const UNDO: &str = "undo";
+pub const EARLIER: &str = "earlier";
+pub const LATER: &str = "later";
+
+/// The commands taking a file name, completed by Tab in the prompt
+pub const WITH_FILENAME: &[&str] = &[OPEN, OPEN_SHORT, NEW, SAVE, SAVE_AND_QUIT, SOURCE];
+
+/// All the commands, as completed by Tab in the prompt
+pub const ALL: &[&str] = &[
+    QUIT,
+    FORCE_QUIT,
+    QUIT_ALL,
+    LINE_NUMBERS,
+    STATS,
+    HELP,
+    OPEN,
+    OPEN_SHORT,
+    NEW,
+    SAVE,
+    SAVE_AND_QUIT,
+    DEBUG,
+    SET,
+    NO_HIGHLIGHT_SEARCH,
+    HEX,
+    JUMPS,
+    MARKS,
+    REGISTERS,
+    REGISTERS_SHORT,
+    SOURCE,
+    NEXT_BUFFER,
+    PREVIOUS_BUFFER,
+    LIST_BUFFERS,
+    SPLIT,
+    SPLIT_SHORT,
+    DATE,
+    DELETE,
+    SORT,
+    NORMALIZE_INDENT,
+    TAG,
+    MAKE_SESSION,
+    SOURCE_SESSION,
+    RECOVER,
+    DISCARD,
+    UNDO,
+    EARLIER,
+    LATER,
+];
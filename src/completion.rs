@@ -0,0 +1,95 @@
+use std::cmp;
+use std::ops::Range;
+
+/// Candidates completing the word being typed in the prompt, cycled through
+/// with Tab and Shift-Tab.
+#[derive(Debug, Default, PartialEq)]
+pub struct Completion {
+    /// Part of the prompt preceding the completed word
+    pub base: String,
+    pub candidates: Vec<String>,
+    /// Index of the selected candidate, if any was selected yet
+    pub selected: Option<usize>,
+}
+
+impl Completion {
+    #[must_use]
+    pub fn new(base: String, candidates: Vec<String>) -> Self {
+        Self {
+            base,
+            candidates,
+            selected: None,
+        }
+    }
+
+    /// Select the next candidate, wrapping around after the last one
+    pub fn select_next(&mut self) {
+        let len = self.candidates.len();
+        if len > 0 {
+            self.selected = Some(self.selected.map_or(0, |index| (index + 1) % len));
+        }
+    }
+
+    /// Select the previous candidate, wrapping around before the first one
+    pub fn select_previous(&mut self) {
+        let len = self.candidates.len();
+        if len > 0 {
+            self.selected = Some(
+                self.selected
+                    .map_or(len - 1, |index| (index + len - 1) % len),
+            );
+        }
+    }
+
+    /// Return the prompt content, completed with the selected candidate
+    #[must_use]
+    pub fn completed_text(&self) -> String {
+        let candidate = self
+            .selected
+            .and_then(|index| self.candidates.get(index))
+            .map_or("", String::as_str);
+        format!("{}{candidate}", self.base)
+    }
+
+    /// Return the longest text all the candidates start with
+    #[must_use]
+    pub fn common_prefix(&self) -> String {
+        let Some((first, others)) = self.candidates.split_first() else {
+            return String::new();
+        };
+        let mut prefix = first.clone();
+        for candidate in others {
+            while !candidate.starts_with(&prefix) {
+                prefix.pop();
+            }
+        }
+        prefix
+    }
+
+    /// Return the range of candidates fitting in a menu of the provided width, each
+    /// candidate being followed by 2 spaces. The selected candidate is always visible.
+    #[must_use]
+    pub fn visible_range(&self, width: usize) -> Range<usize> {
+        let selected = self.selected.unwrap_or(0);
+        let mut start = 0;
+        while start < selected && self.menu_width(start..selected + 1) > width {
+            start += 1;
+        }
+        let mut end = start;
+        while end < self.candidates.len() && self.menu_width(start..end + 1) <= width {
+            end += 1;
+        }
+        start..cmp::min(cmp::max(end, start + 1), self.candidates.len())
+    }
+
+    fn menu_width(&self, range: Range<usize>) -> usize {
+        self.candidates[range]
+            .iter()
+            .map(|candidate| candidate.chars().count() + 2)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+#[path = "./completion_test.rs"]
+mod completion_test;
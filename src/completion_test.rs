@@ -0,0 +1,59 @@
+use crate::Completion;
+
+fn get_completion() -> Completion {
+    Completion::new(
+        String::from(":"),
+        vec![
+            String::from("new"),
+            String::from("noh"),
+            String::from("normalize"),
+        ],
+    )
+}
+
+#[test]
+fn test_completion_cycles_through_candidates() {
+    let mut completion = get_completion();
+    assert_eq!(completion.completed_text(), ":");
+    completion.select_next();
+    assert_eq!(completion.completed_text(), ":new");
+    completion.select_next();
+    completion.select_next();
+    assert_eq!(completion.completed_text(), ":normalize");
+    completion.select_next();
+    assert_eq!(completion.completed_text(), ":new");
+    completion.select_previous();
+    assert_eq!(completion.completed_text(), ":normalize");
+}
+
+#[test]
+fn test_completion_select_previous_first() {
+    let mut completion = get_completion();
+    completion.select_previous();
+    assert_eq!(completion.selected, Some(2));
+    let mut empty_completion = Completion::default();
+    empty_completion.select_next();
+    assert_eq!(empty_completion.selected, None);
+}
+
+#[test]
+fn test_completion_visible_range() {
+    let mut completion = get_completion();
+    assert_eq!(completion.visible_range(100), 0..3);
+    assert_eq!(completion.visible_range(10), 0..2);
+    // the menu scrolls to keep the selected candidate visible
+    completion.selected = Some(2);
+    assert_eq!(completion.visible_range(16), 1..3);
+    assert_eq!(completion.visible_range(5), 2..3);
+}
+
+#[test]
+fn test_completion_common_prefix() {
+    assert_eq!(get_completion().common_prefix(), "n");
+    let completion = Completion::new(
+        String::from(":open "),
+        vec![String::from("src/édit.rs"), String::from("src/édition.rs")],
+    );
+    assert_eq!(completion.common_prefix(), "src/édit");
+    assert_eq!(Completion::default().common_prefix(), "");
+}
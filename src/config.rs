@@ -1,14 +1,354 @@
-#[derive(Default, Debug)]
+use crate::utils;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+pub const CONFIG_FILE: &str = "~/.config/bo/config.toml";
+const DEFAULT_KEYWORD_PROGRAM: &str = "man %s";
+const DEFAULT_TAB_WIDTH: usize = 4;
+const DEFAULT_DATE_FORMAT: &str = "iso";
+
+/// A column highlighted by the colorcolumn, either absolute (starting at 1) or relative
+/// to the `textwidth` (eg: `+1` for the column following it)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorColumn {
+    Absolute(usize),
+    Relative(isize),
+}
+
+impl ColorColumn {
+    /// Parse a comma-separated list of columns (eg: `80,+1,-2`), empty to disable the
+    /// colorcolumn. Return `None` if one of the columns is invalid.
+    #[must_use]
+    pub fn parse_list(value: &str) -> Option<Vec<Self>> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .map(|column| {
+                if let Some(offset) = column.strip_prefix('+') {
+                    offset.parse().ok().map(Self::Relative)
+                } else if let Some(offset) = column.strip_prefix('-') {
+                    offset
+                        .parse::<isize>()
+                        .ok()
+                        .map(|offset| Self::Relative(-offset))
+                } else {
+                    column
+                        .parse()
+                        .ok()
+                        .filter(|column| *column > 0)
+                        .map(Self::Absolute)
+                }
+            })
+            .collect()
+    }
+
+    /// Return the column (starting at 1), the relative ones requiring a `textwidth`
+    #[must_use]
+    pub fn resolve(self, textwidth: usize) -> Option<usize> {
+        match self {
+            Self::Absolute(column) => Some(column),
+            Self::Relative(_) if textwidth == 0 => None,
+            Self::Relative(offset) => textwidth
+                .checked_add_signed(offset)
+                .filter(|column| *column > 0),
+        }
+    }
+}
+
+impl fmt::Display for ColorColumn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Absolute(column) => write!(f, "{column}"),
+            Self::Relative(offset) => write!(f, "{offset:+}"),
+        }
+    }
+}
+
+/// How the path of the document is displayed in the status bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatuslinePath {
+    Absolute,
+    /// Relative to the current directory, the files located outside of it being displayed
+    /// with their absolute path
+    Relative,
+    Basename,
+}
+
+impl StatuslinePath {
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "absolute" => Some(Self::Absolute),
+            "relative" => Some(Self::Relative),
+            "basename" => Some(Self::Basename),
+            _ => None,
+        }
+    }
+
+    /// Format the path, the relative paths being relative to the provided current directory
+    #[must_use]
+    pub fn format(self, path: &Path, current_dir: &Path) -> String {
+        let absolute_path = current_dir.join(path);
+        let formatted = match self {
+            Self::Absolute => absolute_path.as_path(),
+            Self::Relative => absolute_path
+                .strip_prefix(current_dir)
+                .ok()
+                .filter(|relative_path| !relative_path.as_os_str().is_empty())
+                .unwrap_or(&absolute_path),
+            Self::Basename => path.file_name().map_or(path, Path::new),
+        };
+        formatted.to_string_lossy().to_string()
+    }
+}
+
+impl fmt::Display for StatuslinePath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Self::Absolute => "absolute",
+            Self::Relative => "relative",
+            Self::Basename => "basename",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Config {
     pub display_line_numbers: bool,
     pub display_stats: bool,
+    /// Ignore the case of the searched text
+    pub ignore_case: bool,
+    /// Command run by `K` to look up the word under the cursor, `%s` being replaced by the word
+    pub keyword_program: String,
+    /// Per file extension overrides of `keyword_program` (eg: "py" => "pydoc %s")
+    pub keyword_programs: HashMap<String, String>,
+    /// Minimal number of lines to keep above and below the cursor when scrolling
+    pub scrolloff: usize,
+    /// Keep highlighting the search matches until `:noh` or a new search
+    pub hlsearch: bool,
+    /// Remove the trailing whitespace of every line when saving the document
+    pub trim_trailing_spaces: bool,
+    /// Display the completion candidates above the prompt when pressing Tab
+    pub wildmenu: bool,
+    /// Number of columns a literal tab is displayed as, and of spaces inserted by Tab
+    pub tab_width: usize,
+    /// Insert spaces when pressing Tab, instead of a literal tab
+    pub expand_tab: bool,
+    /// Indent the new lines like the line they were created from
+    pub autoindent: bool,
+    /// Let `;` and `,` continue the search of the repeated find-char motion on the
+    /// following (or preceding) lines
+    pub find_across_lines: bool,
+    /// Start bo in insert mode instead of normal mode
+    pub start_in_insert: bool,
+    /// Format of the date inserted by `:date`, either strftime-like (eg: `%d/%m/%Y`) or
+    /// one of the `iso`, `datetime` and `time` presets
+    pub date_format: String,
+    /// Let Home and `0` go to the first non whitespace character of the line, and to
+    /// its first character when pressed again
+    pub smart_home: bool,
+    /// Start the lines created from a line comment with `o`, `O` or Enter with the same
+    /// comment prefix
+    pub comment_continuation: bool,
+    /// Columns highlighted on every line, as guides for the line length
+    pub colorcolumn: Vec<ColorColumn>,
+    /// Maximum line length, which the relative colorcolumns are based on (0 if unset)
+    pub textwidth: usize,
+    /// Instead of the colorcolumn guide, highlight the part of the lines reaching it
+    pub colorcolumn_overflow: bool,
+    /// Indent the opened files like their existing lines (with tabs, or a number of
+    /// spaces), instead of using `tab_width` and `expand_tab`
+    pub detect_indent: bool,
+    /// Display the literal tabs and trailing spaces with visible glyphs
+    pub list: bool,
+    /// Number of lines scrolled by Ctrl-D and Ctrl-U, half of the view when 0
+    pub scroll: usize,
+    /// Save the files opened through a symlink to its target, instead of replacing the
+    /// symlink with a regular file
+    pub follow_symlinks: bool,
+    /// How the path of the document is displayed in the status bar
+    pub statusline_path: StatuslinePath,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            display_line_numbers: false,
+            display_stats: false,
+            ignore_case: false,
+            keyword_program: String::from(DEFAULT_KEYWORD_PROGRAM),
+            keyword_programs: HashMap::new(),
+            scrolloff: 0,
+            hlsearch: false,
+            trim_trailing_spaces: true,
+            wildmenu: true,
+            tab_width: DEFAULT_TAB_WIDTH,
+            expand_tab: true,
+            autoindent: true,
+            find_across_lines: false,
+            start_in_insert: false,
+            date_format: String::from(DEFAULT_DATE_FORMAT),
+            smart_home: false,
+            comment_continuation: false,
+            colorcolumn: vec![],
+            textwidth: 0,
+            colorcolumn_overflow: false,
+            detect_indent: false,
+            list: false,
+            scroll: 0,
+            follow_symlinks: true,
+            statusline_path: StatuslinePath::Relative,
+        }
+    }
 }
 
 impl Config {
+    /// Load the configuration from the user config file, falling back to the defaults
+    /// if it doesn't exist.
+    ///
+    /// # Errors
+    /// Returns the (logged) error message if the file is malformed.
+    pub fn load() -> Result<Self, String> {
+        let path = PathBuf::from(utils::expand_tilde(CONFIG_FILE));
+        Self::load_from(&path).map_err(|e| {
+            let message = format!("Couldn't parse {}: {e}", path.display());
+            utils::log(&message);
+            message
+        })
+    }
+
+    /// Load the configuration from the provided TOML file, falling back to the defaults
+    /// if the file doesn't exist.
+    ///
+    /// # Errors
+    /// Returns an error if the file is malformed.
+    pub fn load_from(path: &Path) -> Result<Self, toml::de::Error> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Parse a TOML configuration, in which each missing or invalid key keeps its default
+    /// value.
+    ///
+    /// # Errors
+    /// Returns an error if the content isn't valid TOML.
+    pub fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        let table: toml::value::Table = toml::from_str(contents)?;
+        let default = Self::default();
+        let bool_value =
+            |key: &str, default: bool| table.get(key).and_then(Value::as_bool).unwrap_or(default);
+        let keyword_programs = table
+            .get("keyword_programs")
+            .and_then(Value::as_table)
+            .map(|programs| {
+                programs
+                    .iter()
+                    .filter_map(|(ext, program)| Some((ext.clone(), program.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or(default.keyword_programs);
+        Ok(Self {
+            display_line_numbers: bool_value("display_line_numbers", default.display_line_numbers),
+            display_stats: bool_value("display_stats", default.display_stats),
+            ignore_case: bool_value("ignore_case", default.ignore_case),
+            keyword_program: table
+                .get("keyword_program")
+                .and_then(Value::as_str)
+                .map_or(default.keyword_program, str::to_string),
+            keyword_programs,
+            scrolloff: table
+                .get("scrolloff")
+                .and_then(Value::as_integer)
+                .and_then(|scrolloff| usize::try_from(scrolloff).ok())
+                .unwrap_or(default.scrolloff),
+            hlsearch: bool_value("hlsearch", default.hlsearch),
+            trim_trailing_spaces: bool_value("trim_trailing_on_save", default.trim_trailing_spaces),
+            wildmenu: bool_value("wildmenu", default.wildmenu),
+            tab_width: table
+                .get("tab_width")
+                .and_then(Value::as_integer)
+                .and_then(|tab_width| usize::try_from(tab_width).ok())
+                .filter(|tab_width| *tab_width > 0)
+                .unwrap_or(default.tab_width),
+            expand_tab: bool_value("expand_tab", default.expand_tab),
+            autoindent: bool_value("autoindent", default.autoindent),
+            find_across_lines: bool_value("find_across_lines", default.find_across_lines),
+            start_in_insert: bool_value("start_in_insert", default.start_in_insert),
+            date_format: table
+                .get("date_format")
+                .and_then(Value::as_str)
+                .map_or(default.date_format, str::to_string),
+            smart_home: bool_value("smart_home", default.smart_home),
+            comment_continuation: bool_value("comment_continuation", default.comment_continuation),
+            colorcolumn: match table.get("colorcolumn") {
+                Some(Value::Integer(column)) => usize::try_from(*column)
+                    .ok()
+                    .filter(|column| *column > 0)
+                    .map_or(default.colorcolumn, |column| {
+                        vec![ColorColumn::Absolute(column)]
+                    }),
+                Some(Value::String(columns)) => {
+                    ColorColumn::parse_list(columns).unwrap_or(default.colorcolumn)
+                }
+                _ => default.colorcolumn,
+            },
+            textwidth: table
+                .get("textwidth")
+                .and_then(Value::as_integer)
+                .and_then(|textwidth| usize::try_from(textwidth).ok())
+                .unwrap_or(default.textwidth),
+            colorcolumn_overflow: bool_value("colorcolumn_overflow", default.colorcolumn_overflow),
+            detect_indent: bool_value("detect_indent", default.detect_indent),
+            list: bool_value("list", default.list),
+            scroll: table
+                .get("scroll")
+                .and_then(Value::as_integer)
+                .and_then(|scroll| usize::try_from(scroll).ok())
+                .unwrap_or(default.scroll),
+            follow_symlinks: bool_value("follow_symlinks", default.follow_symlinks),
+            statusline_path: table
+                .get("statusline_path")
+                .and_then(Value::as_str)
+                .and_then(StatuslinePath::from_name)
+                .unwrap_or(default.statusline_path),
+        })
+    }
+
     #[must_use]
     pub fn toggle(config: bool) -> bool {
         !config
     }
+
+    /// Return the sorted colorcolumns, starting at 0
+    #[must_use]
+    pub fn colorcolumns(&self) -> Vec<usize> {
+        let mut columns: Vec<usize> = self
+            .colorcolumn
+            .iter()
+            .filter_map(|column| column.resolve(self.textwidth))
+            .map(|column| column.saturating_sub(1))
+            .collect();
+        columns.sort_unstable();
+        columns.dedup();
+        columns
+    }
+
+    /// Return the keyword program to use for a file with the provided extension
+    #[must_use]
+    pub fn keyword_program_for(&self, extension: Option<&str>) -> &str {
+        extension
+            .and_then(|ext| self.keyword_programs.get(ext))
+            .unwrap_or(&self.keyword_program)
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,7 @@
-use crate::Config;
+use crate::{ColorColumn, Config, StatuslinePath};
+use std::io::Write;
+use std::path::Path;
+use tempfile::NamedTempFile;
 
 #[test]
 fn test_config_toggle() {
@@ -6,4 +9,146 @@ fn test_config_toggle() {
     assert!(!conf.display_stats);
     conf.display_stats = Config::toggle(conf.display_stats);
     assert!(conf.display_stats);
+    conf.ignore_case = Config::toggle(conf.ignore_case);
+    assert!(conf.ignore_case);
+}
+
+#[test]
+fn test_config_keyword_program_for() {
+    let mut conf = Config::default();
+    assert_eq!(conf.keyword_program_for(None), "man %s");
+    assert_eq!(conf.keyword_program_for(Some("py")), "man %s");
+    conf.keyword_programs
+        .insert(String::from("py"), String::from("pydoc %s"));
+    assert_eq!(conf.keyword_program_for(Some("py")), "pydoc %s");
+    assert_eq!(conf.keyword_program_for(Some("c")), "man %s");
+}
+
+#[test]
+fn test_config_from_toml() {
+    let conf = Config::from_toml(
+        r#"
+display_line_numbers = true
+scrolloff = 5
+tab_width = 2
+trim_trailing_on_save = false
+find_across_lines = true
+start_in_insert = true
+date_format = "%d/%m/%Y"
+smart_home = true
+comment_continuation = true
+colorcolumn = 81
+colorcolumn_overflow = true
+detect_indent = true
+list = true
+scroll = 10
+follow_symlinks = false
+statusline_path = "basename"
+keyword_program = "tldr %s"
+
+[keyword_programs]
+py = "pydoc %s"
+"#,
+    )
+    .unwrap();
+    assert!(conf.display_line_numbers);
+    assert!(!conf.display_stats);
+    assert_eq!(conf.scrolloff, 5);
+    assert_eq!(conf.tab_width, 2);
+    assert!(!conf.trim_trailing_spaces);
+    assert!(conf.find_across_lines);
+    assert!(conf.start_in_insert);
+    assert_eq!(conf.date_format, "%d/%m/%Y");
+    assert!(conf.smart_home);
+    assert!(conf.comment_continuation);
+    assert_eq!(conf.colorcolumn, vec![ColorColumn::Absolute(81)]);
+    assert!(conf.colorcolumn_overflow);
+    assert!(conf.detect_indent);
+    assert!(conf.list);
+    assert_eq!(conf.scroll, 10);
+    assert!(!conf.follow_symlinks);
+    assert_eq!(conf.statusline_path, StatuslinePath::Basename);
+    assert_eq!(conf.keyword_program_for(None), "tldr %s");
+    assert_eq!(conf.keyword_program_for(Some("py")), "pydoc %s");
+}
+
+#[test]
+fn test_config_from_toml_invalid_keys() {
+    // invalid values keep their default, and unknown keys are ignored
+    let conf = Config::from_toml(
+        "display_stats = 1\nscrolloff = -2\ntab_width = 0\ncolorcolumn = 0\nunknown = true",
+    )
+    .unwrap();
+    assert!(!conf.display_stats);
+    assert_eq!(conf.scrolloff, 0);
+    assert_eq!(conf.tab_width, 4);
+    assert_eq!(conf.colorcolumn, vec![]);
+    assert!(Config::from_toml("display_stats = ").is_err());
+}
+
+#[test]
+fn test_statusline_path_format() {
+    let current_dir = Path::new("/home/user/project");
+    let format = |statusline_path: StatuslinePath, path: &str| {
+        statusline_path.format(Path::new(path), current_dir)
+    };
+    assert_eq!(
+        format(StatuslinePath::Relative, "/home/user/project/src/main.rs"),
+        "src/main.rs"
+    );
+    assert_eq!(
+        format(StatuslinePath::Relative, "src/main.rs"),
+        "src/main.rs"
+    );
+    // the files outside of the current directory keep their absolute path
+    assert_eq!(format(StatuslinePath::Relative, "/etc/hosts"), "/etc/hosts");
+    assert_eq!(
+        format(StatuslinePath::Absolute, "src/main.rs"),
+        "/home/user/project/src/main.rs"
+    );
+    assert_eq!(format(StatuslinePath::Basename, "/etc/hosts"), "hosts");
+    assert_eq!(
+        StatuslinePath::from_name("basename"),
+        Some(StatuslinePath::Basename)
+    );
+    assert_eq!(StatuslinePath::from_name("full"), None);
+    assert_eq!(Config::default().statusline_path, StatuslinePath::Relative);
+}
+
+#[test]
+fn test_config_colorcolumns() {
+    assert_eq!(
+        ColorColumn::parse_list("80, +1,-2"),
+        Some(vec![
+            ColorColumn::Absolute(80),
+            ColorColumn::Relative(1),
+            ColorColumn::Relative(-2)
+        ])
+    );
+    assert_eq!(ColorColumn::parse_list(""), Some(vec![]));
+    assert_eq!(ColorColumn::parse_list("80,abc"), None);
+    assert_eq!(ColorColumn::parse_list("0"), None);
+
+    let mut conf = Config::from_toml("colorcolumn = \"120,+1,80,-80\"").unwrap();
+    // the relative columns are ignored without a textwidth
+    assert_eq!(conf.colorcolumns(), vec![79, 119]);
+    conf.textwidth = 100;
+    assert_eq!(conf.colorcolumns(), vec![19, 79, 100, 119]);
+    conf.textwidth = 80;
+    assert_eq!(conf.colorcolumns(), vec![79, 80, 119]);
+}
+
+#[test]
+fn test_config_load_from() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"wildmenu = false\n").unwrap();
+    assert!(!Config::load_from(file.path()).unwrap().wildmenu);
+    // a malformed file is reported, and a missing one gives the default configuration
+    file.write_all(b"[[[").unwrap();
+    assert!(Config::load_from(file.path()).is_err());
+    assert!(
+        Config::load_from(Path::new("/non/existing/config.toml"))
+            .unwrap()
+            .wildmenu
+    );
 }
@@ -76,4 +76,6 @@ pub trait Console: Debug {
     fn set_cursor_as_steady_bar(&self);
 
     fn set_cursor_as_steady_block(&self);
+
+    fn set_cursor_as_steady_underline(&self);
 }
@@ -0,0 +1,112 @@
+/// Digraphs entered with Ctrl-K in insert mode, as in RFC 1345 (eg: `a:` for `ä`)
+const DIGRAPHS: &[(char, char, char)] = &[
+    ('a', ':', 'ä'),
+    ('e', ':', 'ë'),
+    ('i', ':', 'ï'),
+    ('o', ':', 'ö'),
+    ('u', ':', 'ü'),
+    ('y', ':', 'ÿ'),
+    ('A', ':', 'Ä'),
+    ('E', ':', 'Ë'),
+    ('I', ':', 'Ï'),
+    ('O', ':', 'Ö'),
+    ('U', ':', 'Ü'),
+    ('a', '\'', 'á'),
+    ('e', '\'', 'é'),
+    ('i', '\'', 'í'),
+    ('o', '\'', 'ó'),
+    ('u', '\'', 'ú'),
+    ('y', '\'', 'ý'),
+    ('A', '\'', 'Á'),
+    ('E', '\'', 'É'),
+    ('I', '\'', 'Í'),
+    ('O', '\'', 'Ó'),
+    ('U', '\'', 'Ú'),
+    ('a', '!', 'à'),
+    ('e', '!', 'è'),
+    ('i', '!', 'ì'),
+    ('o', '!', 'ò'),
+    ('u', '!', 'ù'),
+    ('A', '!', 'À'),
+    ('E', '!', 'È'),
+    ('I', '!', 'Ì'),
+    ('O', '!', 'Ò'),
+    ('U', '!', 'Ù'),
+    ('a', '>', 'â'),
+    ('e', '>', 'ê'),
+    ('i', '>', 'î'),
+    ('o', '>', 'ô'),
+    ('u', '>', 'û'),
+    ('A', '>', 'Â'),
+    ('E', '>', 'Ê'),
+    ('I', '>', 'Î'),
+    ('O', '>', 'Ô'),
+    ('U', '>', 'Û'),
+    ('a', '?', 'ã'),
+    ('n', '?', 'ñ'),
+    ('o', '?', 'õ'),
+    ('A', '?', 'Ã'),
+    ('N', '?', 'Ñ'),
+    ('O', '?', 'Õ'),
+    ('c', ',', 'ç'),
+    ('C', ',', 'Ç'),
+    ('a', 'a', 'å'),
+    ('A', 'A', 'Å'),
+    ('a', 'e', 'æ'),
+    ('A', 'E', 'Æ'),
+    ('o', 'e', 'œ'),
+    ('O', 'E', 'Œ'),
+    ('o', '/', 'ø'),
+    ('O', '/', 'Ø'),
+    ('s', 's', 'ß'),
+    ('!', 'I', '¡'),
+    ('?', 'I', '¿'),
+    ('<', '<', '«'),
+    ('>', '>', '»'),
+    ('E', 'u', '€'),
+    ('P', 'd', '£'),
+    ('Y', 'e', '¥'),
+    ('C', 't', '¢'),
+    ('C', 'o', '©'),
+    ('R', 'g', '®'),
+    ('T', 'M', '™'),
+    ('S', 'E', '§'),
+    ('D', 'G', '°'),
+    ('+', '-', '±'),
+    ('*', 'X', '×'),
+    ('-', ':', '÷'),
+    ('1', '2', '½'),
+    ('1', '4', '¼'),
+    ('3', '4', '¾'),
+    ('M', 'y', 'µ'),
+    ('-', 'N', '–'),
+    ('-', 'M', '—'),
+    ('.', '.', '‥'),
+    ('-', '>', '→'),
+    ('<', '-', '←'),
+    ('a', '*', 'α'),
+    ('b', '*', 'β'),
+    ('g', '*', 'γ'),
+    ('d', '*', 'δ'),
+    ('l', '*', 'λ'),
+    ('m', '*', 'μ'),
+    ('p', '*', 'π'),
+    ('s', '*', 'σ'),
+];
+
+/// Return the character associated with the digraph, whose characters can be
+/// typed in any order.
+#[must_use]
+pub fn digraph(first: char, second: char) -> Option<char> {
+    let find = |first, second| {
+        DIGRAPHS
+            .iter()
+            .find(|(a, b, _)| *a == first && *b == second)
+            .map(|(_, _, c)| *c)
+    };
+    find(first, second).or_else(|| find(second, first))
+}
+
+#[cfg(test)]
+#[path = "./digraph_test.rs"]
+mod digraph_test;
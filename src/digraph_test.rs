@@ -0,0 +1,15 @@
+use crate::digraph::digraph;
+
+#[test]
+fn test_digraph() {
+    assert_eq!(digraph('a', ':'), Some('ä'));
+    assert_eq!(digraph('E', '\''), Some('É'));
+    assert_eq!(digraph('E', 'u'), Some('€'));
+    assert_eq!(digraph('q', 'q'), None);
+}
+
+#[test]
+fn test_digraph_reversed() {
+    assert_eq!(digraph(':', 'a'), Some('ä'));
+    assert_eq!(digraph(',', 'c'), Some('ç'));
+}
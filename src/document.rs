@@ -1,18 +1,91 @@
-use crate::Row;
+use crate::{EditOp, FileFormat, Indentation, Position, Row, UndoDistance, UndoHistory, UndoStep};
+use regex::{NoExpand, Regex};
 use serde::Serialize;
-use std::cmp::Ordering;
+use std::cmp::{self, Ordering};
 use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
 use std::fmt;
 use std::fs;
 use std::hash::{Hash, Hasher};
 use std::io::{Error, Write};
 use std::path::{Path, PathBuf};
 use std::slice::{Iter, IterMut};
+use std::str;
+
+const BOM: &str = "\u{feff}";
+/// The bytes of the invalid UTF-8 sequences of a file opened in binary mode (all of them
+/// being non-ASCII) are kept as the private use characters located at this offset plus
+/// their value, from U+F780 to U+F7FF, and written back as is on save.
+const RAW_BYTES_OFFSET: u32 = 0xF700;
 
 #[derive(Serialize)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Document {
     rows: Vec<Row>,
     pub filename: Option<PathBuf>,
+    file_format: FileFormat,
+    #[serde(skip)]
+    binary: bool,
+    /// Whether the file isn't valid UTF-8. Until it's reloaded in binary mode, in which
+    /// its invalid bytes are kept verbatim, the document is left empty and can't be saved.
+    #[serde(skip)]
+    invalid_utf8: bool,
+    #[serde(skip)]
+    trailing_newline: bool,
+    /// Whether the file started with a UTF-8 byte order mark, written back on save
+    #[serde(skip)]
+    has_bom: bool,
+    /// Indentation detected in the file when it was opened
+    #[serde(skip)]
+    indentation: Option<Indentation>,
+    /// File the document file links to, when it was opened through a symlink
+    #[serde(skip)]
+    symlink_target: Option<PathBuf>,
+    /// Whether a swap file with a different content (eg: left by a crashed session) was
+    /// found when opening the file. It's neither overwritten nor deleted until it's
+    /// recovered or discarded.
+    #[serde(skip)]
+    pending_swap_file: bool,
+    #[serde(skip)]
+    undo_history: UndoHistory,
+    /// Rows added or removed since the last call to `take_row_shifts`
+    #[serde(skip)]
+    row_shifts: Vec<RowShift>,
+}
+
+/// Rows replaced by a different number of rows, shifting the ones located below them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowShift {
+    /// Index of the first replaced row
+    pub y: usize,
+    pub num_rows_before: usize,
+    pub num_rows_after: usize,
+}
+
+impl RowShift {
+    /// Return the index the row located at the provided index was moved to. The rows
+    /// that were removed end up on the last row of the replacement.
+    #[must_use]
+    pub fn shifted_row_index(&self, row_index: usize) -> usize {
+        if row_index < self.y {
+            return row_index;
+        }
+        let end_before = self.y.saturating_add(self.num_rows_before);
+        if row_index >= end_before {
+            return (row_index.saturating_add(self.num_rows_after))
+                .saturating_sub(self.num_rows_before);
+        }
+        let last_row_after = self.y.saturating_add(self.num_rows_after).saturating_sub(1);
+        cmp::max(cmp::min(row_index, last_row_after), self.y)
+    }
+}
+
+/// Snapshot of the rows about to be edited, turned into an `EditOp` once the edit is done
+struct PendingEdit {
+    y: usize,
+    before: Vec<String>,
+    line_count: usize,
+    position: Position,
 }
 
 impl fmt::Debug for Document {
@@ -27,6 +100,16 @@ impl Default for Document {
         Self {
             rows: vec![Row::from("")],
             filename: None,
+            file_format: FileFormat::default(),
+            binary: false,
+            invalid_utf8: false,
+            trailing_newline: true,
+            has_bom: false,
+            indentation: None,
+            symlink_target: None,
+            pending_swap_file: false,
+            undo_history: UndoHistory::default(),
+            row_shifts: vec![],
         }
     }
 }
@@ -36,6 +119,7 @@ impl Hash for Document {
         for row in self.iter() {
             row.hash(state);
         }
+        self.file_format.hash(state);
     }
 }
 
@@ -45,6 +129,24 @@ impl Document {
         Self {
             rows,
             filename: Some(filename),
+            file_format: FileFormat::default(),
+            binary: false,
+            invalid_utf8: false,
+            trailing_newline: true,
+            has_bom: false,
+            indentation: None,
+            symlink_target: None,
+            pending_swap_file: false,
+            undo_history: UndoHistory::default(),
+            row_shifts: vec![],
+        }
+    }
+
+    /// Add an empty row to a document without any (eg: an empty file), so that there
+    /// always is a row under the cursor.
+    pub fn ensure_has_a_row(&mut self) {
+        if self.rows.is_empty() {
+            self.rows.push(Row::default());
         }
     }
 
@@ -53,6 +155,26 @@ impl Document {
         Self {
             rows: vec![Row::from("")],
             filename: Some(filename),
+            file_format: FileFormat::default(),
+            binary: false,
+            invalid_utf8: false,
+            trailing_newline: true,
+            has_bom: false,
+            indentation: None,
+            symlink_target: None,
+            pending_swap_file: false,
+            undo_history: UndoHistory::default(),
+            row_shifts: vec![],
+        }
+    }
+
+    /// Create an empty document for a file which isn't valid UTF-8, to be reloaded in
+    /// binary mode (`:set binary`) before it can be edited and saved.
+    #[must_use]
+    pub fn new_invalid_utf8(filename: PathBuf) -> Self {
+        Self {
+            invalid_utf8: true,
+            ..Self::new_empty(filename)
         }
     }
 
@@ -69,19 +191,43 @@ impl Document {
         PathBuf::from(out)
     }
 
+    /// Return the path of the file the document is written to before being renamed over
+    /// its file (eg: `.notes.txt.tmp` for `notes.txt`)
+    fn temp_filename(filename: &Path) -> PathBuf {
+        let stripped_filename = filename.file_name().unwrap_or_default();
+        filename.with_file_name(format!(".{}.tmp", stripped_filename.to_string_lossy()))
+    }
+
     /// # Errors
     /// # Panics
     /// Returns an error if a file bearing the provided filename
-    /// cannot be open.
+    /// cannot be open, if it's a directory, or if it isn't valid UTF-8.
     pub fn open(filename: PathBuf) -> Result<Self, Error> {
+        if filename.is_dir() {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is a directory", filename.display()),
+            ));
+        }
         if !filename.is_file() {
-            return Ok(Self::new_empty(filename));
+            return Ok(Self::new_empty(filename).detect_pending_swap_file());
         }
-        let file_contents = if (&Self::swap_filename(&filename)).is_file() {
-            fs::read_to_string(Self::swap_filename(&filename))?
-        } else {
-            fs::read_to_string(&filename)?
-        };
+        // the swap file is only loaded on demand, with `recover_from_swap_file`
+        let file_contents = String::from_utf8(fs::read(&filename)?).map_err(|_| {
+            Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{} isn't valid UTF-8: :set binary to edit it verbatim",
+                    filename.display()
+                ),
+            )
+        })?;
+        let symlink_target = fs::symlink_metadata(&filename)
+            .ok()
+            .filter(|metadata| metadata.file_type().is_symlink())
+            .and_then(|_| fs::canonicalize(&filename).ok());
+        let has_bom = file_contents.starts_with(BOM);
+        let file_contents = file_contents.strip_prefix(BOM).unwrap_or(&file_contents);
 
         let mut rows = Vec::new();
         for line in file_contents.lines() {
@@ -90,42 +236,281 @@ impl Document {
         Ok(Self {
             rows,
             filename: Some(filename),
-        })
+            file_format: FileFormat::detect(file_contents),
+            binary: false,
+            invalid_utf8: false,
+            trailing_newline: true,
+            has_bom,
+            indentation: Indentation::detect(file_contents.lines()),
+            symlink_target,
+            pending_swap_file: false,
+            undo_history: UndoHistory::default(),
+            row_shifts: vec![],
+        }
+        .detect_pending_swap_file())
+    }
+
+    /// Look for a swap file to recover, which is then kept until it's recovered or discarded
+    fn detect_pending_swap_file(mut self) -> Self {
+        self.pending_swap_file = self.has_recoverable_swap_file();
+        self
+    }
+
+    /// Return whether the swap file found when opening the file is yet to be recovered
+    /// or discarded
+    #[must_use]
+    pub fn has_pending_swap_file(&self) -> bool {
+        self.pending_swap_file
     }
 
+    /// Write the document to its swap file, unless the swap file found when opening the
+    /// file is yet to be recovered or discarded.
+    ///
     /// # Errors
     /// # Panics
     /// Can return an error if the file can't be created or written to.
     pub fn save_to_swap_file(&self) -> Result<(), Error> {
-        if self.filename.is_some() {
+        if self.filename.is_some() && !self.pending_swap_file {
             let mut file = fs::File::create(Self::swap_filename(self.filename.as_ref().unwrap()))?;
-            for row in &self.rows {
-                file.write_all(row.as_bytes())?;
-                file.write_all(b"\n")?;
+            self.write_rows(&mut file)?;
+        }
+        Ok(())
+    }
+
+    /// Return the file the document file links to, if it's a symlink
+    #[must_use]
+    pub fn symlink_target(&self) -> Option<&Path> {
+        self.symlink_target.as_deref()
+    }
+
+    /// Save the document to a regular file replacing the symlink it was opened through,
+    /// its target being left untouched.
+    ///
+    /// # Errors
+    /// Returns an error if the file can't be written, or renamed over the symlink, in
+    /// which case the symlink is kept.
+    pub fn save_replacing_symlink(&mut self) -> Result<(), Error> {
+        self.write_file(true)?;
+        self.symlink_target = None;
+        Ok(())
+    }
+
+    /// Return whether the document file has a swap file (eg: left by a crashed session)
+    /// with a different content, which can be recovered.
+    #[must_use]
+    pub fn has_recoverable_swap_file(&self) -> bool {
+        let Some(filename) = self.filename.as_ref() else {
+            return false;
+        };
+        match fs::read(Self::swap_filename(filename)) {
+            Ok(swap_contents) => {
+                fs::read(filename).map_or(true, |contents| contents != swap_contents)
             }
+            Err(_) => false,
         }
+    }
+
+    /// Return whether the swap file was modified after the document file
+    #[must_use]
+    pub fn swap_file_is_newer(&self) -> bool {
+        let Some(filename) = self.filename.as_ref() else {
+            return false;
+        };
+        let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+        match (modified(&Self::swap_filename(filename)), modified(filename)) {
+            (Ok(swap_modified), Ok(file_modified)) => swap_modified > file_modified,
+            (Ok(_), Err(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Replace the rows by the content of the swap file, as a single edit
+    ///
+    /// # Errors
+    /// Returns an error if the document has no file name, or its swap file can't be read.
+    pub fn recover_from_swap_file(&mut self) -> Result<(), Error> {
+        let filename = self
+            .filename
+            .as_ref()
+            .ok_or_else(|| Error::new(std::io::ErrorKind::NotFound, "No file name"))?;
+        let swap_contents = fs::read_to_string(Self::swap_filename(filename))?;
+        let swap_contents = swap_contents.strip_prefix(BOM).unwrap_or(&swap_contents);
+        let lines: Vec<String> = swap_contents.lines().map(str::to_string).collect();
+        let lines = if lines.is_empty() {
+            vec![String::new()]
+        } else {
+            lines
+        };
+        self.replace_lines(0, self.line_count().saturating_sub(1), &lines);
+        // its content is now part of the document, written to the next swap file
+        self.pending_swap_file = false;
+        Ok(())
+    }
+
+    /// Delete the swap file of the document
+    ///
+    /// # Errors
+    /// Returns an error if the document has no file name, or its swap file can't be removed.
+    pub fn discard_swap_file(&mut self) -> Result<(), Error> {
+        let filename = self
+            .filename
+            .as_ref()
+            .ok_or_else(|| Error::new(std::io::ErrorKind::NotFound, "No file name"))?;
+        fs::remove_file(Self::swap_filename(filename))?;
+        self.pending_swap_file = false;
+        Ok(())
+    }
+
+    /// Re-read the document file in binary mode, in which the file content is kept
+    /// verbatim: `\r` characters are preserved, no trailing newline is added on save, and
+    /// the bytes of the invalid UTF-8 sequences are written back as they were.
+    ///
+    /// # Errors
+    /// Returns an error if the document file cannot be read, or if it contains both
+    /// invalid UTF-8 and the characters the invalid bytes are kept as.
+    pub fn reload_as_binary(&mut self) -> Result<(), Error> {
+        self.binary = true;
+        self.file_format = FileFormat::Unix;
+        // the byte order mark is kept verbatim in the first row
+        self.has_bom = false;
+        self.undo_history.clear();
+        let filename = match self.filename.as_ref() {
+            Some(filename) if filename.is_file() => filename,
+            _ => return Ok(()),
+        };
+        let (file_contents, invalid_utf8) = match String::from_utf8(fs::read(filename)?) {
+            Ok(file_contents) => (file_contents, false),
+            Err(error) => (decode_raw_bytes(error.as_bytes())?, true),
+        };
+        self.invalid_utf8 = invalid_utf8;
+        self.trailing_newline = file_contents.ends_with('\n');
+        self.rows = if file_contents.is_empty() {
+            vec![Row::default()]
+        } else {
+            file_contents
+                .strip_suffix('\n')
+                .unwrap_or(&file_contents)
+                .split('\n')
+                .map(Row::from)
+                .collect()
+        };
         Ok(())
     }
 
+    pub fn unset_binary(&mut self) {
+        self.binary = false;
+        self.trailing_newline = true;
+    }
+
+    #[must_use]
+    pub fn is_binary(&self) -> bool {
+        self.binary
+    }
+
+    /// Return the extension of the document file name, if any
+    #[must_use]
+    pub fn extension(&self) -> Option<&str> {
+        self.filename
+            .as_ref()
+            .and_then(|filename| filename.extension())
+            .and_then(|ext| ext.to_str())
+    }
+
+    /// Return the bytes of the document, as they would be written to its file: each row
+    /// is followed by the document line ending. In binary mode, the last line ending is
+    /// only written if the file had one, and the invalid UTF-8 bytes are written back. The
+    /// byte order mark is written back if the file started with one.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let line_ending = self.file_format.line_ending().as_bytes();
+        let mut bytes = vec![];
+        if self.has_bom {
+            bytes.extend_from_slice(BOM.as_bytes());
+        }
+        for (index, row) in self.rows.iter().enumerate() {
+            if self.invalid_utf8 {
+                encode_raw_bytes(&row.string, &mut bytes);
+            } else {
+                bytes.extend_from_slice(row.as_bytes());
+            }
+            if self.trailing_newline || index.saturating_add(1) < self.rows.len() {
+                bytes.extend_from_slice(line_ending);
+            }
+        }
+        bytes
+    }
+
+    /// Write the document bytes to the provided file
+    fn write_rows(&self, file: &mut fs::File) -> Result<(), Error> {
+        file.write_all(&self.to_bytes())
+    }
+
+    #[must_use]
+    pub fn file_format(&self) -> FileFormat {
+        self.file_format
+    }
+
+    #[must_use]
+    pub fn indentation(&self) -> Option<Indentation> {
+        self.indentation
+    }
+
+    /// Convert the document line endings to the provided file format
+    pub fn set_file_format(&mut self, file_format: FileFormat) {
+        self.file_format = file_format;
+    }
+
     pub fn trim_trailing_spaces(&mut self) {
+        let edit = self.start_edit(0, self.line_count(), 0);
         for row in self.iter_mut() {
             row.trim_end_inplace();
         }
+        self.finish_edit(edit);
     }
 
     /// # Errors
     /// # Panics
-    /// Can return an error if the file can't be created or written to.
+    /// Can return an error if the file can't be created or written to, or if it isn't
+    /// valid UTF-8 and the document isn't in binary mode.
     pub fn save(&self) -> Result<(), Error> {
+        self.write_file(false)
+    }
+
+    /// Write the document to its file, or to the target of the symlink it was opened
+    /// through, unless `replace_symlink` is set.
+    fn write_file(&self, replace_symlink: bool) -> Result<(), Error> {
         if self.filename.is_some() {
             let filename = &self.filename.as_ref().unwrap();
-            let mut file = fs::File::create(filename)?;
-
-            for row in &self.rows {
-                file.write_all(row.as_bytes())?;
-                file.write_all(b"\n")?;
+            if self.invalid_utf8 && !self.binary {
+                return Err(Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!(
+                        "{} isn't valid UTF-8: :set binary to save it verbatim",
+                        filename.display()
+                    ),
+                ));
+            }
+            match self.symlink_target.as_ref() {
+                Some(_) if replace_symlink => {
+                    // the new file is written next to the link before being renamed over
+                    // it, for the link to be kept if the write fails
+                    let temp_filename = Self::temp_filename(filename);
+                    let written = fs::File::create(&temp_filename)
+                        .and_then(|mut file| self.write_rows(&mut file))
+                        .and_then(|()| fs::rename(&temp_filename, filename));
+                    if written.is_err() && fs::remove_file(&temp_filename).is_ok() {
+                        // pass
+                    }
+                    written?;
+                }
+                // the target of a symlink is written to, keeping the link in place
+                target => {
+                    let mut file = fs::File::create(target.unwrap_or(filename))?;
+                    self.write_rows(&mut file)?;
+                }
             }
-            if fs::remove_file(Self::swap_filename(filename)).is_ok() {
+            // a swap file yet to be recovered or discarded is kept
+            if !self.pending_swap_file && fs::remove_file(Self::swap_filename(filename)).is_ok() {
                 // pass
             }
         }
@@ -150,16 +535,26 @@ impl Document {
 
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.rows.len() == 0
+        self.line_count() == 0
     }
 
+    /// Return the number of lines in the document.
+    ///
+    /// This is the single source of truth for the document length: every
+    /// line count read should go through it, whatever the rows storage is.
     #[must_use]
-    pub fn num_rows(&self) -> usize {
+    pub fn line_count(&self) -> usize {
         self.rows.len()
     }
 
+    #[must_use]
+    pub fn num_rows(&self) -> usize {
+        self.line_count()
+    }
+
     #[must_use]
     pub fn num_words(&self) -> usize {
+        // Each row caches its own word count, making this O(rows)
         self.iter().map(Row::num_words).sum()
     }
 
@@ -172,7 +567,7 @@ impl Document {
     /// Return the line number of the last line in the file
     #[must_use]
     pub fn last_line_number(&self) -> usize {
-        self.num_rows()
+        self.line_count()
     }
 
     #[must_use]
@@ -186,7 +581,8 @@ impl Document {
     }
 
     pub fn insert(&mut self, c: char, x: usize, y: usize) {
-        match y.cmp(&self.num_rows()) {
+        let edit = self.start_edit(y, 1, x);
+        match y.cmp(&self.line_count()) {
             Ordering::Equal | Ordering::Greater => {
                 let mut row = Row::default();
                 row.insert(0, c);
@@ -198,23 +594,110 @@ impl Document {
                 }
             }
         }
+        self.finish_edit(edit);
+    }
+
+    /// Replace the grapheme located at the provided position, and return it. The grapheme
+    /// is appended if the position is located after the end of the row.
+    pub fn replace_grapheme(&mut self, grapheme: &str, x: usize, y: usize) -> Option<String> {
+        let edit = self.start_edit(y, 1, x);
+        let replaced = self.rows.get_mut(y)?.replace(x, grapheme);
+        self.finish_edit(edit);
+        replaced
+    }
+
+    /// Insert a (possibly multiline) string at the provided position, and return the
+    /// position located right after the last inserted character.
+    pub fn insert_string(&mut self, s: &str, x: usize, y: usize) -> Position {
+        let edit = self.start_edit(y, 1, x);
+        if y >= self.line_count() {
+            self.rows.push(Row::default());
+        }
+        let y = cmp::min(y, self.line_count().saturating_sub(1));
+        let mut lines = s.split('\n').map(|line| line.trim_end_matches('\r'));
+        let first_line = Row::from(lines.next().unwrap_or_default());
+        let mut end = Position {
+            x: x.saturating_add(first_line.len()),
+            y,
+        };
+        let tail = match self.rows.get_mut(y) {
+            Some(row) => {
+                let tail = row.split(x);
+                row.append(&first_line);
+                tail
+            }
+            None => return end,
+        };
+        for line in lines {
+            let row = Row::from(line);
+            end = Position {
+                x: row.len(),
+                y: end.y.saturating_add(1),
+            };
+            self.rows.insert(end.y, row);
+        }
+        if let Some(row) = self.rows.get_mut(end.y) {
+            row.append(&tail);
+        }
+        self.finish_edit(edit);
+        end
     }
 
     pub fn delete(&mut self, x: usize, from_x: usize, y: usize) {
-        if y >= self.num_rows() {
+        if y >= self.line_count() {
             return;
         }
+        // Deletion at the very start of a line means we append the current line to the previous one
+        if x == 0 && from_x == 0 && y > 0 {
+            self.join_row_with_previous_one(x, y, None);
+            return;
+        }
+        let edit = self.start_edit(y, 1, x);
         if let Some(row) = self.rows.get_mut(y) {
-            // Deletion at the very start of a line means we append the current line to the previous one
-            if x == 0 && from_x == 0 && y > 0 {
-                self.join_row_with_previous_one(x, y, None);
-            } else {
-                row.delete(x);
-            }
+            row.delete(x);
+        }
+        self.finish_edit(edit);
+    }
+
+    /// Delete the text located between the start (inclusive) and end (exclusive)
+    /// positions, possibly spanning over multiple rows that get joined together.
+    pub fn delete_range(&mut self, start: &Position, end: &Position) {
+        if (start.y, start.x) >= (end.y, end.x) || end.y >= self.line_count() {
+            return;
+        }
+        let edit = self.start_edit(
+            start.y,
+            end.y.saturating_sub(start.y).saturating_add(1),
+            start.x,
+        );
+        let tail = self.rows[end.y].split(end.x);
+        let _ = self.rows[start.y].split(start.x);
+        self.rows[start.y].append(&tail);
+        self.rows.drain(start.y.saturating_add(1)..=end.y);
+        self.finish_edit(edit);
+    }
+
+    /// Return the text located between the start (inclusive) and end (exclusive)
+    /// positions, rows being separated by a newline.
+    #[must_use]
+    pub fn text_in_range(&self, start: &Position, end: &Position) -> String {
+        let mut lines = vec![];
+        for y in start.y..=cmp::min(end.y, self.line_count().saturating_sub(1)) {
+            let row = &self.rows[y];
+            let from = if y == start.y { start.x } else { 0 };
+            let to = if y == end.y { end.x } else { row.len() };
+            lines.push(
+                row.graphemes()
+                    .skip(from)
+                    .take(to.saturating_sub(from))
+                    .collect::<String>(),
+            );
         }
+        lines.join("\n")
     }
 
     pub fn join_row_with_previous_one(&mut self, x: usize, y: usize, join_with: Option<char>) {
+        let edit = self.start_edit(y.saturating_sub(1), 2, x);
         let current_row = self.rows.remove(y);
         if let Some(previous_row) = self.rows.get_mut(y - 1) {
             if let Some(join_char) = join_with {
@@ -222,12 +705,43 @@ impl Document {
             }
             previous_row.append(&current_row);
         }
+        self.finish_edit(edit);
+    }
+
+    /// Join the `num_rows` rows located at the `y` index into a single one, as a single
+    /// edit. The leading whitespace of the joined rows is removed, and they're separated by
+    /// a single space, unless the previous row is empty or ends with whitespace, or the
+    /// joined row is blank. Return the index of the grapheme at the last join boundary,
+    /// or `None` if there's no row to join.
+    pub fn join_rows(&mut self, y: usize, num_rows: usize) -> Option<usize> {
+        let end = cmp::min(y.saturating_add(num_rows), self.line_count());
+        if end <= y.saturating_add(1) {
+            return None;
+        }
+        let mut joined = Row::from(self.rows[y].string.as_str());
+        let mut boundary = joined.len();
+        for row in &self.rows[y.saturating_add(1)..end] {
+            let next = Row::from(row.string.trim_start());
+            boundary = joined.len();
+            if !next.is_empty()
+                && !joined.string.is_empty()
+                && !joined.string.ends_with(char::is_whitespace)
+            {
+                joined.insert(joined.len(), ' ');
+            }
+            joined.append(&next);
+        }
+        let edit = self.start_edit(y, end.saturating_sub(y), boundary);
+        self.replace_rows(y, end.saturating_sub(y), &[joined.string]);
+        self.finish_edit(edit);
+        Some(boundary)
     }
 
     pub fn insert_newline(&mut self, x: usize, y: usize) {
-        if y > self.num_rows() {
+        if y > self.line_count() {
             return;
         }
+        let edit = self.start_edit(y, 1, x);
         let current_row = self.rows.get_mut(y);
         if let Some(current_row) = current_row {
             if x < current_row.len().saturating_sub(1) {
@@ -236,24 +750,217 @@ impl Document {
                 // newline inserted in the middle of the row
             } else {
                 let new_row = Row::default();
-                if y == self.num_rows() || y.saturating_add(1) == self.num_rows() {
+                if y == self.line_count() || y.saturating_add(1) == self.line_count() {
                     self.rows.push(new_row);
                 } else {
                     self.rows.insert(y.saturating_add(1), new_row);
                 }
             }
         }
+        self.finish_edit(edit);
+    }
+
+    /// Swap the rows located at the provided indices, in a single edit
+    pub fn swap_rows(&mut self, a: usize, b: usize) {
+        if a == b || cmp::max(a, b) >= self.line_count() {
+            return;
+        }
+        let first = cmp::min(a, b);
+        let num_rows = cmp::max(a, b).saturating_sub(first).saturating_add(1);
+        let edit = self.start_edit(first, num_rows, 0);
+        self.rows.swap(a, b);
+        self.finish_edit(edit);
     }
 
     pub fn delete_row(&mut self, y: usize) {
-        if y > self.num_rows() {
-        } else if self.num_rows() == 1 {
+        let edit = self.start_edit(y, 1, 0);
+        if y > self.line_count() {
+        } else if self.line_count() == 1 {
             if let Some(row) = self.rows.get_mut(0) {
-                row.string = "".to_string();
+                row.set_string("");
             }
         } else if self.rows.get(y).is_some() {
             self.rows.remove(y);
         }
+        self.finish_edit(edit);
+    }
+
+    /// Replace the first match of the regex in the given row (or every one if `all`
+    /// is true) by the replacement text, and return the number of replacements made.
+    pub fn replace_in_row(&mut self, y: usize, from: &Regex, to: &str, all: bool) -> usize {
+        let Some(row) = self.rows.get(y) else {
+            return 0;
+        };
+        let limit = usize::from(!all);
+        let num_matches = from.find_iter(&row.string).count();
+        let num_replacements = if all {
+            num_matches
+        } else {
+            cmp::min(num_matches, 1)
+        };
+        if num_replacements == 0 {
+            return 0;
+        }
+        let replaced = from.replacen(&row.string, limit, NoExpand(to)).to_string();
+        let edit = self.start_edit(y, 1, 0);
+        if let Some(row) = self.rows.get_mut(y) {
+            row.set_string(&replaced);
+        }
+        self.finish_edit(edit);
+        num_replacements
+    }
+
+    /// Replace the matches of the regex in the rows located between the `start` and
+    /// `end` indices (inclusive), and return the total number of replacements made.
+    pub fn replace_in_rows(
+        &mut self,
+        start: usize,
+        end: usize,
+        from: &Regex,
+        to: &str,
+        all: bool,
+    ) -> usize {
+        (start..=end)
+            .map(|y| self.replace_in_row(y, from, to, all))
+            .sum()
+    }
+
+    /// Replace the rows located between the `start` and `end` indices (inclusive) by
+    /// the provided lines, as a single edit. The document keeps at least one row.
+    pub fn replace_lines(&mut self, start: usize, end: usize, lines: &[String]) {
+        if start > end || end >= self.line_count() {
+            return;
+        }
+        let edit = self.start_edit(start, end.saturating_sub(start).saturating_add(1), 0);
+        self.replace_rows(start, end.saturating_sub(start).saturating_add(1), lines);
+        self.ensure_has_a_row();
+        self.finish_edit(edit);
+    }
+
+    /// Return the number of edits made to the document, which changes whenever it's edited
+    #[must_use]
+    pub fn edit_count(&self) -> usize {
+        self.undo_history.edit_count()
+    }
+
+    /// Group all the edits made until `end_undo_group` is called into a single undo unit
+    pub fn start_undo_group(&mut self) {
+        self.undo_history.start_group();
+    }
+
+    pub fn end_undo_group(&mut self) {
+        self.undo_history.end_group();
+    }
+
+    /// Undo the last group of edits, and return the position at which they were made
+    pub fn undo(&mut self) -> Option<Position> {
+        let group = self.undo_history.undo()?;
+        for op in group.iter().rev() {
+            self.replace_rows(op.y, op.after.len(), &op.before);
+        }
+        group.first().map(|op| op.position)
+    }
+
+    /// Redo the last undone group of edits, and return the position at which they were made
+    pub fn redo(&mut self) -> Option<Position> {
+        let group = self.undo_history.redo()?;
+        for op in &group {
+            self.replace_rows(op.y, op.before.len(), &op.after);
+        }
+        group.first().map(|op| op.position)
+    }
+
+    /// Return the number of the current undo state, 0 being the document as opened
+    #[must_use]
+    pub fn undo_state(&self) -> usize {
+        self.undo_history.current_seq()
+    }
+
+    /// Undo or redo edits, going through the branches of the undo tree, until reaching
+    /// the undo state with the provided number. Return the position of the last edit
+    /// undone or redone, or `None` if there's no such state.
+    pub fn goto_undo_state(&mut self, seq: usize) -> Option<Position> {
+        if !self.undo_history.has_state(seq) {
+            return None;
+        }
+        let mut position = None;
+        while let Some(step) = self.undo_history.next_step_towards(seq) {
+            let step_position = match step {
+                UndoStep::Undo => self.undo(),
+                UndoStep::Redo => self.redo(),
+            };
+            position = step_position.or(position);
+        }
+        position
+    }
+
+    /// Return the number of the undo state to travel to with `:earlier` or `:later`
+    #[must_use]
+    pub fn undo_state_at(&self, distance: UndoDistance, earlier: bool) -> usize {
+        match distance {
+            UndoDistance::Steps(steps) => self.undo_history.seq_at_step_offset(steps, earlier),
+            UndoDistance::Time(duration) => self.undo_history.seq_at_time_offset(duration, earlier),
+        }
+    }
+
+    /// Return the rows added or removed since the last call, oldest first
+    pub fn take_row_shifts(&mut self) -> Vec<RowShift> {
+        std::mem::take(&mut self.row_shifts)
+    }
+
+    fn record_row_shift(&mut self, y: usize, num_rows_before: usize, num_rows_after: usize) {
+        if num_rows_before != num_rows_after {
+            self.row_shifts.push(RowShift {
+                y,
+                num_rows_before,
+                num_rows_after,
+            });
+        }
+    }
+
+    /// Replace the `num_rows` rows located at the `y` index by the provided lines
+    fn replace_rows(&mut self, y: usize, num_rows: usize, lines: &[String]) {
+        let y = cmp::min(y, self.line_count());
+        let end = cmp::min(y.saturating_add(num_rows), self.line_count());
+        self.record_row_shift(y, end.saturating_sub(y), lines.len());
+        self.rows
+            .splice(y..end, lines.iter().map(|line| Row::from(line.as_str())));
+    }
+
+    /// Take a snapshot of the `num_rows` rows located at the `y` index, before editing them
+    fn start_edit(&self, y: usize, num_rows: usize, x: usize) -> PendingEdit {
+        let y = cmp::min(y, self.line_count());
+        let end = cmp::min(y.saturating_add(num_rows), self.line_count());
+        PendingEdit {
+            y,
+            before: self.rows[y..end]
+                .iter()
+                .map(|row| row.string.clone())
+                .collect(),
+            line_count: self.line_count(),
+            position: Position { x, y },
+        }
+    }
+
+    /// Record the edit made since the snapshot was taken, the edited rows having
+    /// possibly been split or joined together.
+    fn finish_edit(&mut self, edit: PendingEdit) {
+        let num_rows_after =
+            (edit.before.len() + self.line_count()).saturating_sub(edit.line_count);
+        let end = cmp::min(edit.y.saturating_add(num_rows_after), self.line_count());
+        let after: Vec<String> = self.rows[edit.y..end]
+            .iter()
+            .map(|row| row.string.clone())
+            .collect();
+        self.record_row_shift(edit.y, edit.before.len(), after.len());
+        if after != edit.before {
+            self.undo_history.record(EditOp {
+                y: edit.y,
+                before: edit.before,
+                after,
+                position: edit.position,
+            });
+        }
     }
 
     #[must_use]
@@ -264,6 +971,59 @@ impl Document {
     }
 }
 
+/// Return whether the character stands for a byte of an invalid UTF-8 sequence
+fn is_raw_byte(c: char) -> bool {
+    (RAW_BYTES_OFFSET + 0x80..=RAW_BYTES_OFFSET + 0xFF).contains(&u32::from(c))
+}
+
+/// Decode the content of a file which isn't valid UTF-8, each byte of its invalid
+/// sequences being kept as a private use character (see `RAW_BYTES_OFFSET`).
+///
+/// # Errors
+/// Returns an error if the valid parts of the content already contain such characters,
+/// as they couldn't be told apart from the invalid bytes on save.
+fn decode_raw_bytes(mut bytes: &[u8]) -> Result<String, Error> {
+    let mut decoded = String::with_capacity(bytes.len());
+    loop {
+        let (valid, invalid) = match str::from_utf8(bytes) {
+            Ok(valid) => (valid, &[][..]),
+            Err(error) => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                let invalid_len = error.error_len().unwrap_or(rest.len());
+                let (invalid, rest) = rest.split_at(invalid_len);
+                bytes = rest;
+                (str::from_utf8(valid).unwrap_or_default(), invalid)
+            }
+        };
+        if valid.chars().any(is_raw_byte) {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The file mixes invalid UTF-8 with U+F780 to U+F7FF characters",
+            ));
+        }
+        decoded.push_str(valid);
+        if invalid.is_empty() {
+            return Ok(decoded);
+        }
+        for &byte in invalid {
+            decoded.extend(char::from_u32(RAW_BYTES_OFFSET + u32::from(byte)));
+        }
+    }
+}
+
+/// Append the UTF-8 bytes of the string to the provided ones, the characters standing
+/// for the bytes of invalid UTF-8 sequences being converted back to them
+fn encode_raw_bytes(s: &str, bytes: &mut Vec<u8>) {
+    let mut buffer = [0; 4];
+    for c in s.chars() {
+        if is_raw_byte(c) {
+            bytes.push(u8::try_from(u32::from(c) - RAW_BYTES_OFFSET).unwrap_or_default());
+        } else {
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+        }
+    }
+}
+
 #[cfg(test)]
 #[path = "./document_test.rs"]
 mod document_test;
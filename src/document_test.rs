@@ -1,5 +1,10 @@
-use crate::{Document, Row};
+use crate::{Document, FileFormat, Position, Row, RowShift};
+use regex::Regex;
+use std::fs;
+use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tempfile::{tempdir, NamedTempFile};
 
 #[test]
 fn test_document_get_row() {
@@ -30,6 +35,42 @@ fn test_document_num_rows() {
     );
 }
 
+#[test]
+fn test_document_ensure_has_a_row() {
+    let mut doc = Document::new(vec![], PathBuf::from("test.rs"));
+    doc.ensure_has_a_row();
+    assert_eq!(doc.line_count(), 1);
+    assert!(doc.get_row(0).unwrap().is_empty());
+    doc.ensure_has_a_row();
+    assert_eq!(doc.line_count(), 1);
+}
+
+#[test]
+fn test_document_line_count_after_each_mutation() {
+    let mut doc = Document::new(vec![], PathBuf::from("test.rs"));
+    assert_eq!(doc.line_count(), 0);
+    doc.insert('H', 0, 0); // insertion past the last line creates a new one
+    assert_eq!(doc.line_count(), 1);
+    doc.insert('i', 1, 0);
+    assert_eq!(doc.line_count(), 1);
+    doc.insert_newline(2, 0);
+    assert_eq!(doc.line_count(), 2);
+    doc.insert_string("a\nb\nc", 0, 1);
+    assert_eq!(doc.line_count(), 4);
+    doc.delete(0, 0, 3); // deleting at the start of a line joins it with the previous one
+    assert_eq!(doc.line_count(), 3);
+    doc.delete(0, 1, 2); // mid-line deletion
+    assert_eq!(doc.line_count(), 3);
+    doc.join_row_with_previous_one(0, 2, Some(' '));
+    assert_eq!(doc.line_count(), 2);
+    doc.delete_row(1);
+    assert_eq!(doc.line_count(), 1);
+    doc.delete_row(0); // the last line is emptied, not removed
+    assert_eq!(doc.line_count(), 1);
+    assert_eq!(doc.line_count(), doc.num_rows());
+    assert_eq!(doc.line_count(), doc.last_line_number());
+}
+
 #[test]
 fn test_document_num_words() {
     assert_eq!(
@@ -42,6 +83,50 @@ fn test_document_num_words() {
     );
 }
 
+#[test]
+fn test_document_num_words_with_whitespace_and_punctuation() {
+    let num_words = |lines: &[&str]| {
+        Document::new(
+            lines.iter().map(|line| Row::from(*line)).collect(),
+            PathBuf::from("test.rs"),
+        )
+        .num_words()
+    };
+    assert_eq!(num_words(&["hello   world"]), 2);
+    assert_eq!(num_words(&["  hello world  "]), 2);
+    assert_eq!(num_words(&["\thello\t\tworld\t"]), 2);
+    assert_eq!(num_words(&["", "   ", "\t"]), 0);
+    assert_eq!(num_words(&["a well-known fact, isn't it?"]), 5);
+    assert_eq!(num_words(&["hello - world ..."]), 2);
+}
+
+#[test]
+fn test_document_num_words_is_updated_on_edit() {
+    let mut doc = Document::new(
+        vec![Row::from("Hello world"), Row::from("dear reviewer!")],
+        PathBuf::from("test.rs"),
+    );
+    let recomputed_num_words = |doc: &Document| -> usize {
+        doc.iter()
+            .map(|row| Row::from(row.string.as_str()).num_words())
+            .sum()
+    };
+    doc.insert(' ', 2, 0); // "He llo world"
+    assert_eq!(doc.num_words(), 5);
+    doc.delete(6, 6, 0); // "He lloworld"
+    assert_eq!(doc.num_words(), 4);
+    doc.insert_newline(2, 0); // "He" / " lloworld"
+    assert_eq!(doc.num_words(), 4);
+    doc.join_row_with_previous_one(2, 1, None); // "He lloworld"
+    assert_eq!(doc.num_words(), 4);
+    doc.insert(' ', 6, 0); // "He llo world"
+    doc.join_row_with_previous_one(11, 1, Some(' ')); // "He llo world dear reviewer!"
+    assert_eq!(doc.num_words(), 5);
+    assert_eq!(doc.num_words(), recomputed_num_words(&doc));
+    doc.delete_row(0);
+    assert_eq!(doc.num_words(), 0);
+}
+
 #[test]
 fn test_document_row_for_line_number() {
     let row1 = Row::from("Hello world");
@@ -82,6 +167,25 @@ fn test_document_insert() {
     assert_eq!(doc.rows.get(2).unwrap().string, "W");
 }
 
+#[test]
+fn test_document_insert_string() {
+    let mut doc = Document::new(
+        vec![Row::from("Hello"), Row::from("world!")],
+        PathBuf::from("test.rs"),
+    );
+    assert_eq!(doc.insert_string(" dear", 5, 0), Position { x: 10, y: 0 });
+    assert_eq!(doc.get_row(0).unwrap().string, "Hello dear");
+    assert_eq!(doc.num_rows(), 2);
+
+    assert_eq!(
+        doc.insert_string("big\r\nwide ", 0, 1),
+        Position { x: 5, y: 2 }
+    );
+    assert_eq!(doc.rows.get(1).unwrap().string, "big");
+    assert_eq!(doc.rows.get(2).unwrap().string, "wide world!");
+    assert_eq!(doc.num_rows(), 3);
+}
+
 #[test]
 fn test_document_insert_newline_at_the_end() {
     let mut doc = Document::new(
@@ -186,3 +290,356 @@ fn test_document_join_row_with_previous_one() {
     assert_eq!(doc.rows.get(0).unwrap().string, "Hello world!");
     assert_eq!(doc.num_rows(), 1);
 }
+
+#[test]
+fn test_document_join_rows() {
+    let mut doc = Document::new(
+        vec![
+            Row::from("fn main() {"),
+            Row::from("    let a = 1;"),
+            Row::from(""),
+            Row::from("trailing "),
+            Row::from("\tspace"),
+        ],
+        PathBuf::from("test.rs"),
+    );
+    assert_eq!(doc.join_rows(0, 3), Some(22));
+    assert_eq!(doc.rows[0].string, "fn main() { let a = 1;");
+    // no space is added before the content of a line ending with whitespace
+    assert_eq!(doc.join_rows(1, 2), Some(9));
+    assert_eq!(doc.rows[1].string, "trailing space");
+    assert_eq!(doc.num_rows(), 2);
+    // nothing to join past the last line
+    assert_eq!(doc.join_rows(1, 2), None);
+
+    // the joins are undone at once
+    doc.undo();
+    assert_eq!(doc.num_rows(), 3);
+    assert_eq!(doc.rows[2].string, "\tspace");
+}
+
+#[test]
+fn test_document_file_format_round_trip() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"Hello\r\nworld!\r\n").unwrap();
+    let mut doc = Document::open(file.path().to_path_buf()).unwrap();
+    assert_eq!(doc.file_format(), FileFormat::Dos);
+    assert_eq!(doc.get_row(0).unwrap().string, "Hello");
+
+    let dos_hash = doc.hashed();
+    doc.set_file_format(FileFormat::Unix);
+    assert_ne!(doc.hashed(), dos_hash); // the conversion makes the document dirty
+    doc.save().unwrap();
+    assert_eq!(fs::read(file.path()).unwrap(), b"Hello\nworld!\n");
+
+    let mut doc = Document::open(file.path().to_path_buf()).unwrap();
+    assert_eq!(doc.file_format(), FileFormat::Unix);
+    doc.set_file_format(FileFormat::Dos);
+    doc.save().unwrap();
+    assert_eq!(fs::read(file.path()).unwrap(), b"Hello\r\nworld!\r\n");
+}
+
+#[test]
+fn test_document_bom_round_trip() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"\xef\xbb\xbfHello world\n").unwrap();
+    let doc = Document::open(file.path().to_path_buf()).unwrap();
+    assert_eq!(doc.get_row(0).unwrap().string, "Hello world");
+    assert_eq!(doc.get_row(0).unwrap().len(), 11);
+    assert_eq!(doc.num_words(), 2);
+    doc.save().unwrap();
+    assert_eq!(fs::read(file.path()).unwrap(), b"\xef\xbb\xbfHello world\n");
+
+    // no byte order mark is added to the files without one
+    let file = NamedTempFile::new().unwrap();
+    fs::write(file.path(), "Hello world\n").unwrap();
+    let doc = Document::open(file.path().to_path_buf()).unwrap();
+    doc.save().unwrap();
+    assert_eq!(fs::read(file.path()).unwrap(), b"Hello world\n");
+}
+
+#[test]
+fn test_document_binary_round_trip() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(b"dos\r\nunix  \n\ttab\r\nno newline")
+        .unwrap();
+    let mut doc = Document::open(file.path().to_path_buf()).unwrap();
+    doc.reload_as_binary().unwrap();
+    assert!(doc.is_binary());
+    assert_eq!(doc.line_count(), 4);
+    assert_eq!(doc.get_row(0).unwrap().string, "dos\r");
+    doc.save().unwrap();
+    assert_eq!(
+        fs::read(file.path()).unwrap(),
+        b"dos\r\nunix  \n\ttab\r\nno newline"
+    );
+
+    file.write_all(b"\n").unwrap();
+    doc.reload_as_binary().unwrap();
+    assert_eq!(doc.line_count(), 4);
+    doc.save().unwrap();
+    assert_eq!(
+        fs::read(file.path()).unwrap(),
+        b"dos\r\nunix  \n\ttab\r\nno newline\n"
+    );
+}
+
+#[test]
+fn test_document_reload_as_binary_invalid_utf8() {
+    let mut file = NamedTempFile::new().unwrap();
+    let contents = b"\x7fELF\xff\xfe\x00\nH\xc3\xa9llo \xe2\x82\n\xc3";
+    file.write_all(contents).unwrap();
+    let error = Document::open(file.path().to_path_buf()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+    let mut doc = Document::new_invalid_utf8(file.path().to_path_buf());
+    assert!(doc.save().is_err());
+    assert_eq!(fs::read(file.path()).unwrap(), contents);
+    doc.reload_as_binary().unwrap();
+    assert_eq!(doc.line_count(), 3);
+    // each invalid byte is kept as a private use character
+    assert_eq!(doc.get_row(0).unwrap().string, "\x7fELF\u{f7ff}\u{f7fe}\0");
+    assert_eq!(
+        doc.get_row(1).unwrap().string,
+        "H\u{e9}llo \u{f7e2}\u{f782}"
+    );
+    assert_eq!(doc.get_row(2).unwrap().string, "\u{f7c3}");
+    doc.save().unwrap();
+    assert_eq!(fs::read(file.path()).unwrap(), contents);
+
+    // a U+F7FF character (written as EF 9F BF) couldn't be told apart from a FF byte
+    fs::write(file.path(), b"\xef\x9f\xbf\xff").unwrap();
+    assert!(doc.reload_as_binary().is_err());
+}
+
+#[test]
+fn test_document_delete_range() {
+    let mut doc = Document::new(
+        vec![Row::from("Hello"), Row::from("dear"), Row::from("world!")],
+        PathBuf::from("test.rs"),
+    );
+    doc.delete_range(&Position { x: 1, y: 0 }, &Position { x: 3, y: 0 });
+    assert_eq!(doc.get_row(0).unwrap().string, "Hlo");
+    doc.delete_range(&Position { x: 2, y: 0 }, &Position { x: 2, y: 2 });
+    assert_eq!(doc.get_row(0).unwrap().string, "Hlrld!");
+    assert_eq!(doc.line_count(), 1);
+    // the start position must be located before the end one
+    doc.delete_range(&Position { x: 3, y: 0 }, &Position { x: 1, y: 0 });
+    assert_eq!(doc.get_row(0).unwrap().string, "Hlrld!");
+}
+
+#[test]
+fn test_document_text_in_range() {
+    let doc = Document::new(
+        vec![Row::from("Hello"), Row::from("dear"), Row::from("world!")],
+        PathBuf::from("test.rs"),
+    );
+    assert_eq!(
+        doc.text_in_range(&Position { x: 1, y: 0 }, &Position { x: 3, y: 0 }),
+        "el"
+    );
+    assert_eq!(
+        doc.text_in_range(&Position { x: 3, y: 0 }, &Position { x: 2, y: 2 }),
+        "lo\ndear\nwo"
+    );
+}
+
+#[test]
+fn test_document_undo_redo() {
+    let mut doc = Document::new(
+        vec![Row::from("Hello"), Row::from("world!")],
+        PathBuf::from("test.rs"),
+    );
+    let rows = |doc: &Document| doc.iter().map(|row| row.string.clone()).collect::<Vec<_>>();
+    doc.insert('!', 5, 0);
+    doc.insert_newline(2, 0);
+    doc.delete_row(2);
+    assert_eq!(rows(&doc), vec!["He", "llo!"]);
+
+    assert_eq!(doc.undo(), Some(Position { x: 0, y: 2 }));
+    assert_eq!(rows(&doc), vec!["He", "llo!", "world!"]);
+    assert_eq!(doc.undo(), Some(Position { x: 2, y: 0 }));
+    assert_eq!(rows(&doc), vec!["Hello!", "world!"]);
+    assert_eq!(doc.undo(), Some(Position { x: 5, y: 0 }));
+    assert_eq!(rows(&doc), vec!["Hello", "world!"]);
+    assert_eq!(doc.undo(), None);
+
+    assert_eq!(doc.redo(), Some(Position { x: 5, y: 0 }));
+    assert_eq!(doc.redo(), Some(Position { x: 2, y: 0 }));
+    assert_eq!(rows(&doc), vec!["He", "llo!", "world!"]);
+
+    // a new edit discards the undone ones
+    doc.join_row_with_previous_one(1, 1, None);
+    assert_eq!(rows(&doc), vec!["Hello!", "world!"]);
+    assert_eq!(doc.redo(), None);
+    doc.undo();
+    assert_eq!(rows(&doc), vec!["He", "llo!", "world!"]);
+}
+
+#[test]
+fn test_document_undo_group() {
+    let mut doc = Document::new(vec![Row::from("Hello")], PathBuf::from("test.rs"));
+    doc.start_undo_group();
+    doc.insert(' ', 5, 0);
+    doc.insert('y', 6, 0);
+    doc.insert_newline(7, 0);
+    doc.insert('o', 0, 1);
+    doc.end_undo_group();
+    assert_eq!(doc.line_count(), 2);
+    assert_eq!(doc.undo(), Some(Position { x: 5, y: 0 }));
+    assert_eq!(doc.line_count(), 1);
+    assert_eq!(doc.get_row(0).unwrap().string, "Hello");
+    doc.redo();
+    assert_eq!(doc.get_row(0).unwrap().string, "Hello y");
+    assert_eq!(doc.get_row(1).unwrap().string, "o");
+}
+
+#[test]
+fn test_document_replace_in_row() {
+    let mut doc = Document::new(
+        vec![Row::from("foo bar foo"), Row::from("foo")],
+        PathBuf::from("test.rs"),
+    );
+    let regex = Regex::new("foo").unwrap();
+    assert_eq!(doc.replace_in_row(0, &regex, "baz", false), 1);
+    assert_eq!(doc.get_row(0).unwrap().string, "baz bar foo");
+    assert_eq!(doc.replace_in_row(0, &regex, "$1", true), 1);
+    assert_eq!(doc.get_row(0).unwrap().string, "baz bar $1");
+    assert_eq!(doc.replace_in_row(0, &regex, "baz", true), 0);
+    assert_eq!(doc.replace_in_row(5, &regex, "baz", true), 0);
+    assert_eq!(doc.get_row(1).unwrap().string, "foo");
+}
+
+#[test]
+fn test_document_replace_in_rows() {
+    let mut doc = Document::new(
+        vec![Row::from("a-a"), Row::from("b"), Row::from("a")],
+        PathBuf::from("test.rs"),
+    );
+    let rows = |doc: &Document| doc.iter().map(|row| row.string.clone()).collect::<Vec<_>>();
+    let regex = Regex::new("a").unwrap();
+    assert_eq!(doc.replace_in_rows(0, 2, &regex, "c", false), 2);
+    assert_eq!(rows(&doc), vec!["c-a", "b", "c"]);
+    assert_eq!(doc.replace_in_rows(0, 2, &regex, "c", true), 1);
+    assert_eq!(rows(&doc), vec!["c-c", "b", "c"]);
+    assert_eq!(doc.undo(), Some(Position { x: 0, y: 0 }));
+    assert_eq!(rows(&doc), vec!["c-a", "b", "c"]);
+}
+
+#[test]
+fn test_document_row_shifts() {
+    let mut doc = Document::new(
+        vec![Row::from("a"), Row::from("b"), Row::from("c")],
+        PathBuf::from("test.txt"),
+    );
+    doc.insert_newline(1, 0);
+    doc.insert('x', 0, 0);
+    let shifts = doc.take_row_shifts();
+    assert_eq!(
+        shifts,
+        vec![RowShift {
+            y: 0,
+            num_rows_before: 1,
+            num_rows_after: 2
+        }]
+    );
+    assert!(doc.take_row_shifts().is_empty());
+    assert_eq!(shifts[0].shifted_row_index(0), 0);
+    assert_eq!(shifts[0].shifted_row_index(2), 3);
+
+    let deletion = RowShift {
+        y: 2,
+        num_rows_before: 3,
+        num_rows_after: 0,
+    };
+    assert_eq!(deletion.shifted_row_index(1), 1);
+    assert_eq!(deletion.shifted_row_index(3), 2);
+    assert_eq!(deletion.shifted_row_index(6), 3);
+}
+
+#[test]
+fn test_document_swap_rows() {
+    let mut doc = Document::new(
+        vec![Row::from("a"), Row::from("b"), Row::from("c")],
+        PathBuf::from("test.txt"),
+    );
+    doc.swap_rows(2, 0);
+    assert_eq!(doc.get_row(0).unwrap().string, "c");
+    assert_eq!(doc.get_row(2).unwrap().string, "a");
+    // out of bounds indices are ignored
+    doc.swap_rows(1, 3);
+    assert_eq!(doc.get_row(1).unwrap().string, "b");
+    doc.undo();
+    assert_eq!(doc.get_row(0).unwrap().string, "a");
+    assert_eq!(doc.get_row(2).unwrap().string, "c");
+}
+
+#[test]
+fn test_document_recover_from_swap_file() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("a.txt");
+    fs::write(&path, "saved\n").unwrap();
+    let doc = Document::open(path.clone()).unwrap();
+    assert!(!doc.has_recoverable_swap_file());
+
+    // a swap file identical to the file has nothing to recover
+    fs::write(Document::swap_filename(&path), "saved\n").unwrap();
+    assert!(!doc.has_recoverable_swap_file());
+
+    fs::write(Document::swap_filename(&path), "unsaved\nchanges\n").unwrap();
+    let mut doc = Document::open(path.clone()).unwrap();
+    // the file is opened rather than its swap file
+    assert_eq!(doc.get_row(0).unwrap().string, "saved");
+    assert!(doc.has_recoverable_swap_file());
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::UNIX_EPOCH)
+        .unwrap();
+    assert!(doc.swap_file_is_newer());
+
+    doc.recover_from_swap_file().unwrap();
+    assert_eq!(doc.line_count(), 2);
+    assert_eq!(doc.get_row(1).unwrap().string, "changes");
+    doc.undo();
+    assert_eq!(doc.get_row(0).unwrap().string, "saved");
+
+    doc.discard_swap_file().unwrap();
+    assert!(!Document::swap_filename(&path).exists());
+    assert!(!doc.has_recoverable_swap_file());
+    assert!(doc.discard_swap_file().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_document_save_through_symlink() {
+    let dir = tempdir().unwrap();
+    let target = dir.path().join("target.txt");
+    let link = dir.path().join("link.txt");
+    fs::write(&target, "Hello\n").unwrap();
+    std::os::unix::fs::symlink(&target, &link).unwrap();
+
+    let mut doc = Document::open(link.clone()).unwrap();
+    assert_eq!(
+        doc.symlink_target(),
+        Some(fs::canonicalize(&target).unwrap().as_path())
+    );
+    doc.insert_string(" world", 5, 0);
+    doc.save().unwrap();
+    // the link is preserved, and its target updated
+    assert!(fs::symlink_metadata(&link)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello world\n");
+
+    doc.insert_string("!", 11, 0);
+    doc.save_replacing_symlink().unwrap();
+    assert_eq!(doc.symlink_target(), None);
+    assert!(fs::symlink_metadata(&link).unwrap().file_type().is_file());
+    assert_eq!(fs::read_to_string(&link).unwrap(), "Hello world!\n");
+    assert_eq!(fs::read_to_string(&target).unwrap(), "Hello world\n");
+    // no temporary file is left behind
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+}
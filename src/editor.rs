@@ -1,24 +1,43 @@
 use crate::{
-    commands, utils, AnsiPosition, Boundary, Config, Console, Document, Help, Mode, Navigator, Row,
+    clipboard, commands, config, digraph, history, utils, AnsiPosition, Boundary, ColorColumn,
+    Completion, Config, Console, Document, FileFormat, Help, Highlighter, History, Indentation,
+    LineRange, Mode, Navigator, Register, Registers, Row, Session, SessionBuffer, SessionPane,
+    StatuslinePath, Substitution, Tag, TagAddress, UndoDistance,
 };
+use chrono::Local;
+use regex::{Regex, RegexBuilder};
 use serde::ser::{SerializeStruct, Serializer};
 use serde::Serialize;
-use std::cmp;
+use std::cmp::{self, Ordering};
+use std::collections::HashMap;
 use std::env;
+use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use termion::color;
 use termion::event::{Event, Key, MouseButton, MouseEvent};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 const STATUS_FG_COLOR: color::Rgb = color::Rgb(63, 63, 63);
 const STATUS_BG_COLOR: color::Rgb = color::Rgb(239, 239, 239);
+const SELECTION_BG_COLOR: color::Rgb = color::Rgb(90, 90, 90);
+const SEARCH_MATCH_BG_COLOR: color::Rgb = color::Rgb(130, 110, 40);
+const COLORCOLUMN_BG_COLOR: color::Rgb = color::Rgb(60, 60, 60);
+const COLORCOLUMN_OVERFLOW_BG_COLOR: color::Rgb = color::Rgb(140, 40, 40);
+const WILDMENU_SELECTED_BG_COLOR: color::Rgb = color::Rgb(250, 220, 90);
 const PKG: &str = env!("CARGO_PKG_NAME");
+const MARK_PREVIEW_LENGTH: usize = 60;
+const JUMP_LIST_MAX_LENGTH: usize = 100;
+const MAX_TAG_CHOICES: usize = 9;
 const COMMAND_PREFIX: char = ':';
 const SEARCH_PREFIX: char = '/';
 const LINE_NUMBER_OFFSET: u8 = 4; // number of chars
 const START_X: u8 = LINE_NUMBER_OFFSET as u8; // index, so that's actually an offset of 5 chars
-const SPACES_PER_TAB: usize = 4;
 const SWAP_SAVE_EVERY: u8 = 100; // save to a swap file every 100 unsaved edits
+const DOUBLE_CLICK_THRESHOLD: Duration = Duration::from_millis(400);
 
 #[derive(Debug, Default, PartialEq, Clone, Copy, Serialize)]
 pub struct Position {
@@ -45,12 +64,33 @@ impl From<AnsiPosition> for Position {
     }
 }
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
 pub struct ViewportOffset {
     pub rows: usize,
     pub columns: usize,
 }
 
+/// State of a document opened in the background, restored when switching back to it
+#[derive(Debug, Default)]
+struct Buffer {
+    document: Document,
+    cursor_position: Position,
+    offset: ViewportOffset,
+    last_saved_hash: u64,
+    last_swap_hash: u64,
+    marks: HashMap<char, Position>,
+}
+
+/// The unfocused pane of a split screen, displaying a buffer with its own cursor and
+/// viewport. The focused pane uses the cursor and viewport of the editor.
+#[derive(Debug)]
+struct Pane {
+    /// Index of the displayed buffer in the buffer list
+    buffer: usize,
+    cursor_position: Position,
+    offset: ViewportOffset,
+}
+
 #[derive(Debug)]
 enum Direction {
     Up,
@@ -59,26 +99,105 @@ enum Direction {
     Right,
 }
 
+/// Where to place the cursor line in the viewport
+#[derive(Debug)]
+enum ViewPosition {
+    Top,
+    Center,
+    Bottom,
+}
+
 #[derive(Debug)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct Editor {
     should_quit: bool,
     cursor_position: Position,
+    /// Column the cursor goes back to when moving vertically, even after going through
+    /// shorter lines. `usize::MAX` sticks the cursor to the end of the lines (see `$`).
+    desired_x: usize,
     document: Document,
     offset: ViewportOffset,
+    /// Documents opened in the background, the current one being located at the
+    /// `current_buffer` index of the buffer list (and thus absent from this one)
+    buffers: Vec<Buffer>,
+    current_buffer: usize,
+    /// Other pane of the screen, when split with `:split`
+    split: Option<Pane>,
+    top_pane_focused: bool,
     message: String,
     mode: Mode,
     command_buffer: String,
     config: Config,
     normal_command_buffer: Vec<String>,
+    pending_normal_command: String,
+    registers: Registers,
+    /// Register selected with `"` for the next yank, delete or paste command
+    selected_register: Option<char>,
+    /// Named positions in the current document, the ones of the other buffers being
+    /// kept with them
+    marks: HashMap<char, Position>,
+    /// Files and positions the cursor jumped from to a tag, gone back to with Ctrl-T
+    tag_stack: Vec<(PathBuf, Position)>,
+    /// Tags matching the jumped to name, listed in the alternate screen to pick one from
+    tag_choices: Vec<Tag>,
+    /// Positions the cursor jumped from, browsed with Ctrl-O and Ctrl-I
+    jump_list: Vec<Position>,
+    /// Index of the current position in the jump list, equal to its length when the
+    /// cursor is not located on one of its entries.
+    jump_index: usize,
+    completion: Option<Completion>,
+    /// Index of the history entry displayed in the prompt while browsing the history
+    /// with Up and Down, and the text typed before browsing it.
+    history_browsing: Option<(usize, String)>,
     mouse_event_buffer: Vec<Position>,
+    last_click: Option<(Instant, Position)>,
+    click_count: u8,
+    selection_anchor: Option<Position>,
+    /// Graphemes overwritten in replace mode, restored by Backspace (`None` when the
+    /// typed grapheme was appended at the end of the line)
+    replaced_graphemes: Vec<Option<String>>,
+    /// Characters of the digraph being typed after Ctrl-K in insert mode
+    digraph_keys: Option<Vec<char>>,
+    /// Keys of the code point being typed after Ctrl-V in insert mode (eg: `u00e9`)
+    code_point_keys: Option<String>,
+    /// Position (in document coordinates) of the cursor when insert mode was last left
+    last_insert_position: Option<Position>,
+    /// Last find-char motion (eg: `f`) and its target character, repeated by `;` and `,`
+    last_find_char: Option<(char, char)>,
+    /// Keys of the command being typed in normal mode, along with the ones typed in insert
+    /// mode when the command entered it, and the document edit count before the first one
+    change_keys: Vec<Key>,
+    change_start_edit_count: usize,
+    /// Keys of the last command that edited the document, repeated by `.`
+    last_change: Vec<Key>,
+    /// Start and end of the search matches, as (0-based) grapheme and row indices in the
+    /// document. The end is exclusive: it's located right after the last matching grapheme.
     search_matches: Vec<(Position, Position)>,
     current_search_match_index: usize,
+    search_pattern: Option<String>,
+    /// Edit count and undo state of the document when the search matches were found
+    search_document_state: (usize, usize),
+    search_highlighted: bool,
+    /// Rows of the document to redraw on the next refresh, when only they changed since
+    /// the previous one (eg: the row being typed on in insert mode). The whole screen is
+    /// redrawn otherwise.
+    changed_rows: Option<Range<usize>>,
     alternate_screen: bool,
+    alternate_screen_text: Option<String>,
+    alternate_screen_scroll: usize,
+    hex_view: bool,
     last_saved_hash: u64,
+    /// Hash of the document when it was last written to its swap file (or to the file
+    /// itself), the swap file not being written to again until it changes
+    last_swap_hash: u64,
     terminal: Box<dyn Console>,
     unsaved_edits: u8,
     row_prefix_length: u8,
     help_message: String,
+    history: History,
+    /// Working directory bo was started from (it's never changed), which the paths
+    /// displayed in the status bar can be relative to
+    current_dir: PathBuf,
 }
 
 fn die(e: &io::Error) {
@@ -91,7 +210,7 @@ impl Serialize for Editor {
     where
         S: Serializer,
     {
-        let mut s = serializer.serialize_struct("Editor", 10)?;
+        let mut s = serializer.serialize_struct("Editor", 12)?;
         s.serialize_field("cursor_position", &self.cursor_position)?;
         s.serialize_field("offset", &self.offset)?;
         s.serialize_field("mode", format!("{}", self.mode).as_str())?;
@@ -105,6 +224,7 @@ impl Serialize for Editor {
         s.serialize_field("unsaved_edits", &self.unsaved_edits)?;
         s.serialize_field("last_saved_hash", &self.last_saved_hash)?;
         s.serialize_field("row_prefix_length", &self.row_prefix_length)?;
+        s.serialize_field("registers", &self.registers)?;
         s.serialize_field("document", &self.document)?;
         s.end()
     }
@@ -112,33 +232,123 @@ impl Serialize for Editor {
 
 impl Editor {
     pub fn new(filename: Option<String>, terminal: Box<dyn Console>) -> Self {
-        let document: Document = match filename {
-            None => Document::default(),
-            // Some(path) => Document::open(utils::expand_tilde(&path).as_str()).unwrap_or_default(),
-            Some(path) => Document::open(std::path::PathBuf::from(utils::expand_tilde(&path)))
-                .unwrap_or_default(),
+        let history = History::load(&PathBuf::from(utils::expand_tilde(history::HISTORY_FILE)));
+        let (config, config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(error) => (Config::default(), Some(error)),
+        };
+        let mut editor = Self::with_config(filename, terminal, config, history);
+        // the warnings about the opened file take precedence
+        if let Some(error) = config_error {
+            if editor.message.is_empty() {
+                editor.display_message(utils::red(&error));
+            }
+        }
+        editor
+    }
+
+    /// Create an editor using the provided config and command/search history, instead of
+    /// the ones loaded from the user files
+    pub fn with_config(
+        filename: Option<String>,
+        terminal: Box<dyn Console>,
+        config: Config,
+        history: History,
+    ) -> Self {
+        let (mut document, open_error) = match filename {
+            None => (Document::default(), None),
+            Some(path) => {
+                let path = PathBuf::from(utils::expand_tilde(&path));
+                match Document::open(path.clone()) {
+                    Ok(document) => (document, None),
+                    Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                        (Document::new_invalid_utf8(path), Some(error))
+                    }
+                    // eg: a directory, opened as an empty unnamed buffer instead
+                    Err(error) => (Document::default(), Some(error)),
+                }
+            }
         };
+        document.ensure_has_a_row();
         let last_saved_hash = document.hashed();
         let help_message = Help::default().format();
-        Self {
+        let row_prefix_length = if config.display_line_numbers {
+            START_X
+        } else {
+            0
+        };
+        let start_in_insert = config.start_in_insert;
+        let mut editor = Self {
             should_quit: false,
             cursor_position: Position::top_left(),
+            desired_x: 0,
             document,
             offset: ViewportOffset::default(),
+            buffers: vec![],
+            current_buffer: 0,
+            split: None,
+            top_pane_focused: true,
             message: "".to_string(),
             mode: Mode::Normal,
             command_buffer: "".to_string(),
-            config: Config::default(),
+            config,
             normal_command_buffer: vec![],
+            pending_normal_command: String::new(),
+            registers: Registers::default(),
+            selected_register: None,
+            marks: HashMap::new(),
+            tag_stack: vec![],
+            tag_choices: vec![],
+            jump_list: vec![],
+            jump_index: 0,
+            completion: None,
+            history_browsing: None,
             mouse_event_buffer: vec![],
+            last_click: None,
+            click_count: 0,
+            selection_anchor: None,
+            replaced_graphemes: vec![],
+            digraph_keys: None,
+            code_point_keys: None,
+            last_insert_position: None,
+            last_find_char: None,
+            change_keys: vec![],
+            change_start_edit_count: 0,
+            last_change: vec![],
             search_matches: vec![],
             current_search_match_index: 0,
+            search_pattern: None,
+            search_document_state: (0, 0),
+            search_highlighted: false,
+            changed_rows: None,
             alternate_screen: false,
+            alternate_screen_text: None,
+            alternate_screen_scroll: 0,
+            hex_view: false,
             terminal,
             unsaved_edits: 0,
             last_saved_hash,
-            row_prefix_length: 0,
+            last_swap_hash: last_saved_hash,
+            row_prefix_length,
             help_message,
+            history,
+            current_dir: env::current_dir().unwrap_or_default(),
+        };
+        if start_in_insert {
+            editor.start_in_insert_mode();
+        }
+        editor.warn_about_opened_file();
+        if let Some(error) = open_error {
+            editor.display_message(utils::red(&error.to_string()));
+        }
+        editor
+    }
+
+    /// Enter insert mode right away, as requested by the `-i` command line flag
+    /// or the `start_in_insert` option
+    pub fn start_in_insert_mode(&mut self) {
+        if self.mode != Mode::Insert {
+            self.enter_insert_mode();
         }
     }
 
@@ -153,6 +363,7 @@ impl Editor {
             }
             if self.should_quit {
                 self.terminal.clear_screen();
+                self.save_history();
                 break;
             }
         }
@@ -173,69 +384,362 @@ impl Editor {
     /// mode (insert, command, normal) or whether the editor is currently
     /// receiving a user input command (eg: ":q", etc).
     fn process_keystroke(&mut self, pressed_key: Key) {
+        // most commands expect a row to be located under the cursor
+        self.document.ensure_has_a_row();
+        self.changed_rows = None;
         if self.is_receiving_command() {
+            if !matches!(pressed_key, Key::Char('\t') | Key::BackTab) {
+                self.completion = None;
+            }
+            if !matches!(pressed_key, Key::Up | Key::Down) {
+                self.history_browsing = None;
+            }
             // accumulate the command in the command buffer
             match pressed_key {
                 Key::Esc => self.stop_receiving_command(),
                 Key::Char('\n') => {
                     // Enter
+                    self.record_command_in_history();
                     self.process_received_command();
                     self.stop_receiving_command();
                 }
+                Key::Char('\t') => self.complete_command(true),
+                Key::BackTab => self.complete_command(false),
+                Key::Up => self.browse_history(true),
+                Key::Down => self.browse_history(false),
                 Key::Char(c) => self.command_buffer.push(c), // accumulate keystrokes into the buffer
                 Key::Backspace => self
                     .command_buffer
                     .truncate(self.command_buffer.len().saturating_sub(1)),
                 _ => (),
             }
+        } else if self.alternate_screen && self.alternate_screen_text.is_some() {
+            self.process_alternate_screen_command(pressed_key);
+        } else if self.mode == Mode::Normal
+            && pressed_key == Key::Char('.')
+            && self.pending_normal_command.is_empty()
+        {
+            self.repeat_last_change();
         } else {
+            if self.change_keys.is_empty() {
+                self.change_start_edit_count = self.document.edit_count();
+            }
+            self.change_keys.push(pressed_key);
+            let edit_count = self.document.edit_count();
             match self.mode {
                 Mode::Normal => self.process_normal_command(pressed_key),
                 Mode::Insert => self.process_insert_command(pressed_key),
+                Mode::Visual => self.process_visual_command(pressed_key),
+                Mode::Replace => self.process_replace_command(pressed_key),
+            }
+            // an edit resets the column the vertical moves go back to
+            if self.document.edit_count() != edit_count {
+                self.desired_x = self.current_x_position();
+            }
+            self.record_change(pressed_key);
+        }
+        self.shift_marks();
+    }
+
+    /// Keep the marks on the line they were set on, when lines are added or removed
+    /// above them.
+    fn shift_marks(&mut self) {
+        for shift in self.document.take_row_shifts() {
+            for position in self.marks.values_mut() {
+                position.y = shift.shifted_row_index(position.y);
+            }
+        }
+    }
+
+    /// Once the command being typed is complete (including the text typed in insert mode
+    /// if it entered it), record its keys as the last change if it edited the document.
+    /// Pure motions are left out.
+    fn record_change(&mut self, pressed_key: Key) {
+        let typing_count = matches!(pressed_key, Key::Char(c) if c.is_ascii_digit())
+            && !self.normal_command_buffer.is_empty();
+        let command_pending =
+            typing_count || !self.pending_normal_command.is_empty() || self.mode != Mode::Normal;
+        if command_pending {
+            return;
+        }
+        let keys = std::mem::take(&mut self.change_keys);
+        if self.document.edit_count() != self.change_start_edit_count {
+            self.last_change = keys;
+        }
+    }
+
+    /// Repeat the last change at the cursor position (`.`). A count replaces the one
+    /// of the change, which is then repeated that number of times (eg: `3.`).
+    fn repeat_last_change(&mut self) {
+        let count = if self.normal_command_buffer.is_empty() {
+            None
+        } else {
+            Some(self.pop_normal_command_repetitions())
+        };
+        self.change_keys.clear();
+        let last_change = self.last_change.clone();
+        let keys: Vec<Key> = match count {
+            Some(count) => {
+                let uncounted: Vec<Key> = last_change
+                    .iter()
+                    .copied()
+                    .skip_while(|key| matches!(key, Key::Char(c) if c.is_ascii_digit()))
+                    .collect();
+                uncounted.repeat(count)
             }
+            None => last_change.clone(),
+        };
+        for key in keys {
+            self.process_keystroke(key);
         }
+        self.last_change = last_change;
     }
 
     /// React to a mouse event. If the mouse is being pressed, record
-    /// the coordinates, and
+    /// the coordinates, and move the cursor to them when the mouse is released.
+    /// A double click selects the word under the cursor, and a triple click selects the line.
+    /// Dragging the mouse selects the text between the press and current mouse positions.
+    /// A middle click pastes the primary selection where the mouse was clicked.
     fn process_mouse_event(&mut self, mouse_event: MouseEvent) {
         match mouse_event {
-            MouseEvent::Press(MouseButton::Left, _, _) => self.mouse_event_buffer.push(
-                self.terminal
-                    .get_cursor_index_from_mouse_event(mouse_event, self.row_prefix_length),
-            ),
+            MouseEvent::Press(MouseButton::Left, x, y) if self.is_in_line_numbers_gutter(x) => {
+                let line_number = Position::from(AnsiPosition { x, y })
+                    .y
+                    .saturating_add(self.offset.rows)
+                    .saturating_add(1);
+                self.process_gutter_click(line_number);
+            }
+            MouseEvent::Press(MouseButton::Left, _, _) => {
+                let position = self
+                    .terminal
+                    .get_cursor_index_from_mouse_event(mouse_event, self.row_prefix_length);
+                self.register_click(position);
+                self.mouse_event_buffer.push(position);
+            }
+            MouseEvent::Hold(_, _) => {
+                let position = self
+                    .terminal
+                    .get_cursor_index_from_mouse_event(mouse_event, self.row_prefix_length);
+                if let Some(press_position) = self.mouse_event_buffer.pop() {
+                    // we just started dragging: the selection starts where the mouse was pressed
+                    self.move_cursor_to_screen_position(press_position);
+                    self.enter_visual_mode(Position {
+                        x: self.current_x_position(),
+                        y: self.current_row_index(),
+                    });
+                }
+                if self.mode == Mode::Visual {
+                    self.move_cursor_to_screen_position(position);
+                }
+            }
             MouseEvent::Release(_, _) => {
                 if !self.mouse_event_buffer.is_empty() {
                     // Make sure that we're moving to an x/y location in which we already
                     // have text, to avoid breaking out of the document bounds.
                     let cursor_position = self.mouse_event_buffer.pop().unwrap();
-                    if cursor_position.y.saturating_add(1) <= self.document.num_rows() {
+                    if cursor_position.y.saturating_add(1) <= self.document.line_count() {
                         if let Some(target_row) = self.get_row(cursor_position.y) {
                             if cursor_position.x <= target_row.len() {
                                 self.cursor_position = cursor_position;
+                                match self.click_count {
+                                    2 => self.select_word_under_cursor(),
+                                    3 => self.select_current_line(),
+                                    _ => self.leave_visual_mode(),
+                                }
                             }
                         }
                     }
                 }
             }
-            _ => (),
+            MouseEvent::Press(MouseButton::Middle, _, _) => {
+                let position = self
+                    .terminal
+                    .get_cursor_index_from_mouse_event(mouse_event, self.row_prefix_length);
+                self.move_cursor_to_screen_position(position);
+                match clipboard::get_primary_selection() {
+                    Some(text) => self.insert_text_at_cursor(&text),
+                    // without a clipboard backend, the unnamed register is pasted instead
+                    None => self.paste(false, 1),
+                }
+            }
+            MouseEvent::Press(..) => (),
+        }
+    }
+
+    /// Insert the provided text before the cursor, and move the cursor to the last
+    /// inserted character.
+    fn insert_text_at_cursor(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let end =
+            self.document
+                .insert_string(text, self.current_x_position(), self.current_row_index());
+        self.goto_x_y(end.x.saturating_sub(1), end.y);
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+    }
+
+    /// Insert the current date at the cursor, formatted with the provided format
+    /// (`date_format` by default)
+    fn insert_date(&mut self, format: &str) {
+        let format = if format.is_empty() {
+            self.config.date_format.clone()
+        } else {
+            format.to_string()
+        };
+        match utils::format_datetime(&Local::now().naive_local(), &format) {
+            Some(date) => self.insert_text_at_cursor(&date),
+            None => self.display_message(utils::red(&format!("Invalid date format '{format}'"))),
+        }
+    }
+
+    /// Keep track of successive clicks on the same position, to detect double and triple
+    /// clicks. A fourth click starts over as a single click.
+    fn register_click(&mut self, position: Position) {
+        let now = Instant::now();
+        self.click_count = match self.last_click {
+            Some((last_click_instant, last_click_position))
+                if last_click_position == position
+                    && now.duration_since(last_click_instant) <= DOUBLE_CLICK_THRESHOLD =>
+            {
+                self.click_count % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some((now, position));
+    }
+
+    /// Return whether the provided (1-based) terminal column falls into the line numbers gutter
+    fn is_in_line_numbers_gutter(&self, x: u16) -> bool {
+        self.row_prefix_length > 0 && x <= u16::from(self.row_prefix_length).saturating_add(1)
+    }
+
+    /// React to a click in the line numbers gutter. This is kept separate from clicks
+    /// in the text area, so that gutter features (folds, marks, etc) can hook into it.
+    /// For now, we simply move the cursor to the start of the clicked line.
+    fn process_gutter_click(&mut self, line_number: usize) {
+        if line_number <= self.document.line_count() {
+            self.goto_line(line_number, 0);
         }
     }
 
+    /// Enter insert mode. Everything typed until going back to normal mode gets
+    /// undone as a whole.
     fn enter_insert_mode(&mut self) {
+        self.document.start_undo_group();
         self.mode = Mode::Insert;
         self.terminal.set_cursor_as_steady_bar();
     }
 
+    /// Enter replace mode, in which the typed characters overwrite the existing ones
+    fn enter_replace_mode(&mut self) {
+        self.document.start_undo_group();
+        self.replaced_graphemes.clear();
+        self.mode = Mode::Replace;
+        self.terminal.set_cursor_as_steady_underline();
+    }
+
     fn enter_normal_mode(&mut self) {
+        if self.mode == Mode::Insert {
+            self.last_insert_position = Some(Position {
+                x: self.current_x_position(),
+                y: self.current_row_index(),
+            });
+        }
+        self.document.end_undo_group();
         self.mode = Mode::Normal;
         self.terminal.set_cursor_as_steady_block();
     }
 
+    /// Enter visual mode, with the selection spanning from the provided anchor
+    /// (expressed in document coordinates) to the cursor.
+    fn enter_visual_mode(&mut self, anchor: Position) {
+        self.selection_anchor = Some(anchor);
+        self.mode = Mode::Visual;
+        self.terminal.set_cursor_as_steady_block();
+    }
+
+    fn leave_visual_mode(&mut self) {
+        self.selection_anchor = None;
+        if self.mode == Mode::Visual {
+            self.enter_normal_mode();
+        }
+    }
+
     fn start_receiving_command(&mut self) {
         self.command_buffer.push(COMMAND_PREFIX);
     }
 
+    /// Complete the command being typed with the next (or previous) candidate. The file
+    /// names are first completed up to the part all the candidates have in common.
+    fn complete_command(&mut self, forward: bool) {
+        if let Some(completion) = self.completion.as_ref() {
+            if let [candidate] = completion.candidates.as_slice() {
+                // Tab goes on with the content of a completed directory
+                if completion.selected.is_some() && candidate.ends_with('/') {
+                    self.completion = None;
+                }
+            }
+        }
+        if self.completion.is_none() {
+            if let Some(completion) = self.filename_completion() {
+                let common_prefix = format!("{}{}", completion.base, completion.common_prefix());
+                let fill_common_prefix = completion.candidates.len() > 1
+                    && common_prefix.len() > self.command_buffer.len();
+                self.completion = Some(completion);
+                if fill_common_prefix {
+                    self.command_buffer = common_prefix;
+                    return;
+                }
+            } else {
+                self.completion = self.command_completion();
+            }
+        }
+        if let Some(completion) = self.completion.as_mut() {
+            if forward {
+                completion.select_next();
+            } else {
+                completion.select_previous();
+            }
+            self.command_buffer = completion.completed_text();
+        }
+    }
+
+    /// Return the commands starting with the command name being typed, if any
+    fn command_completion(&self) -> Option<Completion> {
+        let typed = self.command_buffer.strip_prefix(COMMAND_PREFIX)?;
+        if typed.contains(' ') {
+            return None;
+        }
+        let mut candidates: Vec<String> = commands::ALL
+            .iter()
+            .filter(|command| command.starts_with(typed))
+            .map(ToString::to_string)
+            .collect();
+        candidates.sort();
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(Completion::new(COMMAND_PREFIX.to_string(), candidates))
+    }
+
+    /// Return the paths starting with the file name being typed as the argument of
+    /// a command expecting one (eg: `:open src/ed`)
+    fn filename_completion(&self) -> Option<Completion> {
+        let typed = self.command_buffer.strip_prefix(COMMAND_PREFIX)?;
+        let (name, argument) = typed.split_once(' ')?;
+        if !commands::WITH_FILENAME.contains(&name) {
+            return None;
+        }
+        let argument = argument.trim_start();
+        let base = self.command_buffer[..self.command_buffer.len() - argument.len()].to_string();
+        let candidates = utils::complete_path(argument);
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(Completion::new(base, candidates))
+    }
+
     fn start_receiving_search_pattern(&mut self) {
         self.command_buffer.push(SEARCH_PREFIX);
     }
@@ -248,6 +752,52 @@ impl Editor {
         !self.command_buffer.is_empty()
     }
 
+    /// Record the command currently in the command buffer in the command or search history
+    fn record_command_in_history(&mut self) {
+        if let Some(command) = self.command_buffer.strip_prefix(COMMAND_PREFIX) {
+            self.history.push_command(command);
+        } else if let Some(pattern) = self.command_buffer.strip_prefix(SEARCH_PREFIX) {
+            self.history.push_search(pattern);
+        }
+    }
+
+    /// Replace the text of the prompt by the previous (or next) entry of the command or
+    /// search history starting with the text typed before browsing it. Going down past
+    /// the most recent entry restores the typed text.
+    fn browse_history(&mut self, backward: bool) {
+        let Some(prefix) = self.command_buffer.chars().next() else {
+            return;
+        };
+        let entries = match prefix {
+            COMMAND_PREFIX => &self.history.commands,
+            SEARCH_PREFIX => &self.history.searches,
+            _ => return,
+        };
+        let (index, typed) = self.history_browsing.clone().unwrap_or_else(|| {
+            let typed = self.command_buffer[prefix.len_utf8()..].to_string();
+            (entries.len(), typed)
+        });
+        match History::find_entry(entries, index, &typed, backward) {
+            Some(index) => {
+                self.command_buffer = format!("{prefix}{}", entries[index]);
+                self.history_browsing = Some((index, typed));
+            }
+            None if !backward => {
+                self.command_buffer = format!("{prefix}{typed}");
+                self.history_browsing = None;
+            }
+            // the oldest matching entry stays displayed
+            None => (),
+        }
+    }
+
+    fn save_history(&self) {
+        let history_file = PathBuf::from(utils::expand_tilde(history::HISTORY_FILE));
+        if let Err(e) = self.history.save(&history_file) {
+            utils::log(&format!("Couldn't save the history: {e}"));
+        }
+    }
+
     fn pop_normal_command_repetitions(&mut self) -> usize {
         let times = match self.normal_command_buffer.len() {
             0 => 1,
@@ -275,58 +825,80 @@ impl Editor {
                 } else if command.chars().all(char::is_numeric) {
                     // :n will get you to line n
                     let line_index = command.parse::<usize>().unwrap();
+                    self.record_jump();
                     self.goto_line(line_index, 0);
-                } else if command.split(' ').count() > 1 {
-                    let cmd_tokens: Vec<&str> = command.split(' ').collect();
-                    match *cmd_tokens.get(0).unwrap_or(&"") {
-                        commands::OPEN | commands::OPEN_SHORT => {
-                            if let Ok(document) = Document::open(PathBuf::from(cmd_tokens[1])) {
-                                self.document = document;
-                                self.last_saved_hash = self.document.hashed();
-                                self.reset_message();
-                            } else {
-                                self.display_message(utils::red(&format!(
-                                    "{} not found",
-                                    cmd_tokens[1]
-                                )));
-                            }
-                        }
+                } else if let Some((range, command)) = LineRange::parse(
+                    command,
+                    self.current_row_index(),
+                    self.document.line_count().saturating_sub(1),
+                ) {
+                    self.process_range_command(range, command);
+                } else if let Some(substitution) = Substitution::parse(command) {
+                    self.substitute(&substitution, self.current_line_range());
+                } else {
+                    // the argument is whatever follows the command name (eg: `:open <file>`)
+                    let (name, argument) = command
+                        .split_once(' ')
+                        .map_or((command, ""), |(name, argument)| (name, argument.trim()));
+                    match name {
+                        commands::OPEN | commands::OPEN_SHORT => self.open_file(argument),
                         commands::NEW => {
-                            self.document =
-                                Document::new_empty(PathBuf::from(cmd_tokens[1].to_string()));
+                            let document = if argument.is_empty() {
+                                let mut document = Document::default();
+                                document.ensure_has_a_row();
+                                document
+                            } else {
+                                Document::new_empty(PathBuf::from(argument))
+                            };
+                            self.open_buffer(document);
                             self.enter_insert_mode();
                         }
-                        commands::SAVE => {
-                            let new_name = cmd_tokens[1..].join(" ");
-                            self.save(new_name.trim());
-                        }
-                        _ => self.display_message(utils::red(&format!(
-                            "Unknown command '{}'",
-                            cmd_tokens[0]
-                        ))),
-                    }
-                } else {
-                    match command {
+                        commands::SET => self.process_set_command(argument),
+                        commands::DELETE => self.delete_lines(self.current_line_range()),
+                        commands::SORT => self.sort_lines(LineRange {
+                            start: 0,
+                            end: self.document.line_count().saturating_sub(1),
+                        }),
+                        commands::NORMALIZE_INDENT => self.normalize_indentation(LineRange {
+                            start: 0,
+                            end: self.document.line_count().saturating_sub(1),
+                        }),
+                        commands::DATE => self.insert_date(argument),
                         commands::FORCE_QUIT => self.quit(true),
                         commands::QUIT => self.quit(false),
-                        commands::LINE_NUMBERS => {
-                            self.config.display_line_numbers =
-                                Config::toggle(self.config.display_line_numbers);
-                            self.row_prefix_length = if self.config.display_line_numbers {
-                                START_X
-                            } else {
-                                0
-                            };
-                        }
-                        commands::STATS => {
-                            self.config.display_stats = Config::toggle(self.config.display_stats);
-                        }
+                        commands::QUIT_ALL => self.quit_all(),
+                        commands::NEXT_BUFFER => self.switch_to_next_buffer(),
+                        commands::PREVIOUS_BUFFER => self.switch_to_buffer(
+                            self.current_buffer
+                                .checked_sub(1)
+                                .unwrap_or(self.buffer_count().saturating_sub(1)),
+                        ),
+                        commands::LIST_BUFFERS => self.display_message(self.format_buffers()),
+                        commands::SPLIT | commands::SPLIT_SHORT => self.split_screen(),
+                        commands::LINE_NUMBERS => self.process_set_command("number!"),
+                        commands::STATS => self.process_set_command("stats!"),
+                        commands::NO_HIGHLIGHT_SEARCH => self.search_highlighted = false,
                         commands::HELP => {
                             self.alternate_screen = true;
                         }
-                        commands::SAVE => self.save(""),
+                        commands::HEX => self.toggle_hex_view(),
+                        commands::MARKS => self.display_in_alternate_screen(self.format_marks()),
+                        commands::TAG => self.jump_to_tag(argument),
+                        commands::JUMPS => self.display_in_alternate_screen(self.format_jumps()),
+                        commands::REGISTERS | commands::REGISTERS_SHORT => {
+                            self.display_in_alternate_screen(self.registers.format());
+                        }
+                        commands::SAVE => self.save(argument),
+                        commands::SOURCE => self.source_config(argument),
+                        commands::MAKE_SESSION => self.make_session(argument),
+                        commands::SOURCE_SESSION => self.load_session(argument),
+                        commands::RECOVER => self.recover_from_swap_file(),
+                        commands::DISCARD => self.discard_swap_file(),
+                        commands::UNDO => self.process_undo_command(argument),
+                        commands::EARLIER => self.travel_undo_history(argument, true),
+                        commands::LATER => self.travel_undo_history(argument, false),
                         commands::SAVE_AND_QUIT => {
-                            self.save("");
+                            self.save(argument);
                             self.quit(false);
                         }
                         commands::DEBUG => {
@@ -334,8 +906,7 @@ impl Editor {
                                 utils::log(state.as_str());
                             }
                         }
-                        _ => self
-                            .display_message(utils::red(&format!("Unknown command '{}'", command))),
+                        _ => self.display_message(utils::red(&format!("Unknown command '{name}'"))),
                     }
                 }
             }
@@ -343,178 +914,2088 @@ impl Editor {
         }
     }
 
-    fn save(&mut self, new_name: &str) {
-        // this will trim trailing spaces, which might cause the cursor to get out of bounds
-        self.document.trim_trailing_spaces();
-        if self.cursor_position.x >= self.current_row().len() {
-            self.cursor_position.x = self.current_row().len().saturating_sub(1);
-        }
-        let initial_filename = self.document.filename.clone();
-        if new_name.is_empty() {
-            if self.document.filename.is_none() {
-                self.display_message(utils::red("No file name"));
-                return;
-            } else if self.document.save().is_ok() {
-                self.display_message("File successfully saved".to_string());
-                self.last_saved_hash = self.document.hashed();
-            } else {
-                self.display_message(utils::red("Error writing to file!"));
-                return;
-            }
-        } else if self.document.save_as(new_name).is_ok() {
-            if initial_filename.is_none() {
-                self.display_message(format!("Buffer saved to {}", new_name));
-            } else {
-                self.display_message(format!(
-                    "{} successfully renamed to {}",
-                    self.document
-                        .filename
-                        .as_ref()
-                        .unwrap()
-                        .to_str()
-                        .unwrap_or_default(),
-                    new_name
-                ));
-            }
-            self.document.filename = Some(PathBuf::from(new_name));
+    /// Process a command preceded by a range of lines (eg: `:%d`, `:1,5sort`, `:.,$!fmt`).
+    /// A range without command moves the cursor to its last line.
+    fn process_range_command(&mut self, range: LineRange, command: &str) {
+        if command.is_empty() {
+            self.record_jump();
+            self.goto_line(range.end.saturating_add(1), 0);
+        } else if let Some(substitution) = Substitution::parse(command) {
+            self.substitute(&substitution, range);
+        } else if let Some(shell_command) = command.strip_prefix('!') {
+            self.filter_lines(range, shell_command.trim());
         } else {
-            self.display_message(utils::red("Error writing to file!"));
+            match command {
+                commands::DELETE => self.delete_lines(range),
+                commands::SORT => self.sort_lines(range),
+                commands::NORMALIZE_INDENT => self.normalize_indentation(range),
+                _ => self.display_message(utils::red(&format!("Unknown command '{command}'"))),
+            }
         }
-        self.unsaved_edits = 0;
-        self.last_saved_hash = self.document.hashed();
     }
 
-    fn save_to_swap_file(&mut self) {
-        if self.document.save_to_swap_file().is_ok() {
-            self.unsaved_edits = 0;
+    /// Return the range made of the current line only
+    fn current_line_range(&self) -> LineRange {
+        LineRange {
+            start: self.current_row_index(),
+            end: self.current_row_index(),
         }
     }
 
-    fn quit(&mut self, force: bool) {
-        if self.is_dirty() && !force {
-            self.display_message(utils::red("Unsaved changes! Run :q! to override"));
-        } else {
-            self.should_quit = true;
-        }
+    /// Return the lines of the range, as they're passed to the ranged commands
+    fn lines_in_range(&self, range: LineRange) -> Vec<String> {
+        (range.start..=range.end)
+            .filter_map(|index| self.get_row(index))
+            .map(|row| row.string.clone())
+            .collect()
     }
 
-    fn process_search_command(&mut self, search_pattern: &str) {
-        self.reset_search();
-        for (row_index, row) in self.document.iter().enumerate() {
-            if row.contains(search_pattern) {
-                if let Some(match_start_index) = row.find(search_pattern) {
-                    let match_start = Position {
-                        x: match_start_index,
-                        y: row_index.saturating_add(1), // terminal line number, 1-bases
-                    };
-                    let match_end = Position {
-                        x: match_start_index
-                            .saturating_add(1)
-                            .saturating_add(search_pattern.len()),
-                        y: row_index.saturating_add(1),
-                    };
-                    self.search_matches.push((match_start, match_end));
-                }
-            }
+    /// Replace the lines of the range, and move the cursor to the first replaced one
+    fn replace_lines(&mut self, range: LineRange, lines: &[String]) {
+        self.document.replace_lines(range.start, range.end, lines);
+        let y = cmp::min(range.start, self.document.line_count().saturating_sub(1));
+        self.goto_x_y(0, y);
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+    }
+
+    /// Delete the lines of the range (`:d`), which are stored in the register
+    fn delete_lines(&mut self, range: LineRange) {
+        let register = Register::linewise(self.lines_in_range(range).join("\n"));
+        self.registers
+            .delete(self.selected_register.take(), register);
+        self.replace_lines(range, &[]);
+        if range.line_count() > 2 {
+            self.display_message(format!("{} fewer lines", range.line_count()));
         }
-        self.display_message(format!("{} matches", self.search_matches.len()));
-        self.current_search_match_index = self.search_matches.len().saturating_sub(1);
-        self.goto_next_search_match();
     }
 
-    fn reset_search(&mut self) {
-        self.search_matches = vec![]; // erase previous search matches
-        self.current_search_match_index = 0;
+    /// Sort the lines of the range (`:sort`), the whole document by default
+    fn sort_lines(&mut self, range: LineRange) {
+        let mut lines = self.lines_in_range(range);
+        lines.sort();
+        self.replace_lines(range, &lines);
     }
 
-    fn revert_to_main_screen(&mut self) {
-        self.reset_message();
-        self.alternate_screen = false;
+    /// Rewrite the indentation of the lines of the range (`:normalize-indent`), the whole
+    /// document by default, with spaces or tabs only depending on `expand_tab`
+    fn normalize_indentation(&mut self, range: LineRange) {
+        let lines = self.lines_in_range(range);
+        let normalized: Vec<String> = lines
+            .iter()
+            .map(|line| {
+                utils::normalize_indentation(line, self.config.tab_width, self.expands_tab())
+            })
+            .collect();
+        let changed = lines
+            .iter()
+            .zip(&normalized)
+            .filter(|(line, normalized_line)| line != normalized_line)
+            .count();
+        if changed > 0 {
+            self.replace_lines(range, &normalized);
+        }
+        let plural = if changed == 1 { "" } else { "s" };
+        self.display_message(format!("{changed} line{plural} changed"));
     }
 
-    /// Process navigation command issued in normal mode, that will
-    /// resolve in having the cursor be moved around the document.
-    ///
-    /// Note: some commands are accumulative (ie: 2j will move the
-    /// cursor down twice) and some are not (ie: g will move the cursor
-    /// to the start of the document only once).
-    /// A buffer is maintained for the accumulative commands, and is purged
-    /// when the last char of the command is received. For now, only commans
-    /// of the form <number>*<char> are supported and I'm not sure I'm
-    /// planning to support anything more complex than that.
-    fn process_normal_command(&mut self, key: Key) {
-        if key == Key::Esc {
-            self.reset_message();
-            self.reset_search();
+    /// Replace the lines of the range by the output of the shell command they're piped
+    /// to (eg: `:%!sort`)
+    fn filter_lines(&mut self, range: LineRange, shell_command: &str) {
+        if shell_command.is_empty() {
+            self.display_message(utils::red("No command to run"));
+            return;
         }
-        if let Key::Char(c) = key {
-            match c {
-                '0' => {
-                    if self.normal_command_buffer.is_empty() {
-                        self.goto_start_or_end_of_line(&Boundary::Start);
-                    } else {
-                        self.normal_command_buffer.push(c.to_string());
-                    }
+        let mut input = self.lines_in_range(range).join("\n");
+        input.push('\n');
+        match utils::filter_through_command(shell_command, &input) {
+            Ok(output) => {
+                let lines: Vec<String> = output.lines().map(String::from).collect();
+                self.replace_lines(range, &lines);
+                self.display_message(format!("{} lines filtered", range.line_count()));
+            }
+            Err(error) => self.display_message(utils::red(&error)),
+        }
+    }
+
+    /// Open the file in a new buffer, or switch to its buffer if it's already opened
+    fn open_file(&mut self, filename: &str) {
+        if filename.is_empty() {
+            self.display_message(utils::red("No file name"));
+            return;
+        }
+        let path = PathBuf::from(utils::expand_tilde(filename));
+        if let Some(index) = self.buffer_index(&path) {
+            self.switch_to_buffer(index);
+        } else {
+            match Document::open(path.clone()) {
+                Ok(mut document) => {
+                    document.ensure_has_a_row();
+                    self.open_buffer(document);
+                    self.reset_message();
+                    self.warn_about_opened_file();
                 }
-                '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
-                    self.normal_command_buffer.push(c.to_string());
+                Err(error) if error.kind() == io::ErrorKind::InvalidData => {
+                    self.open_buffer(Document::new_invalid_utf8(path));
+                    self.display_message(utils::red(&error.to_string()));
                 }
-                'i' => self.enter_insert_mode(),
-                ':' => self.start_receiving_command(),
-                '/' => self.start_receiving_search_pattern(),
-                'G' => self.goto_start_or_end_of_document(&Boundary::End),
-                'g' => self.goto_start_or_end_of_document(&Boundary::Start),
-                '$' => self.goto_start_or_end_of_line(&Boundary::End),
-                '^' => self.goto_first_non_whitespace(),
-                'H' => self.goto_first_line_of_terminal(),
-                'M' => self.goto_middle_of_terminal(),
-                'L' => self.goto_last_line_of_terminal(),
-                'm' => self.goto_matching_closing_symbol(),
-                'n' => self.goto_next_search_match(),
-                'N' => self.goto_previous_search_match(),
-                'q' => self.revert_to_main_screen(),
-                'd' => self.delete_current_line(),
-                'x' => self.delete_current_grapheme(),
-                'o' => self.insert_newline_after_current_line(),
-                'O' => self.insert_newline_before_current_line(),
-                'A' => self.append_to_line(),
-                'J' => self.join_current_line_with_next_one(),
-                _ => {
-                    // at that point, we've iterated over all non accumulative commands
-                    // meaning the command we're processing is an accumulative one.
-                    // we thus pop the repeater value from self.normal_command_buffer
-                    // and we use that value as the number of times the comamnd identified
-                    // by the `c` char must be repeated.
-                    let times = self.pop_normal_command_repetitions();
-                    self.process_normal_command_n_times(c, times);
+                Err(error) if path.is_dir() => {
+                    self.display_message(utils::red(&error.to_string()));
                 }
+                Err(_) => self.display_message(utils::red(&format!("{filename} not found"))),
             }
+        }
+    }
+
+    fn update_row_prefix_length(&mut self) {
+        self.row_prefix_length = if self.config.display_line_numbers {
+            START_X
+        } else {
+            0
         };
     }
 
-    /// Execute the provided normal movement command n timess
-    fn process_normal_command_n_times(&mut self, c: char, n: usize) {
-        match c {
-            'b' => self.goto_start_or_end_of_word(&Boundary::Start, n),
-            'w' => self.goto_start_or_end_of_word(&Boundary::End, n),
-            'h' => self.move_cursor(&Direction::Left, n),
-            'j' => self.move_cursor(&Direction::Down, n),
+    /// Re-read the provided config file (the user config file by default) and apply it
+    /// to the running editor. The current config is kept if the file can't be parsed.
+    fn source_config(&mut self, path: &str) {
+        let path = utils::expand_tilde(if path.is_empty() {
+            config::CONFIG_FILE
+        } else {
+            path
+        });
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.display_message(utils::red(&format!("Couldn't read {path}: {e}")));
+                return;
+            }
+        };
+        match Config::from_toml(&contents) {
+            Ok(config) => {
+                let ignore_case_changed = config.ignore_case != self.config.ignore_case;
+                self.config = config;
+                self.update_row_prefix_length();
+                if ignore_case_changed {
+                    self.recompute_search_matches();
+                }
+                self.scroll_to_cursor_display_x();
+                self.display_message(format!("\"{path}\" sourced"));
+            }
+            Err(e) => self.display_message(utils::red(&format!("Couldn't parse {path}: {e}"))),
+        }
+    }
+
+    /// Save the opened buffers and the screen layout to the session file (`:mksession`)
+    fn make_session(&mut self, name: &str) {
+        if name.is_empty() {
+            self.display_message(utils::red("No session name"));
+            return;
+        }
+        let path = Session::path(name);
+        match self.session().save(&path) {
+            Ok(()) => self.display_message(format!("Session saved to {}", path.display())),
+            Err(e) => self.display_message(utils::red(&format!(
+                "Couldn't save the session to {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Restore the buffers and the screen layout saved in the session file
+    /// (`:source-session` or `bo -S`)
+    pub fn load_session(&mut self, name: &str) {
+        if name.is_empty() {
+            self.display_message(utils::red("No session name"));
+            return;
+        }
+        match Session::load(&Session::path(name)) {
+            Ok(session) => self.restore_session(&session),
+            Err(e) => self.display_message(utils::red(&format!(
+                "Couldn't load the session {name}: {e}"
+            ))),
+        }
+    }
+
+    /// Describe the opened buffers and the screen layout, leaving the unnamed buffers out
+    fn session(&self) -> Session {
+        let mut buffers = vec![];
+        // index of each buffer of the buffer list in the session one
+        let mut session_indices = vec![];
+        for index in 0..self.buffer_count() {
+            let session_index = self
+                .buffer_state(index)
+                .and_then(|(document, cursor, offset)| {
+                    let filename = document.filename.as_ref()?;
+                    buffers.push(SessionBuffer {
+                        filename: fs::canonicalize(filename).unwrap_or_else(|_| filename.clone()),
+                        line: offset.rows.saturating_add(cursor.y),
+                        column: offset.columns.saturating_add(cursor.x),
+                    });
+                    Some(buffers.len().saturating_sub(1))
+                });
+            session_indices.push(session_index);
+        }
+        let split = self.split.as_ref().and_then(|pane| {
+            Some(SessionPane {
+                buffer: (*session_indices.get(pane.buffer)?)?,
+                line: pane.offset.rows.saturating_add(pane.cursor_position.y),
+                column: pane.offset.columns.saturating_add(pane.cursor_position.x),
+            })
+        });
+        Session {
+            buffers,
+            current_buffer: session_indices
+                .get(self.current_buffer)
+                .copied()
+                .flatten()
+                .unwrap_or_default(),
+            split,
+            top_pane_focused: self.top_pane_focused,
+        }
+    }
+
+    /// Replace the opened buffers by the session ones, skipping the files that don't
+    /// exist anymore
+    fn restore_session(&mut self, session: &Session) {
+        let has_unsaved_changes = self.is_dirty()
+            || self
+                .buffers
+                .iter()
+                .any(|buffer| buffer.last_saved_hash != buffer.document.hashed());
+        if has_unsaved_changes {
+            self.display_message(utils::red(
+                "Unsaved changes! Save them before loading a session",
+            ));
+            return;
+        }
+        let (existing, missing): (Vec<&SessionBuffer>, Vec<&SessionBuffer>) = session
+            .buffers
+            .iter()
+            .partition(|buffer| buffer.filename.is_file());
+        if existing.is_empty() {
+            self.display_message(utils::red("None of the session files exist anymore"));
+            return;
+        }
+        // index of each session buffer in the new buffer list
+        let buffer_index = |index: usize| {
+            let buffer = session.buffers.get(index)?;
+            existing
+                .iter()
+                .position(|existing_buffer| existing_buffer.filename == buffer.filename)
+        };
+
+        self.buffers.clear();
+        self.current_buffer = 0;
+        self.split = None;
+        self.top_pane_focused = true;
+        self.document = Document::default();
+        self.document.ensure_has_a_row();
+        self.last_saved_hash = self.document.hashed();
+        self.last_swap_hash = self.last_saved_hash;
+        self.marks.clear();
+        for buffer in &existing {
+            if let Ok(mut document) = Document::open(buffer.filename.clone()) {
+                document.ensure_has_a_row();
+                self.open_buffer(document);
+                self.goto_document_position(buffer.line, buffer.column);
+            }
+        }
+        let current_buffer = buffer_index(session.current_buffer).unwrap_or_default();
+        if let Some(pane) = session.split {
+            if let Some(index) = buffer_index(pane.buffer) {
+                // both panes display the buffer of the unfocused one once the screen is
+                // split, the focused one then switching to its own buffer
+                self.make_buffer_current(index);
+                self.goto_document_position(pane.line, pane.column);
+                self.split_screen();
+                if !session.top_pane_focused {
+                    self.focus_pane(false);
+                }
+            }
+        }
+        self.make_buffer_current(current_buffer);
+        if let Some(buffer) = existing.get(current_buffer) {
+            self.goto_document_position(buffer.line, buffer.column);
+        }
+        if missing.is_empty() {
+            self.reset_message();
+        } else {
+            let filenames: Vec<String> = missing
+                .iter()
+                .map(|buffer| buffer.filename.display().to_string())
+                .collect();
+            self.display_message(utils::red(&format!(
+                "Skipped the missing files: {}",
+                filenames.join(", ")
+            )));
+        }
+    }
+
+    /// Move the cursor to the provided row and grapheme indices, within the document bounds
+    fn goto_document_position(&mut self, line: usize, column: usize) {
+        let y = cmp::min(line, self.document.line_count().saturating_sub(1));
+        self.goto_x_y(0, y);
+        let last_x = self.current_row().len().saturating_sub(1);
+        self.goto_x_y(cmp::min(column, last_x), y);
+    }
+
+    /// Process a `:set <option>=<value>` (or `:set <option> <value>`) command. The boolean
+    /// options are enabled with `:set <option>`, disabled with `:set no<option>`, toggled
+    /// with `:set <option>!` (or `:set inv<option>`) and displayed with `:set <option>?`.
+    fn process_set_command(&mut self, option: &str) {
+        let (name, value) = match option.trim().split_once(['=', ' ']) {
+            Some((name, value)) => (name, Some(value)),
+            None => (option.trim(), None),
+        };
+        match name {
+            "so" | "scrolloff" | "scr" | "scroll" | "ts" | "tabwidth" | "tw" | "textwidth" => {
+                self.set_number_option(name, value);
+            }
+            "cc" | "colorcolumn" => match value.map(ColorColumn::parse_list) {
+                None => {
+                    let columns: Vec<String> = self
+                        .config
+                        .colorcolumn
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect();
+                    self.display_message(format!("colorcolumn={}", columns.join(",")));
+                }
+                Some(Some(columns)) => self.config.colorcolumn = columns,
+                Some(None) => {
+                    self.display_message(utils::red(&format!("Invalid value for '{name}'")));
+                }
+            },
+            "ff" | "fileformat" => match value {
+                None => self.display_message(format!("fileformat={}", self.document.file_format())),
+                Some(value) => match FileFormat::from_name(value) {
+                    Some(file_format) => self.document.set_file_format(file_format),
+                    None => {
+                        self.display_message(utils::red(&format!("Invalid value for '{name}'")));
+                    }
+                },
+            },
+            "bin" | "binary" => {
+                if self.is_dirty() {
+                    self.display_message(utils::red(
+                        "Unsaved changes! Save them before switching to binary mode",
+                    ));
+                } else if let Err(error) = self.document.reload_as_binary() {
+                    self.display_message(utils::red(&error.to_string()));
+                } else {
+                    self.last_saved_hash = self.document.hashed();
+                    self.last_swap_hash = self.last_saved_hash;
+                    self.recompute_search_matches();
+                    self.goto_start_line(self.current_line_number());
+                }
+            }
+            "nobin" | "nobinary" => self.document.unset_binary(),
+            "statuslinepath" => match value {
+                None => {
+                    self.display_message(format!("statuslinepath={}", self.config.statusline_path));
+                }
+                Some(value) => match StatuslinePath::from_name(value) {
+                    Some(statusline_path) => self.config.statusline_path = statusline_path,
+                    None => {
+                        self.display_message(utils::red(&format!("Invalid value for '{name}'")));
+                    }
+                },
+            },
+            _ => self.set_boolean_option(name, value),
+        }
+    }
+
+    /// Return the config field of the boolean option with the provided (full or short) name
+    fn boolean_option_mut(&mut self, name: &str) -> Option<&mut bool> {
+        let config = &mut self.config;
+        match name {
+            "nu" | "number" => Some(&mut config.display_line_numbers),
+            "stats" => Some(&mut config.display_stats),
+            "ic" | "ignorecase" => Some(&mut config.ignore_case),
+            "ai" | "autoindent" => Some(&mut config.autoindent),
+            "et" | "expandtab" => Some(&mut config.expand_tab),
+            "findacrosslines" => Some(&mut config.find_across_lines),
+            "detectindent" => Some(&mut config.detect_indent),
+            "list" => Some(&mut config.list),
+            "hls" | "hlsearch" => Some(&mut config.hlsearch),
+            "wmnu" | "wildmenu" => Some(&mut config.wildmenu),
+            "trim" => Some(&mut config.trim_trailing_spaces),
+            "followsymlinks" => Some(&mut config.follow_symlinks),
+            _ => None,
+        }
+    }
+
+    /// Enable, disable, toggle or display a boolean option, depending on its prefix or suffix
+    fn set_boolean_option(&mut self, option: &str, value: Option<&str>) {
+        if let Some(name) = option.strip_suffix('?') {
+            match self.boolean_option_mut(name) {
+                Some(enabled) => {
+                    let message = if *enabled {
+                        name.to_string()
+                    } else {
+                        format!("no{name}")
+                    };
+                    self.display_message(message);
+                }
+                None => self.display_message(utils::red(&format!("Unknown option '{name}'"))),
+            }
+            return;
+        }
+        let (name, enable) = if self.boolean_option_mut(option).is_some() {
+            (option, Some(true))
+        } else if let Some(name) = option
+            .strip_suffix('!')
+            .or_else(|| option.strip_prefix("inv"))
+        {
+            (name, None)
+        } else if let Some(name) = option.strip_prefix("no") {
+            (name, Some(false))
+        } else {
+            (option, Some(true))
+        };
+        let Some(enabled) = self.boolean_option_mut(name) else {
+            self.display_message(utils::red(&format!("Unknown option '{option}'")));
+            return;
+        };
+        if value.is_some() {
+            self.display_message(utils::red(&format!("Option '{option}' takes no value")));
+            return;
+        }
+        *enabled = enable.unwrap_or(!*enabled);
+        // some options need more than their config field to be updated
+        match name {
+            "nu" | "number" => self.update_row_prefix_length(),
+            "ic" | "ignorecase" => self.set_ignore_case(self.config.ignore_case),
+            _ => (),
+        }
+    }
+
+    /// Set (or display, without a value) a numeric option
+    fn set_number_option(&mut self, name: &str, value: Option<&str>) {
+        let (option, min_value) = match name {
+            "so" | "scrolloff" => (&mut self.config.scrolloff, 0),
+            "scr" | "scroll" => (&mut self.config.scroll, 0),
+            "tw" | "textwidth" => (&mut self.config.textwidth, 0),
+            _ => (&mut self.config.tab_width, 1),
+        };
+        match value.map(str::parse::<usize>) {
+            None => {
+                let message = format!("{name}={option}");
+                self.display_message(message);
+            }
+            Some(Ok(number)) if number >= min_value => *option = number,
+            Some(_) => self.display_message(utils::red(&format!("Invalid value for '{name}'"))),
+        }
+    }
+
+    /// Make the search case sensitive or not, and update the current search matches
+    fn set_ignore_case(&mut self, ignore_case: bool) {
+        self.config.ignore_case = ignore_case;
+        self.recompute_search_matches();
+        if ignore_case {
+            self.display_message(String::from("Case insensitive search"));
+        } else {
+            self.display_message(String::from("Case sensitive search"));
+        }
+    }
+
+    fn save(&mut self, new_name: &str) {
+        if self.config.trim_trailing_spaces && !self.document.is_binary() {
+            self.document.trim_trailing_spaces();
+            // the cursor might have been located in the trimmed whitespace
+            let row_len = self.current_row().len();
+            let max_x = if matches!(self.mode, Mode::Insert | Mode::Replace) {
+                row_len
+            } else {
+                row_len.saturating_sub(1)
+            };
+            if self.current_x_position() > max_x {
+                self.move_cursor_to_position_x(max_x);
+            }
+        }
+        let initial_filename = self.document.filename.clone();
+        if new_name.is_empty() {
+            if self.document.filename.is_none() {
+                self.display_message(utils::red("No file name"));
+                return;
+            }
+            let replace_symlink =
+                !self.config.follow_symlinks && self.document.symlink_target().is_some();
+            let saved = if replace_symlink {
+                self.document.save_replacing_symlink()
+            } else {
+                self.document.save()
+            };
+            if let Err(error) = saved {
+                // eg: a file which isn't valid UTF-8, outside of binary mode
+                let message = if error.kind() == io::ErrorKind::InvalidData {
+                    error.to_string()
+                } else if replace_symlink {
+                    String::from("Couldn't replace the symlink!")
+                } else {
+                    String::from("Error writing to file!")
+                };
+                self.display_message(utils::red(&message));
+                return;
+            }
+            self.display_message("File successfully saved".to_string());
+            self.last_saved_hash = self.document.hashed();
+        } else if self.document.save_as(new_name).is_ok() {
+            if initial_filename.is_none() {
+                self.display_message(format!("Buffer saved to {}", new_name));
+            } else {
+                self.display_message(format!(
+                    "{} successfully renamed to {}",
+                    self.document
+                        .filename
+                        .as_ref()
+                        .unwrap()
+                        .to_str()
+                        .unwrap_or_default(),
+                    new_name
+                ));
+            }
+            self.document.filename = Some(PathBuf::from(new_name));
+        } else {
+            self.display_message(utils::red("Error writing to file!"));
+        }
+        self.unsaved_edits = 0;
+        self.last_saved_hash = self.document.hashed();
+        // saving the file deletes its swap file
+        self.last_swap_hash = self.last_saved_hash;
+    }
+
+    /// Write the document to its swap file, unless it's unchanged since it was last
+    /// written to it (eg: after undoing and redoing edits), or the swap file found when
+    /// opening the file is yet to be recovered or discarded
+    fn save_to_swap_file(&mut self) {
+        if self.document.has_pending_swap_file() {
+            self.unsaved_edits = 0;
+            return;
+        }
+        let hash = self.document.hashed();
+        if hash == self.last_swap_hash {
+            self.unsaved_edits = 0;
+        } else if self.document.save_to_swap_file().is_ok() {
+            self.unsaved_edits = 0;
+            self.last_swap_hash = hash;
+        }
+    }
+
+    /// Let the user know that the opened file is a symlink, or has a swap file with a
+    /// different content (eg: left by a crashed session), the file itself being opened.
+    fn warn_about_opened_file(&mut self) {
+        if let Some(target) = self.document.symlink_target() {
+            let message = if self.config.follow_symlinks {
+                format!("Symlink to {}, saved to its target", target.display())
+            } else {
+                format!(
+                    "Symlink to {}, replaced by the file on save",
+                    target.display()
+                )
+            };
+            self.display_message(message);
+        }
+        if self.document.has_pending_swap_file() {
+            let age = if self.document.swap_file_is_newer() {
+                "newer"
+            } else {
+                "older"
+            };
+            self.display_message(utils::red(&format!(
+                "Found a swap file {age} than the file: :recover to load it, :discard to delete it"
+            )));
+        }
+    }
+
+    /// Load the content of the swap file in the document (`:recover`), as an undoable edit
+    fn recover_from_swap_file(&mut self) {
+        match self.document.recover_from_swap_file() {
+            Ok(()) => {
+                let line_number = cmp::min(self.current_line_number(), self.document.line_count());
+                self.goto_line(line_number, 0);
+                self.display_message(String::from("Recovered from the swap file"));
+            }
+            Err(e) => self.display_message(utils::red(&format!("Couldn't recover: {e}"))),
+        }
+    }
+
+    /// Delete the swap file of the document (`:discard`)
+    fn discard_swap_file(&mut self) {
+        match self.document.discard_swap_file() {
+            Ok(()) => self.display_message(String::from("Swap file deleted")),
+            Err(e) => {
+                self.display_message(utils::red(&format!("Couldn't delete the swap file: {e}")));
+            }
+        }
+    }
+
+    /// Close the focused pane of the split screen, or else the current buffer, and quit
+    /// when it's the last one. With `force`, quit right away, discarding the unsaved
+    /// changes of every buffer.
+    fn quit(&mut self, force: bool) {
+        if force {
+            self.should_quit = true;
+        } else if self.split.is_some() {
+            // the buffer stays open in the background
+            self.close_pane();
+        } else if self.is_dirty() {
+            self.display_message(utils::red(self.unsaved_changes_warning()));
+        } else if self.buffers.is_empty() {
+            self.should_quit = true;
+        } else {
+            // the next buffer takes the place of the closed one
+            let index = cmp::min(self.current_buffer, self.buffers.len().saturating_sub(1));
+            let buffer = self.buffers.remove(index);
+            self.current_buffer = index;
+            self.restore_buffer(buffer);
+        }
+    }
+
+    /// Tell how to deal with the unsaved changes of the current buffer, which first needs
+    /// a file name if it doesn't have one
+    fn unsaved_changes_warning(&self) -> &'static str {
+        if self.document.filename.is_none() {
+            "No file name! Run :w <name> to save the changes, or :q! to discard them"
+        } else {
+            "Unsaved changes! Run :q! to override"
+        }
+    }
+
+    /// Quit, unless one of the buffers has unsaved changes
+    fn quit_all(&mut self) {
+        if self.is_dirty() {
+            self.quit(false);
+        } else if let Some(buffer) = self
+            .buffers
+            .iter()
+            .find(|buffer| buffer.last_saved_hash != buffer.document.hashed())
+        {
+            self.display_message(utils::red(&format!(
+                "Unsaved changes in {}! Run :q! to override",
+                Self::buffer_name(&buffer.document)
+            )));
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    fn buffer_count(&self) -> usize {
+        self.buffers.len().saturating_add(1)
+    }
+
+    fn buffer_name(document: &Document) -> String {
+        document.filename.as_ref().map_or_else(
+            || String::from("No Name"),
+            |filename| filename.display().to_string(),
+        )
+    }
+
+    /// Return the index of the buffer (in the buffer list) of the provided file, if opened
+    fn buffer_index(&self, path: &Path) -> Option<usize> {
+        if self.document.filename.as_deref() == Some(path) {
+            return Some(self.current_buffer);
+        }
+        self.buffers
+            .iter()
+            .position(|buffer| buffer.document.filename.as_deref() == Some(path))
+            .map(|index| {
+                if index < self.current_buffer {
+                    index
+                } else {
+                    index.saturating_add(1)
+                }
+            })
+    }
+
+    /// Move the state of the current document into a buffer
+    fn take_current_buffer(&mut self) -> Buffer {
+        Buffer {
+            document: std::mem::take(&mut self.document),
+            cursor_position: self.cursor_position,
+            offset: std::mem::take(&mut self.offset),
+            last_saved_hash: self.last_saved_hash,
+            last_swap_hash: self.last_swap_hash,
+            marks: std::mem::take(&mut self.marks),
+        }
+    }
+
+    /// Make the buffer the current document, and display it where it was left
+    fn restore_buffer(&mut self, buffer: Buffer) {
+        self.document = buffer.document;
+        self.cursor_position = buffer.cursor_position;
+        self.offset = buffer.offset;
+        self.last_saved_hash = buffer.last_saved_hash;
+        self.last_swap_hash = buffer.last_swap_hash;
+        self.marks = buffer.marks;
+        self.desired_x = self.current_x_position();
+        self.selection_anchor = None;
+        self.recompute_search_matches();
+    }
+
+    /// Open the document in a new buffer, added at the end of the buffer list. An empty
+    /// unnamed document is replaced instead.
+    fn open_buffer(&mut self, document: Document) {
+        if self.document.filename.is_some() || self.is_dirty() {
+            let current = self.take_current_buffer();
+            self.buffers.insert(self.current_buffer, current);
+            self.current_buffer = self.buffers.len();
+        }
+        self.restore_buffer(Buffer {
+            last_saved_hash: document.hashed(),
+            last_swap_hash: document.hashed(),
+            document,
+            ..Buffer::default()
+        });
+    }
+
+    /// Make the buffer following the current one in the buffer list the current one,
+    /// going back to the first one after the last one
+    fn switch_to_next_buffer(&mut self) {
+        self.switch_to_buffer(self.current_buffer.saturating_add(1) % self.buffer_count());
+    }
+
+    /// Make the buffer located at the provided index of the buffer list the current one
+    fn switch_to_buffer(&mut self, index: usize) {
+        if index == self.current_buffer || index >= self.buffer_count() {
+            return;
+        }
+        self.make_buffer_current(index);
+        self.display_message(format!(
+            "\"{}\" {}/{}",
+            Self::buffer_name(&self.document),
+            index.saturating_add(1),
+            self.buffer_count()
+        ));
+    }
+
+    fn make_buffer_current(&mut self, index: usize) {
+        if index == self.current_buffer {
+            return;
+        }
+        let current = self.take_current_buffer();
+        self.buffers.insert(self.current_buffer, current);
+        let buffer = self.buffers.remove(index);
+        self.current_buffer = index;
+        self.restore_buffer(buffer);
+    }
+
+    /// Return the document of the buffer located at the provided index of the buffer
+    /// list, along with the position of its cursor and viewport
+    fn buffer_state(&self, index: usize) -> Option<(&Document, Position, ViewportOffset)> {
+        let buffer = match index.cmp(&self.current_buffer) {
+            Ordering::Equal => return Some((&self.document, self.cursor_position, self.offset)),
+            Ordering::Less => self.buffers.get(index),
+            Ordering::Greater => self.buffers.get(index.saturating_sub(1)),
+        }?;
+        Some((&buffer.document, buffer.cursor_position, buffer.offset))
+    }
+
+    /// Return the document of the buffer located at the provided index of the buffer
+    /// list, and whether it has unsaved changes
+    fn buffer_document(&self, index: usize) -> Option<(&Document, bool)> {
+        let buffer = match index.cmp(&self.current_buffer) {
+            Ordering::Equal => return Some((&self.document, self.is_dirty())),
+            Ordering::Less => self.buffers.get(index),
+            Ordering::Greater => self.buffers.get(index.saturating_sub(1)),
+        }?;
+        Some((
+            &buffer.document,
+            buffer.last_saved_hash != buffer.document.hashed(),
+        ))
+    }
+
+    /// Split the screen in two panes displaying the current buffer, the top one being
+    /// focused
+    fn split_screen(&mut self) {
+        if self.split.is_some() {
+            self.display_message(utils::red("The screen is already split"));
+            return;
+        }
+        let mut pane = Pane {
+            buffer: self.current_buffer,
+            cursor_position: self.cursor_position,
+            offset: self.offset,
+        };
+        let (top_height, bottom_height) = self.pane_heights();
+        Self::scroll_to_fit(&mut pane.cursor_position, &mut pane.offset, bottom_height);
+        Self::scroll_to_fit(&mut self.cursor_position, &mut self.offset, top_height);
+        self.split = Some(pane);
+        self.top_pane_focused = true;
+    }
+
+    /// Scroll the viewport down until the cursor fits in a pane of the provided height
+    fn scroll_to_fit(cursor_position: &mut Position, offset: &mut ViewportOffset, height: usize) {
+        let last_y = height.saturating_sub(1);
+        if cursor_position.y > last_y {
+            offset.rows = offset
+                .rows
+                .saturating_add(cursor_position.y.saturating_sub(last_y));
+            cursor_position.y = last_y;
+        }
+    }
+
+    /// Give the focus to the top or bottom pane of the split screen
+    fn focus_pane(&mut self, top: bool) {
+        if self.top_pane_focused == top {
+            return;
+        }
+        if let Some(pane) = self.split.take() {
+            self.split = Some(Pane {
+                buffer: self.current_buffer,
+                cursor_position: self.cursor_position,
+                offset: self.offset,
+            });
+            self.display_pane(&pane);
+            self.top_pane_focused = top;
+        }
+    }
+
+    /// Close the focused pane, the other one taking the whole screen
+    fn close_pane(&mut self) {
+        if let Some(pane) = self.split.take() {
+            self.display_pane(&pane);
+            self.top_pane_focused = true;
+        }
+    }
+
+    /// Display the buffer of the pane where its cursor was, in the focused pane
+    fn display_pane(&mut self, pane: &Pane) {
+        self.make_buffer_current(pane.buffer);
+        self.cursor_position = pane.cursor_position;
+        self.offset = pane.offset;
+        // the document might have been edited in the other pane
+        let last_row_index = self.document.line_count().saturating_sub(1);
+        if self.current_row_index() > last_row_index {
+            self.goto_x_y(0, last_row_index);
+        }
+        self.clamp_cursor_to_current_row();
+        self.desired_x = self.current_x_position();
+    }
+
+    /// Return the number of lines of the top pane of the split screen, and of the bottom
+    /// one, which is preceded by the status bar of the top pane.
+    fn pane_heights(&self) -> (usize, usize) {
+        let height = self.terminal.size().height as usize;
+        let top_height = height / 2;
+        (
+            top_height,
+            height.saturating_sub(top_height).saturating_sub(1),
+        )
+    }
+
+    /// Return the index of the first screen line of the focused pane
+    fn focused_pane_top(&self) -> usize {
+        if self.split.is_some() && !self.top_pane_focused {
+            self.pane_heights().0.saturating_add(1)
+        } else {
+            0
+        }
+    }
+
+    /// Return the number of lines of the focused pane
+    fn view_height(&self) -> usize {
+        match (&self.split, self.top_pane_focused) {
+            (None, _) => self.terminal.size().height as usize,
+            (Some(_), true) => self.pane_heights().0,
+            (Some(_), false) => self.pane_heights().1,
+        }
+    }
+
+    /// Return the number of the line located in the middle of the focused pane
+    fn middle_of_view_line_number(&self) -> usize {
+        if self.split.is_some() {
+            self.view_height() / 2
+        } else {
+            self.terminal.middle_of_screen_line_number()
+        }
+    }
+
+    /// Format the buffer list, the current buffer being marked with `%`, and the ones
+    /// with unsaved changes with `+` (eg: `1 "main.rs"  2%+ "editor.rs"`).
+    fn format_buffers(&self) -> String {
+        let mut entries: Vec<(&Document, bool)> = self
+            .buffers
+            .iter()
+            .map(|buffer| {
+                let dirty = buffer.last_saved_hash != buffer.document.hashed();
+                (&buffer.document, dirty)
+            })
+            .collect();
+        entries.insert(self.current_buffer, (&self.document, self.is_dirty()));
+        entries
+            .iter()
+            .enumerate()
+            .map(|(index, (document, dirty))| {
+                format!(
+                    "{}{}{} \"{}\"",
+                    index.saturating_add(1),
+                    if index == self.current_buffer {
+                        "%"
+                    } else {
+                        ""
+                    },
+                    if *dirty { "+" } else { "" },
+                    Self::buffer_name(document)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("  ")
+    }
+
+    fn process_search_command(&mut self, search_pattern: &str) {
+        self.reset_search();
+        self.find_search_matches(search_pattern);
+        self.search_pattern = Some(search_pattern.to_string());
+        self.search_highlighted = true;
+        self.display_message(format!("{} matches", self.search_matches.len()));
+        self.current_search_match_index = self.search_matches.len().saturating_sub(1);
+        self.goto_next_search_match();
+        if self.search_regex(search_pattern, false).is_none() {
+            self.display_message(format!(
+                "Invalid regex, searched for the literal text instead: {} matches",
+                self.search_matches.len()
+            ));
+        }
+    }
+
+    /// Replace the matches of the substitution pattern in the current line, or in the
+    /// whole document, as a single undoable change.
+    fn substitute(&mut self, substitution: &Substitution, range: LineRange) {
+        let Some(regex) = self
+            .search_regex(&substitution.pattern, false)
+            .or_else(|| self.search_regex(&substitution.pattern, true))
+        else {
+            return;
+        };
+        self.document.start_undo_group();
+        let num_replacements = self.document.replace_in_rows(
+            range.start,
+            range.end,
+            &regex,
+            &substitution.replacement,
+            substitution.global,
+        );
+        self.document.end_undo_group();
+        if num_replacements == 0 {
+            self.display_message(utils::red(&format!(
+                "Pattern not found: {}",
+                substitution.pattern
+            )));
+            return;
+        }
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+        self.clamp_cursor_to_current_row();
+        self.display_message(format!("{num_replacements} substitutions"));
+    }
+
+    /// Compile the search pattern into a regex, matching the pattern as a literal text if
+    /// requested. The case is ignored with `:set ic`, or if the pattern contains `\c`.
+    fn search_regex(&self, search_pattern: &str, literal: bool) -> Option<Regex> {
+        let ignore_case = self.config.ignore_case || search_pattern.contains("\\c");
+        let pattern = search_pattern.replace("\\c", "");
+        let pattern = if literal {
+            regex::escape(&pattern)
+        } else {
+            pattern
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(ignore_case)
+            .build()
+            .ok()
+    }
+
+    /// Populate the search matches with the positions of all the pattern occurrences in
+    /// the document. The pattern is a regex, or a literal text if it isn't a valid regex.
+    fn find_search_matches(&mut self, search_pattern: &str) {
+        self.search_matches = vec![];
+        self.search_document_state = self.document_state();
+        let Some(regex) = self
+            .search_regex(search_pattern, false)
+            .or_else(|| self.search_regex(search_pattern, true))
+        else {
+            return;
+        };
+        for (row_index, row) in self.document.iter().enumerate() {
+            for (start, end) in row.find_all(&regex) {
+                let match_start = Position {
+                    x: start,
+                    y: row_index,
+                };
+                let match_end = Position {
+                    x: end,
+                    y: row_index,
+                };
+                self.search_matches.push((match_start, match_end));
+            }
+        }
+    }
+
+    /// Recompute the search matches if the document was edited since they were found,
+    /// as their positions might not point to the matching text anymore.
+    fn refresh_stale_search_matches(&mut self) {
+        if self.search_document_state != self.document_state() {
+            self.recompute_search_matches();
+        }
+    }
+
+    /// Return the edit count and the undo state of the document, either of which changes
+    /// when its content does (undoing and redoing changes not being counted as edits)
+    fn document_state(&self) -> (usize, usize) {
+        (self.document.edit_count(), self.document.undo_state())
+    }
+
+    /// Find the matches of the current search pattern again, keeping the current match
+    /// index within bounds.
+    fn recompute_search_matches(&mut self) {
+        if let Some(search_pattern) = self.search_pattern.clone() {
+            self.find_search_matches(&search_pattern);
+            self.current_search_match_index = cmp::min(
+                self.current_search_match_index,
+                self.search_matches.len().saturating_sub(1),
+            );
+        }
+    }
+
+    fn reset_search(&mut self) {
+        self.search_matches = vec![]; // erase previous search matches
+        self.current_search_match_index = 0;
+        self.search_pattern = None;
+    }
+
+    fn revert_to_main_screen(&mut self) {
+        self.reset_message();
+        self.alternate_screen = false;
+        self.alternate_screen_text = None;
+        self.alternate_screen_scroll = 0;
+        self.hex_view = false;
+    }
+
+    /// Display a read-only hex dump of the document, or go back to the document
+    /// if it is already displayed.
+    fn toggle_hex_view(&mut self) {
+        if self.hex_view {
+            self.revert_to_main_screen();
+        } else {
+            self.display_in_alternate_screen(utils::hex_dump(&self.document.to_bytes()));
+            self.hex_view = true;
+        }
+    }
+
+    /// Set the mark with the provided name (a lowercase letter) at the cursor position
+    fn set_mark(&mut self, name: char) {
+        if name.is_ascii_lowercase() {
+            let position = Position {
+                x: self.current_x_position(),
+                y: self.current_row_index(),
+            };
+            self.marks.insert(name, position);
+        } else {
+            self.display_message(utils::red("Marks are named with a letter from a to z"));
+        }
+    }
+
+    /// Go to the position of the mark with the provided name, or as close as possible
+    /// to it if its line got shorter.
+    fn goto_mark(&mut self, name: char) {
+        let Some(position) = self.marks.get(&name).copied() else {
+            self.display_message(utils::red(&format!("Mark {name} is not set")));
+            return;
+        };
+        let y = cmp::min(position.y, self.document.line_count().saturating_sub(1));
+        let x = cmp::min(
+            position.x,
+            self.get_row(y).map_or(0, Row::len).saturating_sub(1),
+        );
+        self.record_jump();
+        self.goto_x_y(x, y);
+    }
+
+    /// Return a table of the marks, sorted by name, with their position and a preview
+    /// of the marked line.
+    fn format_marks(&self) -> String {
+        let mut marks: Vec<(&char, &Position)> = self.marks.iter().collect();
+        marks.sort_by_key(|(name, _)| **name);
+        let mut lines = vec![String::from("mark  line  col text")];
+        for (name, position) in marks {
+            let preview: String = self
+                .get_row(position.y)
+                .map_or("", |row| row.string.trim())
+                .chars()
+                .take(MARK_PREVIEW_LENGTH)
+                .collect();
+            let line = format!(
+                " {name} {:>6} {:>4} {preview}",
+                position.y.saturating_add(1),
+                position.x
+            );
+            lines.push(line.trim_end().to_string());
+        }
+        lines.join("\n")
+    }
+
+    /// Return the jump list, from the oldest to the newest jump, each jump being numbered
+    /// by its distance to the current position, marked with a `>`. Jumps to lines that
+    /// don't exist anymore are skipped.
+    fn format_jumps(&self) -> String {
+        let mut lines = vec![String::from(" jump line  col text")];
+        for (index, position) in self.jump_list.iter().enumerate() {
+            let Some(row) = self.get_row(position.y) else {
+                continue;
+            };
+            let marker = if index == self.jump_index { '>' } else { ' ' };
+            let preview: String = row
+                .string
+                .trim()
+                .chars()
+                .take(MARK_PREVIEW_LENGTH)
+                .collect();
+            let line = format!(
+                "{marker}{:>4} {:>4} {:>4} {preview}",
+                index.abs_diff(self.jump_index),
+                position.y.saturating_add(1),
+                position.x
+            );
+            lines.push(line.trim_end().to_string());
+        }
+        if self.jump_index == self.jump_list.len() {
+            lines.push(String::from(">"));
+        }
+        lines.join("\n")
+    }
+
+    /// Record the current position in the jump list, before jumping somewhere else.
+    /// A line appears only once in the jump list.
+    fn record_jump(&mut self) {
+        let position = Position {
+            x: self.current_x_position(),
+            y: self.current_row_index(),
+        };
+        self.jump_list.retain(|jump| jump.y != position.y);
+        self.jump_list.push(position);
+        if self.jump_list.len() > JUMP_LIST_MAX_LENGTH {
+            self.jump_list.remove(0);
+        }
+        self.jump_index = self.jump_list.len();
+    }
+
+    /// Go back to the position the cursor last jumped from
+    fn goto_older_jump(&mut self) {
+        if self.jump_index == 0 {
+            return;
+        }
+        if self.jump_index == self.jump_list.len() {
+            // record the current position, to be able to come back to it with Ctrl-I
+            self.record_jump();
+            self.jump_index = self.jump_list.len().saturating_sub(1);
+        }
+        self.jump_index = self.jump_index.saturating_sub(1);
+        self.goto_jump(self.jump_index);
+    }
+
+    /// Go forward to the position the cursor jumped back from with Ctrl-O
+    fn goto_newer_jump(&mut self) {
+        if self.jump_index.saturating_add(1) >= self.jump_list.len() {
+            return;
+        }
+        self.jump_index = self.jump_index.saturating_add(1);
+        self.goto_jump(self.jump_index);
+    }
+
+    fn goto_jump(&mut self, index: usize) {
+        if let Some(position) = self.jump_list.get(index).copied() {
+            let y = cmp::min(position.y, self.document.line_count().saturating_sub(1));
+            self.goto_x_y(position.x, y);
+            self.clamp_cursor_to_current_row();
+        }
+    }
+
+    /// Display the provided text in a scrollable view, in the alternate screen
+    fn display_in_alternate_screen(&mut self, text: String) {
+        self.tag_choices.clear();
+        self.alternate_screen = true;
+        self.alternate_screen_text = Some(text);
+        self.alternate_screen_scroll = 0;
+    }
+
+    /// Process a command issued while some text is displayed in the alternate screen
+    fn process_alternate_screen_command(&mut self, key: Key) {
+        let num_lines = self
+            .alternate_screen_text
+            .as_ref()
+            .map_or(0, |text| text.lines().count());
+        let max_scroll = num_lines.saturating_sub(self.text_area_height());
+        match key {
+            Key::Char('q') | Key::Esc => self.revert_to_main_screen(),
+            Key::Char('j') | Key::Down => {
+                self.alternate_screen_scroll =
+                    cmp::min(self.alternate_screen_scroll.saturating_add(1), max_scroll);
+            }
+            Key::Char('k') | Key::Up => {
+                self.alternate_screen_scroll = self.alternate_screen_scroll.saturating_sub(1);
+            }
+            Key::Char('g') => self.alternate_screen_scroll = 0,
+            Key::Char('G') => self.alternate_screen_scroll = max_scroll,
+            Key::Char(':') => self.start_receiving_command(),
+            Key::Char(c @ '1'..='9') if !self.tag_choices.is_empty() => {
+                let index = c.to_digit(10).map_or(0, |digit| digit as usize - 1);
+                if let Some(tag) = self.tag_choices.get(index).cloned() {
+                    self.revert_to_main_screen();
+                    self.goto_tag(&tag);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Jump to the definition of the tag with the provided name, found in the tags file
+    /// of the document directory (or of the current directory), or of one of their
+    /// parents up to the project root. When several tags have that name, they're listed
+    /// for the user to pick one.
+    fn jump_to_tag(&mut self, name: &str) {
+        if name.is_empty() {
+            self.display_message(utils::red("No tag name"));
+            return;
+        }
+        let document_directory = self.document.filename.as_ref().and_then(|filename| {
+            self.current_dir
+                .join(filename)
+                .parent()
+                .map(Path::to_path_buf)
+        });
+        let tags_file = document_directory
+            .and_then(|directory| Tag::find_file(&directory))
+            .or_else(|| Tag::find_file(&self.current_dir));
+        let Some(Ok(tags)) = tags_file.map(|tags_file| Tag::load(&tags_file)) else {
+            self.display_message(utils::red("No tags file found"));
+            return;
+        };
+        let mut matching_tags: Vec<Tag> = tags.into_iter().filter(|tag| tag.name == name).collect();
+        match matching_tags.len() {
+            0 => self.display_message(utils::red(&format!("Tag not found: {name}"))),
+            1 => self.goto_tag(&matching_tags[0]),
+            _ => {
+                matching_tags.truncate(MAX_TAG_CHOICES);
+                let mut lines = vec![format!(
+                    "Press the number of the \"{name}\" tag to jump to:"
+                )];
+                for (index, tag) in matching_tags.iter().enumerate() {
+                    let location = match &tag.address {
+                        TagAddress::Line(line_number) => format!("line {line_number}"),
+                        TagAddress::Pattern { text, .. } => text.trim().to_string(),
+                    };
+                    lines.push(format!(
+                        "{:>2} {} {location}",
+                        index.saturating_add(1),
+                        tag.filename.display()
+                    ));
+                }
+                self.display_in_alternate_screen(lines.join("\n"));
+                self.tag_choices = matching_tags;
+            }
+        }
+    }
+
+    /// Open the file of the tag, and move the cursor to its definition. The position
+    /// the cursor jumped from is pushed onto the tag stack.
+    fn goto_tag(&mut self, tag: &Tag) {
+        let origin = self.document.filename.clone().map(|filename| {
+            let position = Position {
+                x: self.current_x_position(),
+                y: self.current_row_index(),
+            };
+            (filename, position)
+        });
+        self.open_file(&tag.filename.to_string_lossy());
+        // the tag file name may be written differently (eg: relative to the tags file)
+        let tag_file = fs::canonicalize(&tag.filename).ok();
+        let opened_file = self
+            .document
+            .filename
+            .as_ref()
+            .and_then(|filename| fs::canonicalize(filename).ok());
+        if tag_file.is_none() || opened_file != tag_file {
+            return;
+        }
+        self.tag_stack.extend(origin);
+        match tag.find_row_index(&self.document) {
+            Some(row_index) => self.goto_x_y(0, row_index),
+            None => self.display_message(utils::red(&format!(
+                "Couldn't find the definition of {} in the file",
+                tag.name
+            ))),
+        }
+    }
+
+    /// Go back to where the cursor was before the last jump to a tag (Ctrl-T)
+    fn pop_tag_stack(&mut self) {
+        let Some((filename, position)) = self.tag_stack.pop() else {
+            self.display_message(utils::red("The tag stack is empty"));
+            return;
+        };
+        self.open_file(&filename.to_string_lossy());
+        if self.document.filename.as_ref() == Some(&filename) {
+            self.goto_document_position(position.y, position.x);
+        }
+    }
+
+    /// Return the number of terminal lines in which text can be displayed
+    fn text_area_height(&self) -> usize {
+        (self.terminal.size().height as usize).saturating_sub(2)
+    }
+
+    /// Run the keyword program (eg: `man`) on the word under the cursor, and display
+    /// its output in the alternate screen.
+    fn lookup_word_under_cursor(&mut self) {
+        let Some(word) = self.word_under_cursor() else {
+            return;
+        };
+        let keyword_program = self
+            .config
+            .keyword_program_for(self.document.extension())
+            .to_string();
+        match utils::run_command_template(&keyword_program, &word) {
+            Ok(output) => self.display_in_alternate_screen(output),
+            Err(error) => self.display_message(utils::red(&error)),
+        }
+    }
+
+    /// Process navigation command issued in normal mode, that will
+    /// resolve in having the cursor be moved around the document.
+    ///
+    /// Note: some commands are accumulative (ie: 2j will move the
+    /// cursor down twice) and some are not (ie: gg will move the cursor
+    /// to the start of the document only once).
+    /// A buffer is maintained for the accumulative commands, and is purged
+    /// when the last char of the command is received. For now, only commans
+    /// of the form <number>*<char> are supported and I'm not sure I'm
+    /// planning to support anything more complex than that.
+    fn process_normal_command(&mut self, key: Key) {
+        if !self.pending_normal_command.is_empty() {
+            let prefix = std::mem::take(&mut self.pending_normal_command);
+            self.process_prefixed_normal_command(&prefix, key);
+            return;
+        }
+        if key == Key::Esc {
+            self.reset_message();
+            self.selected_register = None;
+            // with hlsearch on, the search matches stay highlighted until :noh
+            if !self.config.hlsearch {
+                self.reset_search();
+            }
+        }
+        match key {
+            Key::Ctrl('r') => self.redo(),
+            Key::Ctrl('o') => self.goto_older_jump(),
+            // Ctrl-] is read as Ctrl-5 by termion, both sending the same byte
+            Key::Ctrl(']' | '5') => {
+                if let Some(word) = self.word_under_cursor() {
+                    self.jump_to_tag(&word);
+                }
+            }
+            Key::Ctrl('t') => self.pop_tag_stack(),
+            Key::Ctrl('g') => self.display_message(self.file_info()),
+            Key::Ctrl('w') => self.pending_normal_command = String::from("^W"),
+            Key::Ctrl('d') => {
+                let lines = self.scroll_amount();
+                self.scroll_by(&Direction::Down, lines);
+            }
+            Key::Ctrl('u') => {
+                let lines = self.scroll_amount();
+                self.scroll_by(&Direction::Up, lines);
+            }
+            Key::Ctrl('f') => self.scroll_by(&Direction::Down, self.view_height()),
+            Key::Ctrl('b') => self.scroll_by(&Direction::Up, self.view_height()),
+            Key::Home => self.goto_line_start(),
+            Key::Alt('j') => self.move_lines(&Direction::Down),
+            Key::Alt('k') => self.move_lines(&Direction::Up),
+            _ => (),
+        }
+        if let Key::Char(c) = key {
+            match c {
+                '0' => {
+                    if self.normal_command_buffer.is_empty() {
+                        self.goto_line_start();
+                    } else {
+                        self.normal_command_buffer.push(c.to_string());
+                    }
+                }
+                '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => {
+                    self.normal_command_buffer.push(c.to_string());
+                }
+                'i' => self.enter_insert_mode(),
+                'R' => self.enter_replace_mode(),
+                'v' => self.enter_visual_mode(Position {
+                    x: self.current_x_position(),
+                    y: self.current_row_index(),
+                }),
+                ':' => self.start_receiving_command(),
+                '/' => self.start_receiving_search_pattern(),
+                'G' => self.goto_start_or_end_of_document(&Boundary::End),
+                '$' => {
+                    self.goto_start_or_end_of_line(&Boundary::End);
+                    self.desired_x = usize::MAX;
+                }
+                '^' => self.goto_first_non_whitespace(),
+                'M' => {
+                    // M ignores any count
+                    self.pop_normal_command_repetitions();
+                    self.goto_middle_of_terminal();
+                }
+                '%' if self.normal_command_buffer.is_empty() => {
+                    self.goto_matching_closing_symbol();
+                }
+                'n' => self.goto_next_search_match(),
+                'N' => self.goto_previous_search_match(),
+                'q' => self.revert_to_main_screen(),
+                'x' => self.delete_current_grapheme(),
+                'u' => self.undo(),
+                // Ctrl-I and Tab can't be told apart
+                '\t' => self.goto_newer_jump(),
+                'p' | 'P' => {
+                    let times = self.pop_normal_command_repetitions();
+                    self.paste(c == 'p', times);
+                }
+                'o' => self.insert_newline_after_current_line(),
+                'O' => self.insert_newline_before_current_line(),
+                'A' => self.append_to_line(),
+                'C' => self.apply_operator_to_motion("c", '$'),
+                'D' => self.apply_operator_to_motion("d", '$'),
+                'J' => self.join_current_line_with_next_one(),
+                'K' => self.lookup_word_under_cursor(),
+                'g' | 'z' | 'd' | 'c' | 'y' | '"' | 'f' | 'F' | 't' | 'T' | 'm' | '`' | 'r' => {
+                    self.pending_normal_command = c.to_string();
+                }
+                _ => {
+                    // at that point, we've iterated over all non accumulative commands
+                    // meaning the command we're processing is an accumulative one.
+                    // we thus pop the repeater value from self.normal_command_buffer
+                    // and we use that value as the number of times the comamnd identified
+                    // by the `c` char must be repeated.
+                    let times = self.pop_normal_command_repetitions();
+                    self.process_normal_command_n_times(c, times);
+                }
+            }
+        };
+    }
+
+    /// Process the next key of a multi-keys normal command (eg: `zz` or `dis`), the
+    /// previous ones being the provided prefix. Any unexpected key cancels the command.
+    fn process_prefixed_normal_command(&mut self, prefix: &str, key: Key) {
+        match (prefix, key) {
+            ("g", Key::Char('g')) => self.goto_start_or_end_of_document(&Boundary::Start),
+            ("g", Key::Char('i')) => self.insert_at_last_insert_position(),
+            ("g", Key::Char('s')) => self.insert_date(""),
+            ("g", Key::Char(c @ ('-' | '+'))) => self.goto_chronological_undo_state(c == '-'),
+            ("g", Key::Char(c @ ('e' | 'E'))) => {
+                let times = self.pop_normal_command_repetitions();
+                self.goto_end_of_previous_word(c == 'E', times);
+            }
+            ("^W", Key::Char('k') | Key::Up) => self.focus_pane(true),
+            ("^W", Key::Char('j') | Key::Down) => self.focus_pane(false),
+            // without a split screen, the buffers are cycled through instead of the panes
+            ("^W", Key::Char('w') | Key::Ctrl('w')) if self.split.is_none() => {
+                self.switch_to_next_buffer();
+            }
+            ("^W", Key::Char('w') | Key::Ctrl('w')) => self.focus_pane(!self.top_pane_focused),
+            ("^W", Key::Char('s' | 'S') | Key::Ctrl('s')) => self.split_screen(),
+            ("^W", Key::Char('q') | Key::Ctrl('q')) => self.quit(false),
+            ("^W", Key::Char('v') | Key::Ctrl('v')) => self.display_message(utils::red(
+                "Vertical splits aren't supported yet, use Ctrl-W s to split horizontally",
+            )),
+            ("^W", Key::Char('h' | 'l') | Key::Left | Key::Right) => self
+                .display_message(utils::red(
+                "There is no pane on the left or right, as the screen is only split horizontally",
+            )),
+            ("z", Key::Char('t')) => self.reposition_view(&ViewPosition::Top),
+            ("z", Key::Char('z')) => self.reposition_view(&ViewPosition::Center),
+            ("z", Key::Char('b')) => self.reposition_view(&ViewPosition::Bottom),
+            ("d", Key::Char('d')) => {
+                let times = self.pop_normal_command_repetitions();
+                if times > 1 {
+                    let start = self.current_row_index();
+                    let end = cmp::min(
+                        start.saturating_add(times).saturating_sub(1),
+                        self.document.line_count().saturating_sub(1),
+                    );
+                    self.delete_lines(LineRange { start, end });
+                } else {
+                    self.delete_current_line();
+                }
+            }
+            ("\"", Key::Char(c)) if Registers::is_valid_name(c) => self.selected_register = Some(c),
+            ("r", Key::Char(c)) => {
+                let times = self.pop_normal_command_repetitions();
+                self.replace_graphemes(c, times);
+            }
+            ("m", Key::Char(c)) => self.set_mark(c),
+            ("`", Key::Char(c)) => self.goto_mark(c),
+            ("y", Key::Char('y')) => {
+                let times = self.pop_normal_command_repetitions();
+                self.yank_lines(times);
+            }
+            ("f" | "F" | "t" | "T", Key::Char(target)) => {
+                let times = self.pop_normal_command_repetitions();
+                let motion = prefix.chars().next().unwrap_or_default();
+                self.last_find_char = Some((motion, target));
+                if let Some(x) = self.find_char_motion_target(motion, target, times) {
+                    self.goto_x_y(x, self.current_row_index());
+                }
+            }
+            // a count can be typed between the operator and its motion (eg: `d2f,`)
+            ("d" | "c" | "y", Key::Char(c))
+                if c.is_ascii_digit() && (c != '0' || !self.normal_command_buffer.is_empty()) =>
+            {
+                self.normal_command_buffer.push(c.to_string());
+                self.pending_normal_command = prefix.to_string();
+            }
+            (
+                "df" | "dF" | "dt" | "dT" | "cf" | "cF" | "ct" | "cT" | "yf" | "yF" | "yt" | "yT",
+                Key::Char(target),
+            ) => self.apply_operator_to_find_char_motion(prefix, target),
+            ("c", Key::Char('c')) => self.change_lines(),
+            ("d" | "c" | "y", Key::Char('%')) => self.apply_operator_to_matching_symbol(prefix),
+            ("d" | "c" | "y", Key::Char(motion @ ('w' | 'b' | '$' | '0'))) => {
+                self.apply_operator_to_motion(prefix, motion);
+            }
+            // inner/around text objects (eg: `dis`) and find-char motions (eg: `dt)`)
+            ("d" | "c" | "y", Key::Char(c @ ('i' | 'a' | 'f' | 'F' | 't' | 'T'))) => {
+                self.pending_normal_command = format!("{prefix}{c}");
+            }
+            ("di" | "da" | "ci" | "ca" | "yi" | "ya", Key::Char(c))
+                if Navigator::brackets_of_text_object(c).is_some() =>
+            {
+                self.apply_operator_to_brackets(prefix, c);
+            }
+            ("di" | "da" | "ci" | "ca" | "yi" | "ya", Key::Char(quote @ ('"' | '\'' | '`'))) => {
+                self.apply_operator_to_quotes(prefix, quote);
+            }
+            ("di" | "da" | "ci" | "ca" | "yi" | "ya", Key::Char('s')) => {
+                let (start, end) = Navigator::find_boundaries_of_sentence_under_cursor(
+                    &self.document,
+                    &Position {
+                        x: self.current_x_position(),
+                        y: self.current_row_index(),
+                    },
+                    prefix.ends_with('a'),
+                );
+                self.apply_operator(prefix.chars().next().unwrap_or_default(), &start, &end);
+            }
+            _ => (),
+        }
+    }
+
+    /// Apply the operator to the text located inside (or around) the brackets selected by
+    /// the text object key (eg: `ci(`), which can be located after the cursor on its line.
+    fn apply_operator_to_brackets(&mut self, prefix: &str, key: char) {
+        let Some(brackets) = Navigator::brackets_of_text_object(key) else {
+            return;
+        };
+        if let Some((start, end)) = Navigator::find_boundaries_of_brackets_around_cursor(
+            &self.document,
+            &Position {
+                x: self.current_x_position(),
+                y: self.current_row_index(),
+            },
+            brackets,
+            prefix.ends_with('a'),
+        ) {
+            self.apply_operator(prefix.chars().next().unwrap_or_default(), &start, &end);
+        } else {
+            self.display_message(utils::red(&format!(
+                "No {}{} pair found around the cursor",
+                brackets.0, brackets.1
+            )));
+        }
+    }
+
+    /// Apply the operator to the text located inside (or around) the quotes of the
+    /// current line selected by the text object key (eg: `ci"`).
+    fn apply_operator_to_quotes(&mut self, prefix: &str, quote: char) {
+        let y = self.current_row_index();
+        if let Some((start, end)) = Navigator::find_boundaries_of_quotes_in_row(
+            self.current_row(),
+            self.current_x_position(),
+            &quote.to_string(),
+            prefix.ends_with('a'),
+        ) {
+            self.apply_operator(
+                prefix.chars().next().unwrap_or_default(),
+                &Position { x: start, y },
+                &Position { x: end, y },
+            );
+        } else {
+            self.display_message(utils::red(&format!(
+                "No {quote}{quote} pair found on the line"
+            )));
+        }
+    }
+
+    /// Return the x index the `f`, `F`, `t` or `T` motion leads to on the current line,
+    /// if the nth occurrence of the target character is found.
+    fn find_char_motion_target(&self, motion: char, target: char, times: usize) -> Option<usize> {
+        let forward = matches!(motion, 'f' | 't');
+        let index = Navigator::find_char_in_row(
+            self.current_row(),
+            self.current_x_position(),
+            target,
+            forward,
+            times,
+        )?;
+        Some(match motion {
+            't' => index.saturating_sub(1),
+            'T' => index.saturating_add(1),
+            _ => index,
+        })
+    }
+
+    /// Repeat the last find-char motion (`;`), or repeat it in the opposite direction
+    /// (`,`). With `find_across_lines`, the search continues on the following (or
+    /// preceding) lines when the character isn't found on the current one.
+    fn repeat_last_find_char(&mut self, reverse: bool, times: usize) {
+        let Some((motion, target)) = self.last_find_char else {
+            return;
+        };
+        let motion = match (motion, reverse) {
+            ('f', true) => 'F',
+            ('F', true) => 'f',
+            ('t', true) => 'T',
+            ('T', true) => 't',
+            (motion, _) => motion,
+        };
+        let forward = matches!(motion, 'f' | 't');
+        let x = self.current_x_position();
+        let y = self.current_row_index();
+        // skip the character next to the cursor, that `t` and `T` already stopped before
+        let from_x = match motion {
+            't' => x.saturating_add(1),
+            'T' => x.saturating_sub(1),
+            _ => x,
+        };
+        let found = if self.config.find_across_lines {
+            Navigator::find_char_in_document(
+                &self.document,
+                &Position { x: from_x, y },
+                target,
+                forward,
+                times,
+            )
+        } else {
+            Navigator::find_char_in_row(self.current_row(), from_x, target, forward, times)
+                .map(|x| Position { x, y })
+        };
+        if let Some(position) = found {
+            let x = match motion {
+                't' => position.x.saturating_sub(1),
+                'T' => position.x.saturating_add(1),
+                _ => position.x,
+            };
+            self.goto_x_y(x, position.y);
+        }
+    }
+
+    /// Apply the operator to the text spanning from the cursor to the target of the
+    /// find-char motion (eg: `dt)`, `df"`). The backward motions exclude the character
+    /// under the cursor, while the forward ones include the character they lead to.
+    fn apply_operator_to_find_char_motion(&mut self, prefix: &str, target: char) {
+        let mut chars = prefix.chars();
+        let (Some(operator), Some(motion)) = (chars.next(), chars.next()) else {
+            return;
+        };
+        let times = self.pop_normal_command_repetitions();
+        self.last_find_char = Some((motion, target));
+        let Some(target_x) = self.find_char_motion_target(motion, target, times) else {
+            return;
+        };
+        let x = self.current_x_position();
+        let y = self.current_row_index();
+        let (start, end) = if matches!(motion, 'f' | 't') {
+            (x, target_x.saturating_add(1))
+        } else {
+            (target_x, x)
+        };
+        self.apply_operator(operator, &Position { x: start, y }, &Position { x: end, y });
+    }
+
+    /// Apply the operator to the text spanning from the cursor to where the motion
+    /// leads on the current line (eg: `dw`, `d3b`, `d$`). A forward word motion which
+    /// doesn't find any next word on the line goes to its end.
+    fn apply_operator_to_motion(&mut self, operator: &str, motion: char) {
+        let times = self.pop_normal_command_repetitions();
+        let x = self.current_x_position();
+        let y = self.current_row_index();
+        let row = self.current_row();
+        let (start, end) = match motion {
+            'w' => {
+                let mut end = x;
+                for _ in 0..times {
+                    let next =
+                        Navigator::find_index_of_next_or_previous_word(row, end, &Boundary::End);
+                    let found = next > end
+                        && Navigator::is_word_delimiter(
+                            row.nth_char(next.saturating_sub(1)),
+                            row.nth_char(next),
+                        );
+                    if !found {
+                        end = row.len();
+                        break;
+                    }
+                    end = next;
+                }
+                // like `ce`, `cw` keeps the whitespace following the word
+                if operator == "c" && !row.nth_char(x).is_whitespace() {
+                    while end > x && row.nth_char(end.saturating_sub(1)).is_whitespace() {
+                        end = end.saturating_sub(1);
+                    }
+                }
+                (x, end)
+            }
+            'b' => {
+                let mut start = x;
+                for _ in 0..times {
+                    start = Navigator::find_index_of_next_or_previous_word(
+                        row,
+                        start,
+                        &Boundary::Start,
+                    );
+                }
+                (start, x)
+            }
+            '$' => (x, row.len()),
+            _ => (0, x),
+        };
+        if start == end {
+            return;
+        }
+        self.apply_operator(
+            operator.chars().next().unwrap_or_default(),
+            &Position { x: start, y },
+            &Position { x: end, y },
+        );
+    }
+
+    /// Apply the provided operator (`d`elete, `c`hange or `y`ank) to the text located
+    /// between the start (inclusive) and end (exclusive) document positions.
+    fn apply_operator(&mut self, operator: char, start: &Position, end: &Position) {
+        let register = Register::charwise(self.document.text_in_range(start, end));
+        if operator == 'y' {
+            self.registers.yank(self.selected_register.take(), register);
+            self.goto_x_y(start.x, start.y);
+            return;
+        }
+        self.registers
+            .delete(self.selected_register.take(), register);
+        if operator == 'c' {
+            // the deletion and the text typed afterwards are undone together
+            self.document.start_undo_group();
+        }
+        self.document.delete_range(start, end);
+        self.goto_x_y(start.x, start.y);
+        if operator == 'c' {
+            self.enter_insert_mode();
+        } else {
+            self.clamp_cursor_to_current_row();
+        }
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+    }
+
+    /// Process a command issued when the editor is in visual mode. Movement commands
+    /// extend the selection, and Esc cancels it.
+    fn process_visual_command(&mut self, key: Key) {
+        match key {
+            Key::Esc | Key::Char('v') => self.leave_visual_mode(),
+            Key::Char('d' | 'x') => self.apply_operator_to_selection('d'),
+            Key::Char('y') => self.apply_operator_to_selection('y'),
+            Key::Alt('j') => self.move_lines(&Direction::Down),
+            Key::Alt('k') => self.move_lines(&Direction::Up),
+            Key::Char(
+                '0'..='9'
+                | 'h'
+                | 'j'
+                | 'k'
+                | 'l'
+                | 'w'
+                | 'b'
+                | '$'
+                | '^'
+                | 'g'
+                | 'e'
+                | 'E'
+                | 'G'
+                | 'H'
+                | 'M'
+                | 'L'
+                | '{'
+                | '}'
+                | '('
+                | ')'
+                | '%',
+            ) => self.process_normal_command(key),
+            _ => (),
+        }
+    }
+
+    /// Apply the provided operator to the whole visual selection, and go back to normal mode
+    fn apply_operator_to_selection(&mut self, operator: char) {
+        if let Some((start, end)) = self.selection_bounds() {
+            // the selection end is inclusive, while the operator end is not
+            let end_row_len = self.get_row(end.y).map_or(0, Row::len);
+            let end = Position {
+                x: cmp::min(end.x.saturating_add(1), end_row_len),
+                y: end.y,
+            };
+            self.apply_operator(operator, &start, &end);
+        }
+        self.leave_visual_mode();
+    }
+
+    /// Execute the provided normal movement command n timess
+    fn process_normal_command_n_times(&mut self, c: char, n: usize) {
+        match c {
+            'b' => self.goto_start_or_end_of_word(&Boundary::Start, n),
+            'w' => self.goto_start_or_end_of_word(&Boundary::End, n),
+            'h' => self.move_cursor(&Direction::Left, n),
+            'j' => self.move_cursor(&Direction::Down, n),
             'k' => self.move_cursor(&Direction::Up, n),
             'l' => self.move_cursor(&Direction::Right, n),
             '}' => self.goto_start_or_end_of_paragraph(&Boundary::End, n),
             '{' => self.goto_start_or_end_of_paragraph(&Boundary::Start, n),
+            'H' => self.goto_first_line_of_terminal(n),
+            'L' => self.goto_last_line_of_terminal(n),
+            ')' => self.goto_start_of_next_or_previous_sentence(&Boundary::End, n),
+            '(' => self.goto_start_of_next_or_previous_sentence(&Boundary::Start, n),
             '%' => self.goto_percentage_in_document(n),
+            ';' => self.repeat_last_find_char(false, n),
+            ',' => self.repeat_last_find_char(true, n),
             _ => (),
         }
     }
 
     /// Process a command issued when the editor is in normal mode
+    fn process_replace_command(&mut self, pressed_key: Key) {
+        match pressed_key {
+            Key::Esc => {
+                self.enter_normal_mode();
+                return;
+            }
+            Key::Backspace => self.restore_replaced_grapheme(),
+            Key::Char('\n') => {
+                // the line break is inserted, and can't be restored by Backspace
+                self.replaced_graphemes.clear();
+                self.document
+                    .insert_newline(self.current_x_position(), self.current_row_index());
+                self.goto_x_y(0, self.current_row_index().saturating_add(1));
+            }
+            Key::Char('\t') if self.expands_tab() => {
+                for _ in 0..self.indent_width() {
+                    self.replace_grapheme_under_cursor(" ");
+                }
+            }
+            Key::Char(c) => self.replace_grapheme_under_cursor(&c.to_string()),
+            _ => (),
+        }
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+        if self.unsaved_edits >= SWAP_SAVE_EVERY {
+            self.save_to_swap_file();
+        }
+    }
+
+    /// Overwrite the grapheme under the cursor (extending the line when the cursor is
+    /// located after its end), and move the cursor to the next one.
+    fn replace_grapheme_under_cursor(&mut self, grapheme: &str) {
+        let replaced = self.document.replace_grapheme(
+            grapheme,
+            self.current_x_position(),
+            self.current_row_index(),
+        );
+        self.replaced_graphemes.push(replaced);
+        self.move_cursor(&Direction::Right, 1);
+    }
+
+    /// Move the cursor back to the previous grapheme, restoring it if it was overwritten
+    /// in replace mode.
+    fn restore_replaced_grapheme(&mut self) {
+        if self.current_x_position() == 0 {
+            return;
+        }
+        let x = self.current_x_position().saturating_sub(1);
+        match self.replaced_graphemes.pop() {
+            Some(Some(original)) => {
+                self.document
+                    .replace_grapheme(&original, x, self.current_row_index());
+            }
+            Some(None) => {
+                self.document
+                    .delete(x, self.current_x_position(), self.current_row_index());
+            }
+            None => (),
+        }
+        self.move_cursor(&Direction::Left, 1);
+    }
+
+    /// Return the indentation detected in the document, if `detect_indent` is enabled
+    fn detected_indentation(&self) -> Option<Indentation> {
+        self.config
+            .detect_indent
+            .then(|| self.document.indentation())
+            .flatten()
+    }
+
+    /// Return whether Tab inserts spaces, rather than a literal tab (always kept in binary mode)
+    fn expands_tab(&self) -> bool {
+        let expand_tab = match self.detected_indentation() {
+            Some(Indentation::Spaces(_)) => true,
+            Some(Indentation::Tabs) => false,
+            None => self.config.expand_tab,
+        };
+        expand_tab && !self.document.is_binary()
+    }
+
+    /// Return the number of spaces inserted by Tab when it is expanded
+    fn indent_width(&self) -> usize {
+        match self.detected_indentation() {
+            Some(Indentation::Spaces(width)) => width,
+            _ => self.config.tab_width,
+        }
+    }
+
+    /// Go back to where insert mode was last left, and enter insert mode again
+    fn insert_at_last_insert_position(&mut self) {
+        let position = self.last_insert_position.unwrap_or(Position {
+            x: self.current_x_position(),
+            y: self.current_row_index(),
+        });
+        self.enter_insert_mode();
+        let y = cmp::min(position.y, self.document.line_count().saturating_sub(1));
+        self.goto_x_y(position.x, y);
+    }
+
+    /// Accumulate the characters of a digraph typed after Ctrl-K, and insert the associated
+    /// character once both are typed (or the second one, if the digraph is unknown).
+    fn process_digraph_key(&mut self, pressed_key: Key) {
+        let Key::Char(c) = pressed_key else {
+            self.digraph_keys = None;
+            return;
+        };
+        let keys = self.digraph_keys.get_or_insert_with(Vec::new);
+        keys.push(c);
+        if let [first, second] = keys[..] {
+            self.digraph_keys = None;
+            self.insert_char(digraph::digraph(first, second).unwrap_or(second));
+        }
+    }
+
+    /// Accumulate the digits of a code point typed after Ctrl-V, and insert the associated
+    /// character once all of them are typed (4 for `u`, 8 for `U` and 3 for a decimal value)
+    /// or a non-digit key is pressed. Any other key following Ctrl-V is inserted literally.
+    fn process_code_point_key(&mut self, pressed_key: Key) {
+        let mut keys = self.code_point_keys.take().unwrap_or_default();
+        let (radix, max_length) = match keys.chars().next() {
+            Some('u') => (16, 5),
+            Some('U') => (16, 9),
+            _ => (10, 3),
+        };
+        match pressed_key {
+            Key::Char(c @ ('u' | 'U')) if keys.is_empty() => {
+                self.code_point_keys = Some(c.to_string());
+            }
+            Key::Char(c) if c.is_digit(radix) => {
+                keys.push(c);
+                if keys.len() == max_length {
+                    self.insert_code_point(&keys);
+                } else {
+                    self.code_point_keys = Some(keys);
+                }
+            }
+            Key::Char(c) if keys.is_empty() => self.insert_char(c),
+            _ => {
+                self.insert_code_point(&keys);
+                self.process_insert_command(pressed_key);
+            }
+        }
+    }
+
+    /// Insert the character of the code point, ignoring invalid ones
+    fn insert_code_point(&mut self, keys: &str) {
+        if let Some(c) = utils::parse_code_point(keys) {
+            self.insert_char(c);
+        }
+    }
+
+    /// Insert the character at the cursor position, and move the cursor after it
+    fn insert_char(&mut self, c: char) {
+        self.document
+            .insert(c, self.current_x_position(), self.current_row_index());
+        self.move_cursor(&Direction::Right, 1);
+    }
+
+    /// Return the number of graphemes deleted by Backspace in insert mode: a whole soft tab
+    /// when the cursor sits at a tab stop within an indentation made of spaces, and a single
+    /// grapheme otherwise.
+    fn num_graphemes_deleted_by_backspace(&self) -> usize {
+        let x = self.current_x_position();
+        let tab_width = self.indent_width();
+        let in_space_indentation = self
+            .current_row()
+            .graphemes()
+            .take(x)
+            .all(|grapheme| grapheme == " ");
+        if self.expands_tab() && x > 0 && x % tab_width == 0 && in_space_indentation {
+            tab_width
+        } else {
+            1
+        }
+    }
+
     fn process_insert_command(&mut self, pressed_key: Key) {
+        let offset = self.offset;
+        let row_index = self.current_row_index();
+        let line_count = self.document.line_count();
+        self.process_insert_key(pressed_key);
+        // only the edited rows need to be redrawn, unless the view scrolled or the other
+        // rows might be displayed differently
+        let partial_redraw = self.mode == Mode::Insert
+            && self.offset == offset
+            && self.split.is_none()
+            && !self.search_highlighted
+            && line_count > 1;
+        if partial_redraw {
+            let start = cmp::min(row_index, self.current_row_index());
+            // the rows located below a joined or split one are shifted
+            let end = if self.document.line_count() == line_count {
+                start.saturating_add(1)
+            } else {
+                self.offset.rows.saturating_add(self.view_height())
+            };
+            self.changed_rows = Some(start..end);
+        }
+    }
+
+    fn process_insert_key(&mut self, pressed_key: Key) {
+        if self.digraph_keys.is_some() {
+            self.process_digraph_key(pressed_key);
+            return;
+        }
+        if self.code_point_keys.is_some() {
+            self.process_code_point_key(pressed_key);
+            return;
+        }
         match pressed_key {
+            Key::Ctrl('k') => {
+                self.digraph_keys = Some(vec![]);
+                return;
+            }
+            Key::Ctrl('v') => {
+                self.code_point_keys = Some(String::new());
+                return;
+            }
             Key::Esc => {
                 self.enter_normal_mode();
                 return;
             }
+            Key::Home => {
+                self.goto_line_start();
+                return;
+            }
             Key::Backspace => {
                 // When Backspace is pressed on the first column of a line, it means that we
                 // should append the current line with the previous one
@@ -532,26 +3013,36 @@ impl Editor {
                         );
                     }
                 } else {
-                    // Delete previous character
-                    self.document.delete(
-                        self.current_x_position().saturating_sub(1),
-                        self.current_x_position(),
-                        self.current_row_index(),
-                    );
-                    self.move_cursor(&Direction::Left, 1);
+                    // Delete previous character, or the whole soft tab in the indentation
+                    for _ in 0..self.num_graphemes_deleted_by_backspace() {
+                        self.document.delete(
+                            self.current_x_position().saturating_sub(1),
+                            self.current_x_position(),
+                            self.current_row_index(),
+                        );
+                        self.move_cursor(&Direction::Left, 1);
+                    }
                 }
             }
             Key::Char('\n') => {
+                let mut indentation = self.autoindentation();
+                // the comment is only continued when the line is split after its start
+                if self.current_x_position()
+                    > self.current_row().leading_whitespace().chars().count()
+                {
+                    indentation.push_str(&self.comment_continuation());
+                }
                 self.document
                     .insert_newline(self.current_x_position(), self.current_row_index());
-                self.goto_x_y(0, self.current_row_index().saturating_add(1));
+                self.indent_line(&indentation, self.current_row_index().saturating_add(1));
             }
-            Key::Char('\t') => {
-                for _ in 0..SPACES_PER_TAB {
+            Key::Char('\t') if self.expands_tab() => {
+                let indent_width = self.indent_width();
+                for _ in 0..indent_width {
                     self.document
                         .insert(' ', self.current_x_position(), self.current_row_index());
                 }
-                self.move_cursor(&Direction::Right, SPACES_PER_TAB);
+                self.move_cursor(&Direction::Right, indent_width);
             }
             Key::Char(c) => {
                 self.document
@@ -571,63 +3062,350 @@ impl Editor {
         self.document.get_row(index)
     }
 
-    /// Return the index of the row associated to the current cursor position / vertical offset
-    fn current_row_index(&self) -> usize {
-        self.cursor_position.y.saturating_add(self.offset.rows)
+    /// Return the index of the row associated to the current cursor position / vertical offset
+    fn current_row_index(&self) -> usize {
+        self.cursor_position.y.saturating_add(self.offset.rows)
+    }
+
+    fn current_x_position(&self) -> usize {
+        self.cursor_position.x.saturating_add(self.offset.columns)
+    }
+
+    /// Return the character currently under the cursor
+    fn current_grapheme(&self) -> &str {
+        self.current_row().nth_grapheme(self.current_x_position())
+    }
+
+    /// Return the line number associated to the current cursor position / vertical offset
+    fn current_line_number(&self) -> usize {
+        self.current_row_index().saturating_add(1)
+    }
+
+    /// Return the word located under the cursor, if any
+    fn word_under_cursor(&self) -> Option<String> {
+        let (start, end) = Navigator::find_boundaries_of_word_under_cursor(
+            self.current_row(),
+            self.current_x_position(),
+        )?;
+        Some(
+            self.current_row()
+                .graphemes()
+                .skip(start)
+                .take(end.saturating_sub(start).saturating_add(1))
+                .collect(),
+        )
+    }
+
+    /// Return the Row object associated to the current cursor position / vertical offset
+    fn current_row(&self) -> &Row {
+        self.get_row(self.current_row_index()).unwrap()
+    }
+
+    /// Delete the line currently under the cursor
+    fn delete_current_line(&mut self) {
+        let register = Register::linewise(self.current_row().string.clone());
+        self.registers
+            .delete(self.selected_register.take(), register);
+        self.document.delete_row(self.current_row_index());
+        if self.cursor_position.y >= self.document.line_count().saturating_sub(1) {
+            self.goto_line(self.document.line_count(), self.cursor_position.x);
+        } else {
+            self.cursor_position.reset_x();
+        }
+    }
+
+    /// Move the current line (or the selected ones in visual mode) n lines up or down,
+    /// swapping it with the lines it goes past, the cursor following it. The move is
+    /// undone in a single step.
+    fn move_lines(&mut self, direction: &Direction) {
+        let times = self.pop_normal_command_repetitions();
+        let (mut start, mut end) = self.selection_bounds().map_or(
+            (self.current_row_index(), self.current_row_index()),
+            |(start, end)| (start.y, end.y),
+        );
+        let last_row_index = self.document.line_count().saturating_sub(1);
+        let down = matches!(direction, Direction::Down);
+        let shift = if down {
+            cmp::min(times, last_row_index.saturating_sub(end))
+        } else {
+            cmp::min(times, start)
+        };
+        if shift == 0 {
+            return;
+        }
+        self.document.start_undo_group();
+        for _ in 0..shift {
+            if down {
+                for y in (start..=end).rev() {
+                    self.document.swap_rows(y, y.saturating_add(1));
+                }
+                start = start.saturating_add(1);
+                end = end.saturating_add(1);
+            } else {
+                for y in start..=end {
+                    self.document.swap_rows(y.saturating_sub(1), y);
+                }
+                start = start.saturating_sub(1);
+                end = end.saturating_sub(1);
+            }
+        }
+        self.document.end_undo_group();
+        let shifted = |y: usize| {
+            if down {
+                y.saturating_add(shift)
+            } else {
+                y.saturating_sub(shift)
+            }
+        };
+        if let Some(anchor) = self.selection_anchor.as_mut() {
+            anchor.y = shifted(anchor.y);
+        }
+        self.goto_x_y(self.current_x_position(), shifted(self.current_row_index()));
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+    }
+
+    /// Clear the content of n lines, starting from the current one, which are replaced
+    /// by a single line keeping its indentation (with autoindent), and enter insert
+    /// mode at its end (`cc`).
+    fn change_lines(&mut self) {
+        let times = self.pop_normal_command_repetitions();
+        let start = self.current_row_index();
+        let end = cmp::min(
+            start.saturating_add(times).saturating_sub(1),
+            self.document.line_count().saturating_sub(1),
+        );
+        let range = LineRange { start, end };
+        let register = Register::linewise(self.lines_in_range(range).join("\n"));
+        self.registers
+            .delete(self.selected_register.take(), register);
+        let indentation = self.autoindentation();
+        // the deletion and the text typed afterwards are undone together
+        self.document.start_undo_group();
+        self.document
+            .replace_lines(start, end, std::slice::from_ref(&indentation));
+        self.enter_insert_mode();
+        self.goto_x_y(indentation.chars().count(), start);
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+    }
+
+    /// Replace n graphemes, starting from the one under the cursor, by the provided
+    /// character (`r`), without going past the end of the line. A line break replaces
+    /// them all at once, splitting the line.
+    fn replace_graphemes(&mut self, c: char, times: usize) {
+        let x = self.current_x_position();
+        let y = self.current_row_index();
+        let end = cmp::min(x.saturating_add(times), self.current_row().len());
+        if end <= x {
+            return;
+        }
+        self.document.start_undo_group();
+        if c == '\n' {
+            self.document
+                .delete_range(&Position { x, y }, &Position { x: end, y });
+            self.document.insert_newline(x, y);
+            self.goto_x_y(0, y.saturating_add(1));
+        } else {
+            for index in x..end {
+                self.document.replace_grapheme(&c.to_string(), index, y);
+            }
+            self.goto_x_y(end.saturating_sub(1), y);
+        }
+        self.document.end_undo_group();
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+    }
+
+    /// Delete the grapheme currently under the cursor
+    fn delete_current_grapheme(&mut self) {
+        self.document.delete(
+            self.current_x_position(),
+            self.current_x_position(),
+            self.current_row_index(),
+        );
+        self.clamp_cursor_to_current_row();
+    }
+
+    /// Yank n lines, starting from the current one, into the register
+    fn yank_lines(&mut self, n: usize) {
+        let start = self.current_row_index();
+        let end = cmp::min(start.saturating_add(n), self.document.line_count());
+        let lines: Vec<&str> = (start..end)
+            .filter_map(|index| self.get_row(index))
+            .map(|row| row.string.as_str())
+            .collect();
+        let register = Register::linewise(lines.join("\n"));
+        self.registers.yank(self.selected_register.take(), register);
+    }
+
+    /// Paste the register content n times after (or before) the cursor. Linewise
+    /// content is pasted below (or above) the current line.
+    fn paste(&mut self, after: bool, n: usize) {
+        let register = match self.registers.read(self.selected_register.take()) {
+            Some(register) if !register.text.is_empty() || register.linewise => register,
+            _ => return,
+        };
+        let text = vec![register.text.as_str(); cmp::max(n, 1)].join(if register.linewise {
+            "\n"
+        } else {
+            ""
+        });
+        let y = self.current_row_index();
+        if register.linewise {
+            let row_len = self.current_row().len();
+            let pasted_row_index = if after {
+                self.document
+                    .insert_string(&format!("\n{text}"), row_len, y);
+                y.saturating_add(1)
+            } else {
+                self.document.insert_string(&format!("{text}\n"), 0, y);
+                y
+            };
+            self.goto_x_y(0, pasted_row_index);
+            self.goto_first_non_whitespace();
+        } else {
+            let x = if after {
+                cmp::min(
+                    self.current_x_position().saturating_add(1),
+                    self.current_row().len(),
+                )
+            } else {
+                self.current_x_position()
+            };
+            let end = self.document.insert_string(&text, x, y);
+            self.goto_x_y(end.x.saturating_sub(1), end.y);
+        }
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+    }
+
+    /// Undo the last change, and move the cursor where it was made
+    fn undo(&mut self) {
+        match self.document.undo() {
+            Some(position) => self.goto_changed_position(&position),
+            None => self.display_message("Already at oldest change".to_string()),
+        }
     }
 
-    fn current_x_position(&self) -> usize {
-        self.cursor_position.x.saturating_add(self.offset.columns)
+    /// Redo the last undone change, and move the cursor where it was made
+    fn redo(&mut self) {
+        match self.document.redo() {
+            Some(position) => self.goto_changed_position(&position),
+            None => self.display_message("Already at newest change".to_string()),
+        }
     }
 
-    /// Return the character currently under the cursor
-    fn current_grapheme(&self) -> &str {
-        self.current_row().nth_grapheme(self.current_x_position())
+    /// Process `:undo`, which undoes the last change, or `:undo {n}`, which goes to the
+    /// undo state with that number
+    fn process_undo_command(&mut self, argument: &str) {
+        match argument.parse() {
+            Ok(seq) => self.goto_undo_state(seq),
+            Err(_) if argument.is_empty() => self.undo(),
+            Err(_) => {
+                self.display_message(utils::red(&format!("Invalid undo number '{argument}'")));
+            }
+        }
     }
 
-    /// Return the line number associated to the current cursor position / vertical offset
-    fn current_line_number(&self) -> usize {
-        self.current_row_index().saturating_add(1)
+    /// Undo or redo changes until reaching the undo state with the provided number
+    fn goto_undo_state(&mut self, seq: usize) {
+        if seq == self.document.undo_state() {
+            self.display_message(format!("Already at undo state {seq}"));
+            return;
+        }
+        match self.document.goto_undo_state(seq) {
+            Some(position) => {
+                self.goto_changed_position(&position);
+                self.display_message(format!("Undo state {seq}"));
+            }
+            None => self.display_message(utils::red(&format!("Undo number {seq} not found"))),
+        }
     }
 
-    /// Return the Row object associated to the current cursor position / vertical offset
-    fn current_row(&self) -> &Row {
-        self.get_row(self.current_row_index()).unwrap()
+    /// Go to the undo state created before (or after) the current one, whatever its branch
+    /// of the undo tree, as the undo states are numbered in the order they were created
+    fn goto_chronological_undo_state(&mut self, earlier: bool) {
+        let times = self.pop_normal_command_repetitions();
+        let seq = self
+            .document
+            .undo_state_at(UndoDistance::Steps(times), earlier);
+        self.goto_undo_state(seq);
     }
 
-    /// Delete the line currently under the cursor
-    fn delete_current_line(&mut self) {
-        self.document.delete_row(self.current_row_index());
-        if self.cursor_position.y >= self.document.num_rows().saturating_sub(1) {
-            self.goto_line(self.document.num_rows(), self.cursor_position.x);
-        } else {
-            self.cursor_position.reset_x();
+    /// Travel back (or forward) in the undo history, by a number of changes (eg:
+    /// `:earlier 3`) or a time span (eg: `:later 10m`)
+    fn travel_undo_history(&mut self, argument: &str, earlier: bool) {
+        match UndoDistance::parse(argument) {
+            Some(distance) => {
+                let seq = self.document.undo_state_at(distance, earlier);
+                self.goto_undo_state(seq);
+            }
+            None => self.display_message(utils::red(&format!("Invalid time span '{argument}'"))),
         }
     }
 
-    /// Delete the grapheme currently under the cursor
-    fn delete_current_grapheme(&mut self) {
-        self.document.delete(
-            self.current_x_position(),
-            self.current_x_position(),
-            self.current_row_index(),
+    fn goto_changed_position(&mut self, position: &Position) {
+        let y = cmp::min(
+            position.y,
+            self.document.last_line_number().saturating_sub(1),
         );
+        self.goto_x_y(position.x, y);
+        self.clamp_cursor_to_current_row();
+        self.unsaved_edits = self.unsaved_edits.saturating_add(1);
+    }
+
+    /// Move the cursor back on the last character of the current row if it went past it,
+    /// as it must stay on the line in normal mode.
+    fn clamp_cursor_to_current_row(&mut self) {
+        let last_x = self.current_row().len().saturating_sub(1);
+        if self.current_x_position() > last_x {
+            self.goto_x_y(last_x, self.current_row_index());
+        }
     }
 
     /// Insert a newline after the current one, move cursor to it in insert mode
     fn insert_newline_after_current_line(&mut self) {
+        self.document.start_undo_group();
+        let indentation = self.autoindentation() + &self.comment_continuation();
         let next_row_index = self.current_row_index().saturating_add(1);
         self.document
             .insert_newline(self.current_row().len(), self.current_row_index());
-        self.goto_x_y(0, next_row_index);
         self.enter_insert_mode();
+        self.indent_line(&indentation, next_row_index);
     }
 
     /// Insert a newline before the current one, move cursor to it in insert mode
     fn insert_newline_before_current_line(&mut self) {
+        self.document.start_undo_group();
+        let indentation = self.autoindentation() + &self.comment_continuation();
         self.document.insert_newline(0, self.current_row_index());
-        self.goto_x_y(0, self.current_row_index());
         self.enter_insert_mode();
+        self.indent_line(&indentation, self.current_row_index());
+    }
+
+    /// Return the indentation of the current line, to be copied to the new lines
+    /// created from it (none with `:set noautoindent`).
+    fn autoindentation(&self) -> String {
+        if self.config.autoindent {
+            self.current_row().leading_whitespace().to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Return the comment leader (eg: `// `) to start the lines created from the current
+    /// one with, when it's a line comment and `comment_continuation` is on.
+    fn comment_continuation(&self) -> String {
+        if !self.config.comment_continuation {
+            return String::new();
+        }
+        utils::comment_leader(&self.current_row().string, self.document.extension())
+            .unwrap_or_default()
+    }
+
+    /// Insert the indentation at the start of the provided line, and move the cursor after it
+    fn indent_line(&mut self, indentation: &str, y: usize) {
+        if !indentation.is_empty() {
+            self.document.insert_string(indentation, 0, y);
+        }
+        self.goto_x_y(indentation.chars().count(), y);
     }
 
     fn append_to_line(&mut self) {
@@ -636,19 +3414,14 @@ impl Editor {
         self.move_cursor(&Direction::Right, 1);
     }
 
+    /// Join the current line with the next one, and move the cursor where they were joined
     fn join_current_line_with_next_one(&mut self) {
-        if self.current_line_number() < self.document.num_rows() {
-            let next_line_row_index = self.cursor_position.y.saturating_add(1);
-            self.document.join_row_with_previous_one(
-                self.document
-                    .get_row(self.cursor_position.y.saturating_add(1))
-                    .unwrap()
-                    .len()
-                    .saturating_sub(1),
-                next_line_row_index,
-                Some(' '),
-            );
-            self.goto_start_or_end_of_line(&Boundary::End);
+        // a count joins that many lines, the current one included (eg: `3J`)
+        let times = self.pop_normal_command_repetitions();
+        let y = self.current_row_index();
+        if let Some(x) = self.document.join_rows(y, cmp::max(times, 2)) {
+            self.goto_x_y(x, y);
+            self.clamp_cursor_to_current_row();
         }
     }
 
@@ -665,11 +3438,40 @@ impl Editor {
         }
     }
 
+    /// Move the cursor to the end of the previous word, or WORD
+    fn goto_end_of_previous_word(&mut self, big_word: bool, times: usize) {
+        for _ in 0..times {
+            let position = Navigator::find_end_of_previous_word(
+                &self.document,
+                &Position {
+                    x: self.current_x_position(),
+                    y: self.current_row_index(),
+                },
+                big_word,
+            );
+            self.goto_x_y(position.x, position.y);
+        }
+    }
+
+    /// Move the cursor to the start of the next or previous sentence
+    fn goto_start_of_next_or_previous_sentence(&mut self, boundary: &Boundary, times: usize) {
+        for _ in 0..times {
+            let current_position = Position {
+                x: self.current_x_position(),
+                y: self.current_row_index(),
+            };
+            let position =
+                Navigator::find_sentence_boundary(&self.document, &current_position, boundary);
+            self.goto_x_y(position.x, position.y);
+        }
+    }
+
     /// Move the cursor either to the first or last line of the document
     fn goto_start_or_end_of_document(&mut self, boundary: &Boundary) {
+        self.record_jump();
         match boundary {
             Boundary::Start => self.goto_line(1, 0),
-            Boundary::End => self.goto_line(self.document.last_line_number(), 0),
+            Boundary::End => self.goto_line(self.document.line_count(), 0),
         }
     }
 
@@ -695,6 +3497,84 @@ impl Editor {
         }
     }
 
+    /// Select the word located under the cursor in visual mode
+    fn select_word_under_cursor(&mut self) {
+        if let Some((start, end)) = Navigator::find_boundaries_of_word_under_cursor(
+            self.current_row(),
+            self.current_x_position(),
+        ) {
+            self.enter_visual_mode(Position {
+                x: start,
+                y: self.current_row_index(),
+            });
+            self.move_cursor_to_position_x(end);
+        }
+    }
+
+    /// Select the whole current line in visual mode
+    fn select_current_line(&mut self) {
+        self.enter_visual_mode(Position {
+            x: 0,
+            y: self.current_row_index(),
+        });
+        self.goto_start_or_end_of_line(&Boundary::End);
+    }
+
+    /// Return the (inclusive) start and end document positions of the visual selection, in order
+    fn selection_bounds(&self) -> Option<(Position, Position)> {
+        let anchor = self.selection_anchor?;
+        let cursor = Position {
+            x: self.current_x_position(),
+            y: self.current_row_index(),
+        };
+        if (anchor.y, anchor.x) <= (cursor.y, cursor.x) {
+            Some((anchor, cursor))
+        } else {
+            Some((cursor, anchor))
+        }
+    }
+
+    /// Return the range of indices of the provided row falling into the visual selection
+    fn selected_range_in_row(&self, row_index: usize, row: &Row) -> Option<Range<usize>> {
+        let (start, end) = self.selection_bounds()?;
+        if row_index < start.y || row_index > end.y {
+            return None;
+        }
+        let range_start = if row_index == start.y { start.x } else { 0 };
+        let range_end = if row_index == end.y {
+            end.x.saturating_add(1)
+        } else {
+            row.len()
+        };
+        Some(range_start..range_end)
+    }
+
+    /// Return the ranges of indices of the provided row matching the current search, if
+    /// the search matches should be highlighted.
+    fn search_match_ranges_in_row(&self, row_index: usize) -> Vec<Range<usize>> {
+        if !self.config.hlsearch || !self.search_highlighted {
+            return vec![];
+        }
+        self.search_matches
+            .iter()
+            .filter(|(match_start, _)| match_start.y == row_index)
+            .map(|(match_start, match_end)| match_start.x..match_end.x)
+            .collect()
+    }
+
+    /// Move the cursor to the first character of the line or, with `smart_home`, to its
+    /// first non whitespace character, unless the cursor is already located there.
+    fn goto_line_start(&mut self) {
+        let first_non_whitespace =
+            Navigator::find_index_of_first_non_whitespace(self.current_row());
+        match first_non_whitespace {
+            Some(x) if self.config.smart_home && x != self.current_x_position() => {
+                self.move_cursor_to_position_x(x);
+            }
+            _ => self.goto_start_or_end_of_line(&Boundary::Start),
+        }
+    }
+
     /// Move the cursor to the first non whitespace character in the line
     fn goto_first_non_whitespace(&mut self) {
         if let Some(x) = Navigator::find_index_of_first_non_whitespace(self.current_row()) {
@@ -702,70 +3582,153 @@ impl Editor {
         }
     }
 
+    /// Scroll the viewport so that the current line is displayed at its top, center
+    /// or bottom, without moving the cursor within the document. We never scroll
+    /// past the end of the document.
+    fn reposition_view(&mut self, view_position: &ViewPosition) {
+        let term_height = self.view_height().saturating_sub(1);
+        let row_index = self.current_row_index();
+        let max_offset = self
+            .document
+            .line_count()
+            .saturating_sub(1)
+            .saturating_sub(term_height);
+        let offset = match view_position {
+            ViewPosition::Top => row_index,
+            ViewPosition::Center => row_index.saturating_sub(self.middle_of_view_line_number()),
+            ViewPosition::Bottom => row_index.saturating_sub(term_height),
+        };
+        self.offset.rows = cmp::min(offset, max_offset);
+        self.cursor_position.y = row_index.saturating_sub(self.offset.rows);
+    }
+
     /// Move the cursor to the middle of the terminal
     fn goto_middle_of_terminal(&mut self) {
+        self.record_jump();
         self.goto_line(
-            self.terminal
-                .middle_of_screen_line_number()
+            self.middle_of_view_line_number()
                 .saturating_add(self.offset.rows)
                 .saturating_add(1),
             0,
         );
     }
 
-    /// Move the cursor to the middle of the terminal
-    fn goto_first_line_of_terminal(&mut self) {
-        self.goto_line(self.offset.rows.saturating_add(1), 0);
+    /// Move the cursor to the nth line from the top of the terminal
+    fn goto_first_line_of_terminal(&mut self, n: usize) {
+        self.record_jump();
+        let n = cmp::min(n, self.view_height());
+        let line_number = self.offset.rows.saturating_add(cmp::max(n, 1));
+        self.goto_line(cmp::min(line_number, self.document.line_count()), 0);
     }
 
-    /// Move the cursor to the last line of the terminal
-    fn goto_last_line_of_terminal(&mut self) {
-        self.goto_line(
-            (self.terminal.size().height as usize)
-                .saturating_add(self.offset.rows)
-                .saturating_add(1),
-            0,
+    /// Move the cursor to the nth line from the bottom of the terminal. When the document
+    /// ends before the bottom of the terminal, its last line is used as the bottom line.
+    fn goto_last_line_of_terminal(&mut self, n: usize) {
+        self.record_jump();
+        let term_height = self.view_height();
+        let first_visible_line_number = self.offset.rows.saturating_add(1);
+        let last_visible_line_number = cmp::min(
+            self.offset.rows.saturating_add(term_height),
+            self.document.line_count(),
+        );
+        let line_number = cmp::max(
+            last_visible_line_number.saturating_sub(n.saturating_sub(1)),
+            first_visible_line_number,
         );
+        self.goto_line(line_number, 0);
     }
 
     /// Move to {n}% in the file
     fn goto_percentage_in_document(&mut self, percent: usize) {
+        self.record_jump();
         let percent = cmp::min(percent, 100);
-        let line_number = (self.document.last_line_number() * percent) / 100;
-        self.goto_line(line_number, 0);
+        // rounded to the nearest line, 0% going to the first one
+        let line_number = self
+            .document
+            .line_count()
+            .saturating_mul(percent)
+            .saturating_add(50)
+            / 100;
+        self.goto_line(cmp::max(line_number, 1), 0);
     }
 
     /// Go to the matching closing symbol (whether that's a quote, curly/square/regular brace, etc).
+    /// In HTML and XML files, the cursor goes to the matching tag when it's located on one.
     fn goto_matching_closing_symbol(&mut self) {
-        let current_grapheme = self.current_grapheme();
-        match current_grapheme {
-            "\"" | "'" | "{" | "<" | "(" | "[" => {
-                if let Some(position) = Navigator::find_matching_closing_symbol(
-                    &self.document,
-                    &self.cursor_position,
-                    &self.offset,
-                ) {
-                    self.goto_x_y(position.x, position.y);
-                }
-            }
-            "}" | ">" | ")" | "]" => {
-                if let Some(position) = Navigator::find_matching_opening_symbol(
-                    &self.document,
-                    &self.cursor_position,
-                    &self.offset,
-                ) {
-                    self.goto_x_y(position.x, position.y);
-                }
+        if self.is_markup_document() {
+            if let Some(position) = Navigator::find_matching_tag(
+                &self.document,
+                &Position {
+                    x: self.current_x_position(),
+                    y: self.current_row_index(),
+                },
+            ) {
+                self.record_jump();
+                self.goto_x_y(position.x, position.y);
+                return;
             }
-            _ => (),
+        }
+        if let Some(position) = self.matching_symbol_position() {
+            self.record_jump();
+            self.goto_x_y(position.x, position.y);
+        }
+    }
+
+    /// Return the position of the symbol matching the bracket or quote under the cursor
+    fn matching_symbol_position(&self) -> Option<Position> {
+        match self.current_grapheme() {
+            "\"" | "'" | "{" | "<" | "(" | "[" => Navigator::find_matching_closing_symbol(
+                &self.document,
+                &self.cursor_position,
+                &self.offset,
+            ),
+            "}" | ">" | ")" | "]" => Navigator::find_matching_opening_symbol(
+                &self.document,
+                &self.cursor_position,
+                &self.offset,
+            ),
+            _ => None,
+        }
+    }
+
+    /// Apply the operator to the text spanning from the bracket or quote under the
+    /// cursor to the matching one, both included (eg: `d%`). Nothing happens when the
+    /// cursor isn't located on a bracket or quote.
+    fn apply_operator_to_matching_symbol(&mut self, operator: &str) {
+        let Some(target) = self.matching_symbol_position() else {
+            return;
+        };
+        let cursor = Position {
+            x: self.current_x_position(),
+            y: self.current_row_index(),
+        };
+        let (start, end) = if (target.y, target.x) > (cursor.y, cursor.x) {
+            (cursor, target)
+        } else {
+            (target, cursor)
+        };
+        let end = Position {
+            x: end.x.saturating_add(1),
+            y: end.y,
         };
+        self.apply_operator(operator.chars().next().unwrap_or_default(), &start, &end);
+    }
+
+    fn is_markup_document(&self) -> bool {
+        matches!(
+            self.document.extension(),
+            Some("html" | "htm" | "xhtml" | "xml" | "svg")
+        )
     }
 
     /// Move to the first character of the next search match
     fn goto_next_search_match(&mut self) {
+        self.refresh_stale_search_matches();
         if self.search_matches.is_empty() {
             return;
         }
+        self.record_jump();
+        self.search_highlighted = true;
         if self.current_search_match_index == self.search_matches.len().saturating_sub(1) {
             self.current_search_match_index = 0;
         } else {
@@ -776,18 +3739,19 @@ impl Editor {
             self.current_search_match_index.saturating_add(1),
             self.search_matches.len()
         ));
-        if let Some(search_match) = self.search_matches.get(self.current_search_match_index) {
-            let x_position = search_match.0.x;
-            let line_number = search_match.0.y;
-            self.goto_line(line_number, x_position);
+        if let Some((match_start, _)) = self.search_matches.get(self.current_search_match_index) {
+            self.goto_x_y(match_start.x, match_start.y);
         }
     }
 
     /// Move to the first character of the previous search match
     fn goto_previous_search_match(&mut self) {
+        self.refresh_stale_search_matches();
         if self.search_matches.is_empty() {
             return;
         }
+        self.record_jump();
+        self.search_highlighted = true;
         if self.current_search_match_index == 0 {
             self.current_search_match_index = self.search_matches.len().saturating_sub(1);
         } else {
@@ -798,13 +3762,18 @@ impl Editor {
             self.current_search_match_index.saturating_add(1),
             self.search_matches.len()
         ));
-        if let Some(search_match) = self.search_matches.get(self.current_search_match_index) {
-            let line_number = search_match.0.y;
-            let x_position = search_match.0.x;
-            self.goto_line(line_number, x_position);
+        if let Some((match_start, _)) = self.search_matches.get(self.current_search_match_index) {
+            self.goto_x_y(match_start.x, match_start.y);
         }
     }
 
+    /// Move the cursor to the line requested on the command line (eg: `bo +12 file`),
+    /// falling back to the last line if the document is shorter than that.
+    pub fn goto_start_line(&mut self, line_number: usize) {
+        let line_number = cmp::min(line_number, self.document.line_count());
+        self.goto_line(line_number, 0);
+    }
+
     /// Move the cursor to the nth line in the file and adjust the viewport
     fn goto_line(&mut self, line_number: usize, x_position: usize) {
         let y = line_number.saturating_sub(1);
@@ -813,39 +3782,55 @@ impl Editor {
 
     /// Move the cursor to the first column of the nth line
     fn goto_x_y(&mut self, x: usize, y: usize) {
-        self.move_cursor_to_position_x(x);
+        // the row is reached first, as the wide characters it contains set the view offset
         self.move_cursor_to_position_y(y);
+        self.move_cursor_to_position_x(x);
     }
 
     /// Move the cursor up/down/left/right by adjusting its x/y position
     fn move_cursor(&mut self, direction: &Direction, times: usize) {
-        let size = self.terminal.size();
-        let term_height = size.height.saturating_sub(1) as usize;
-        let term_width = size.width.saturating_sub(1) as usize;
+        let term_height = self.view_height().saturating_sub(1);
+        let term_width = self.text_area_width().saturating_sub(1);
         let Position { mut x, mut y } = self.cursor_position;
+        let last_row_index = self.document.line_count().saturating_sub(1);
+
+        // When the scrolloff exceeds half of the screen, the cursor stays in the middle of it
+        let middle_of_screen = self.middle_of_view_line_number();
+        let scrolloff_top = cmp::min(self.config.scrolloff, middle_of_screen);
+        let scrolloff_bottom = cmp::min(
+            self.config.scrolloff,
+            term_height.saturating_sub(middle_of_screen),
+        );
 
         let ViewportOffset {
             columns: mut offset_x,
             rows: mut offset_y,
         } = self.offset;
+        let vertical = matches!(direction, Direction::Up | Direction::Down);
+        if vertical {
+            // go back to the column the cursor was on before going through shorter lines
+            x = self.desired_x.saturating_sub(offset_x);
+        }
 
         for _ in 0..times {
             match direction {
                 Direction::Up => {
-                    if y == 0 {
-                        // we reached the top of the terminal so adjust offset instead
-                        offset_y = offset_y.saturating_sub(1);
+                    if y > scrolloff_top || offset_y == 0 {
+                        y = y.saturating_sub(1); // cannot be < 0
                     } else {
-                        y = y.saturating_sub(1);
+                        // we reached the top of the terminal (minus the scrolloff),
+                        // so adjust offset instead
+                        offset_y = offset_y.saturating_sub(1);
                     }
-                } // cannot be < 0
+                }
                 Direction::Down => {
-                    if y.saturating_add(offset_y)
-                        < self.document.last_line_number().saturating_sub(1)
-                    {
+                    if y.saturating_add(offset_y) < last_row_index {
                         // don't scroll past the last line in the document
-                        if y < term_height {
-                            // don't scroll past the confine the of terminal itself
+                        if y < term_height.saturating_sub(scrolloff_bottom)
+                            || offset_y.saturating_add(term_height) >= last_row_index
+                        {
+                            // don't scroll past the confine the of terminal itself (minus
+                            // the scrolloff), or when the end of the document is visible
                             y = y.saturating_add(1);
                         } else {
                             // increase offset to that scrolling adjusts the viewport
@@ -854,7 +3839,10 @@ impl Editor {
                     }
                 }
                 Direction::Left => {
-                    if x >= term_width {
+                    // the cursor can sit left of the last column when wide characters
+                    // were scrolled past, in which case the view scrolls back once it
+                    // reaches the first column
+                    if x >= term_width || x == 0 {
                         offset_x = offset_x.saturating_sub(1);
                     } else {
                         x = x.saturating_sub(1);
@@ -878,17 +3866,54 @@ impl Editor {
         // if we move from a line to another in normal mode, and the previous x position
         // would cause teh cursor to be placed outside of the destination line x boundary,
         // we make sure to place the cursor on the last character of the line.
-        if self.mode == Mode::Normal {
+        if matches!(self.mode, Mode::Insert | Mode::Replace) {
+            self.cursor_position.x = cmp::min(self.current_row().len(), x);
+        } else {
             self.cursor_position.x = cmp::min(self.current_row().len().saturating_sub(1), x);
+        }
+        self.scroll_to_cursor_display_x();
+        if !vertical {
+            self.desired_x = self.current_x_position();
+        }
+    }
+
+    /// Return the terminal column at which the cursor is displayed, literal tabs
+    /// being wider than a single column.
+    fn cursor_display_x(&self) -> usize {
+        let Some(row) = self.get_row(self.current_row_index()) else {
+            return self.cursor_position.x;
+        };
+        let tab_width = self.config.tab_width;
+        row.display_column(self.current_x_position(), tab_width)
+            .saturating_sub(row.display_column(self.offset.columns, tab_width))
+    }
+
+    /// Return the number of columns the rows are displayed on, right of the line numbers
+    fn text_area_width(&self) -> usize {
+        let term_width = self.terminal.size().width as usize;
+        if self.row_prefix_length > 0 {
+            term_width
+                .saturating_sub(self.row_prefix_length as usize)
+                .saturating_sub(1)
         } else {
-            self.cursor_position.x = x;
+            term_width
+        }
+    }
+
+    /// Scroll the view to the right until the cursor is displayed within the text area,
+    /// including after the last character of the line in insert mode
+    fn scroll_to_cursor_display_x(&mut self) {
+        let term_width = self.text_area_width().saturating_sub(1);
+        while self.cursor_display_x() > term_width && self.cursor_position.x > 0 {
+            self.offset.columns = self.offset.columns.saturating_add(1);
+            self.cursor_position.x = self.cursor_position.x.saturating_sub(1);
         }
     }
 
     fn move_cursor_to_position_y(&mut self, y: usize) {
-        let max_line_number = self.document.last_line_number(); // last line number in the document
-        let term_height = self.terminal.size().height as usize;
-        let middle_of_screen_line_number = self.terminal.middle_of_screen_line_number(); // number of the line in the middle of the terminal
+        let max_line_number = self.document.line_count(); // last line number in the document
+        let term_height = self.view_height();
+        let middle_of_screen_line_number = self.middle_of_view_line_number();
 
         let y = cmp::max(0, y);
         let y = cmp::min(y, max_line_number);
@@ -911,6 +3936,67 @@ impl Editor {
         }
     }
 
+    /// Return the number of lines scrolled by Ctrl-D and Ctrl-U: the `scroll` option, or
+    /// half of the view when it's 0. A count typed before them becomes the new `scroll`.
+    fn scroll_amount(&mut self) -> usize {
+        if !self.normal_command_buffer.is_empty() {
+            self.config.scroll = self.pop_normal_command_repetitions();
+        }
+        match self.config.scroll {
+            0 => self.view_height() / 2,
+            scroll => scroll,
+        }
+    }
+
+    /// Move the viewport and the cursor up or down by the provided number of lines,
+    /// keeping the cursor column when the destination line is long enough.
+    fn scroll_by(&mut self, direction: &Direction, lines: usize) {
+        let last_row_index = self.document.line_count().saturating_sub(1);
+        let max_offset = self
+            .document
+            .line_count()
+            .saturating_sub(self.view_height());
+        let (y, offset) = match direction {
+            Direction::Down => (
+                cmp::min(
+                    self.current_row_index().saturating_add(lines),
+                    last_row_index,
+                ),
+                cmp::min(self.offset.rows.saturating_add(lines), max_offset),
+            ),
+            _ => (
+                self.current_row_index().saturating_sub(lines),
+                self.offset.rows.saturating_sub(lines),
+            ),
+        };
+        self.offset.rows = offset;
+        self.cursor_position.y = y.saturating_sub(offset);
+
+        let last_x_position = if matches!(self.mode, Mode::Insert | Mode::Replace) {
+            self.current_row().len()
+        } else {
+            self.current_row().len().saturating_sub(1)
+        };
+        self.cursor_position.x = cmp::min(
+            self.desired_x.saturating_sub(self.offset.columns),
+            last_x_position.saturating_sub(self.offset.columns),
+        );
+    }
+
+    /// Move the cursor to the provided position on screen, without scrolling the viewport,
+    /// making sure it stays within the document bounds.
+    fn move_cursor_to_screen_position(&mut self, position: Position) {
+        let last_row_index = self.document.line_count().saturating_sub(1);
+        self.cursor_position.y =
+            cmp::min(position.y, last_row_index.saturating_sub(self.offset.rows));
+        let last_x_position = self.current_row().len().saturating_sub(1);
+        self.cursor_position.x = cmp::min(
+            position.x,
+            last_x_position.saturating_sub(self.offset.columns),
+        );
+        self.desired_x = self.current_x_position();
+    }
+
     fn move_cursor_to_position_x(&mut self, x: usize) {
         let term_width = self.terminal.size().width as usize;
         let x = cmp::max(0, x);
@@ -924,6 +4010,8 @@ impl Editor {
             self.cursor_position.x = x;
             self.offset.columns = 0;
         }
+        self.scroll_to_cursor_display_x();
+        self.desired_x = self.current_x_position();
     }
 
     fn is_dirty(&self) -> bool {
@@ -936,12 +4024,26 @@ impl Editor {
             if self.alternate_screen {
                 self.terminal.clear_all();
                 self.terminal.to_alternate_screen();
-                self.draw_help_screen();
+                if self.alternate_screen_text.is_some() {
+                    self.draw_alternate_screen_text();
+                } else {
+                    self.draw_help_screen();
+                }
             } else {
                 self.terminal.to_main_screen();
-                self.draw_rows();
+                if self.config.hlsearch && self.search_highlighted {
+                    self.refresh_stale_search_matches();
+                }
+                match self.changed_rows.take() {
+                    Some(rows) => self.draw_changed_rows(rows),
+                    None => self.draw_rows(),
+                }
+            }
+            if self.config.wildmenu && self.completion.is_some() {
+                self.draw_wildmenu();
+            } else {
+                self.draw_status_bar();
             }
-            self.draw_status_bar();
             self.draw_message_bar();
             if self.alternate_screen {
                 self.terminal.set_cursor_position_in_text_area(
@@ -957,7 +4059,13 @@ impl Editor {
                 });
             } else {
                 self.terminal.set_cursor_position_in_text_area(
-                    &self.cursor_position,
+                    &Position {
+                        x: self.cursor_display_x(),
+                        y: self
+                            .cursor_position
+                            .y
+                            .saturating_add(self.focused_pane_top()),
+                    },
                     self.row_prefix_length,
                 );
             }
@@ -966,54 +4074,182 @@ impl Editor {
         self.terminal.flush()
     }
 
+    /// Describe the document in a single line, as displayed by Ctrl-G: its full path,
+    /// modified status, line count, cursor position percentage and file format.
+    fn file_info(&self) -> String {
+        let path = self.document.filename.as_ref().map_or_else(
+            || String::from("No Name"),
+            |filename| {
+                fs::canonicalize(filename)
+                    .unwrap_or_else(|_| filename.clone())
+                    .display()
+                    .to_string()
+            },
+        );
+        let modified_marker = if self.is_dirty() { " [Modified]" } else { "" };
+        let binary_marker = if self.document.is_binary() {
+            " [binary]"
+        } else {
+            ""
+        };
+        let line_count = self.document.line_count();
+        let lines = if line_count == 0 {
+            String::from("--No lines in buffer--")
+        } else {
+            format!(
+                "{} line{} --{}%--",
+                line_count,
+                if line_count == 1 { "" } else { "s" },
+                self.current_line_number().saturating_mul(100) / line_count
+            )
+        };
+        format!(
+            "\"{path}\"{modified_marker}{binary_marker} {lines} [{}]",
+            self.document.file_format()
+        )
+    }
+
     fn generate_status(&self) -> String {
-        let dirty_marker = if self.is_dirty() { " +" } else { "" };
-        let left_status = format!(
-            "[{}]{} {}",
-            self.document
-                .filename
-                .as_ref()
-                .unwrap_or(&PathBuf::from("No Name"))
-                .to_str()
-                .unwrap_or_default(),
+        self.format_status(
+            &self.document,
+            self.is_dirty(),
+            Some(&self.mode),
+            &self.cursor_position,
+            &self.offset,
+        )
+    }
+
+    /// Generate the status of the unfocused pane, in which no mode is displayed
+    fn generate_pane_status(&self, pane: &Pane) -> String {
+        match self.buffer_document(pane.buffer) {
+            Some((document, dirty)) => {
+                self.format_status(document, dirty, None, &pane.cursor_position, &pane.offset)
+            }
+            None => String::new(),
+        }
+    }
+
+    fn format_status(
+        &self,
+        document: &Document,
+        dirty: bool,
+        mode: Option<&Mode>,
+        cursor_position: &Position,
+        offset: &ViewportOffset,
+    ) -> String {
+        let dirty_marker = if dirty { " +" } else { "" };
+        let binary_marker = if document.is_binary() {
+            " [binary]"
+        } else {
+            ""
+        };
+        let markers = format!(
+            "{}{}{}",
             dirty_marker,
-            self.mode
+            binary_marker,
+            mode.map_or_else(String::new, |mode| format!(" {mode}"))
         );
         let stats = if self.config.display_stats {
-            format!(
-                "[{}L/{}W]",
-                self.document.last_line_number(),
-                self.document.num_words()
-            )
+            format!("[{}L/{}W]", document.line_count(), document.num_words())
         } else {
             "".to_string()
         };
         let position = format!(
             "Ln {}, Col {}",
-            self.current_line_number(),
-            self.cursor_position
+            cursor_position
+                .y
+                .saturating_add(offset.rows)
+                .saturating_add(1),
+            cursor_position
                 .x
-                .saturating_add(self.offset.columns)
+                .saturating_add(offset.columns)
                 .saturating_add(1),
         );
         let right_status = format!("{} {}", stats, position);
         let right_status = right_status.trim_start();
+        let path = document.filename.as_ref().map_or_else(
+            || String::from("No Name"),
+            |filename| {
+                self.config
+                    .statusline_path
+                    .format(filename, &self.current_dir)
+            },
+        );
+        // the start of a path too long to fit is replaced by `<`
+        let max_path_width = (self.terminal.size().width as usize)
+            .saturating_sub(right_status.width())
+            .saturating_sub(markers.width())
+            .saturating_sub(3);
+        let path = if path.width() > max_path_width {
+            let mut kept_width = path.width();
+            let kept: String = path
+                .graphemes(true)
+                .skip_while(|grapheme| {
+                    let skipped = kept_width > max_path_width.saturating_sub(1);
+                    if skipped {
+                        kept_width = kept_width.saturating_sub(grapheme.width());
+                    }
+                    skipped
+                })
+                .collect();
+            format!("<{kept}")
+        } else {
+            path
+        };
+        let left_status = format!("[{path}]{markers}");
         let spaces = " ".repeat(
             (self.terminal.size().width as usize)
-                .saturating_sub(left_status.len())
-                .saturating_sub(right_status.len()),
+                .saturating_sub(left_status.width())
+                .saturating_sub(right_status.width()),
         );
         format!("{}{}{}\r", left_status, spaces, right_status)
     }
 
+    /// Draw the status bar of the bottom pane (the whole screen when it's not split)
     fn draw_status_bar(&self) {
+        match &self.split {
+            Some(pane) if self.top_pane_focused => {
+                self.draw_status_line(&self.generate_pane_status(pane));
+            }
+            _ => self.draw_status_line(&self.generate_status()),
+        }
+    }
+
+    fn draw_status_line(&self, status: &str) {
         self.terminal.set_bg_color(STATUS_BG_COLOR);
         self.terminal.set_fg_color(STATUS_FG_COLOR);
-        println!("{}", self.generate_status());
+        println!("{status}");
         self.terminal.reset_fg_color();
         self.terminal.reset_bg_color();
     }
 
+    /// Draw the completion candidates in place of the status bar, the selected one
+    /// being highlighted.
+    fn draw_wildmenu(&self) {
+        if let Some(completion) = self.completion.as_ref() {
+            let width = self.terminal.size().width as usize;
+            let visible_range = completion.visible_range(width);
+            let mut menu_width = 0;
+            self.terminal.set_bg_color(STATUS_BG_COLOR);
+            self.terminal.set_fg_color(STATUS_FG_COLOR);
+            for index in visible_range {
+                let candidate = &completion.candidates[index];
+                if completion.selected == Some(index) {
+                    self.terminal.set_bg_color(WILDMENU_SELECTED_BG_COLOR);
+                    print!("{candidate}");
+                    self.terminal.set_bg_color(STATUS_BG_COLOR);
+                } else {
+                    print!("{candidate}");
+                }
+                print!("  ");
+                menu_width += candidate.chars().count() + 2;
+            }
+            println!("{}\r", " ".repeat(width.saturating_sub(menu_width)));
+            self.terminal.reset_fg_color();
+            self.terminal.reset_bg_color();
+        }
+    }
+
     fn draw_message_bar(&self) {
         self.terminal.clear_current_line();
         if self.is_receiving_command() {
@@ -1070,16 +4306,116 @@ impl Editor {
         self.display_message("Press q to quit".to_string());
     }
 
+    fn draw_alternate_screen_text(&mut self) {
+        if let Some(text) = &self.alternate_screen_text {
+            let term_width = self.terminal.size().width as usize;
+            let mut lines = text.lines().skip(self.alternate_screen_scroll);
+            for _ in 0..self.text_area_height() {
+                let line: String = lines
+                    .next()
+                    .unwrap_or_default()
+                    .chars()
+                    .take(term_width)
+                    .collect();
+                println!("{line}\r");
+            }
+        }
+        self.display_message("Press q to quit".to_string());
+    }
+
+    /// Draw the rows of the document, or of both panes of the split screen along with
+    /// the status bar of the top one (the other status bar being drawn afterwards).
     fn draw_rows(&self) {
-        let term_height = self.terminal.size().height;
-        for terminal_row_idx in self.offset.rows..(term_height as usize + self.offset.rows) {
+        let Some(pane) = &self.split else {
+            self.draw_pane_rows(&self.document, &self.offset, self.view_height(), true);
+            return;
+        };
+        let (top_height, bottom_height) = self.pane_heights();
+        if self.top_pane_focused {
+            self.draw_pane_rows(&self.document, &self.offset, top_height, true);
+            self.draw_status_line(&self.generate_status());
+            self.draw_unfocused_pane_rows(pane, bottom_height);
+        } else {
+            self.draw_unfocused_pane_rows(pane, top_height);
+            self.draw_status_line(&self.generate_pane_status(pane));
+            self.draw_pane_rows(&self.document, &self.offset, bottom_height, true);
+        }
+    }
+
+    /// Redraw the provided rows of the document only, and move the cursor to the status
+    /// bar, drawn next. As a full redraw prints 2 more lines than the screen height (for
+    /// the status and message bars), the first 2 printed rows are scrolled out of it.
+    fn draw_changed_rows(&self, rows: Range<usize>) {
+        let highlighter = if self.document.is_binary() {
+            None
+        } else {
+            Highlighter::for_extension(self.document.extension())
+        };
+        let height = self.view_height();
+        let start = cmp::max(rows.start, self.offset.rows);
+        let end = cmp::min(rows.end, self.offset.rows.saturating_add(height));
+        for row_index in start..end {
+            let Some(y) = row_index.saturating_sub(self.offset.rows).checked_sub(2) else {
+                continue;
+            };
+            self.terminal
+                .set_cursor_position_anywhere(&Position { x: 0, y });
+            self.terminal.clear_current_line();
+            if let Some(row) = self.document.get_row(row_index) {
+                self.draw_row(
+                    row,
+                    row_index.saturating_add(1),
+                    self.offset.columns,
+                    highlighter.as_ref(),
+                    true,
+                );
+            } else {
+                println!("~\r");
+            }
+        }
+        self.terminal.set_cursor_position_anywhere(&Position {
+            x: 0,
+            y: height.saturating_sub(2),
+        });
+    }
+
+    fn draw_unfocused_pane_rows(&self, pane: &Pane, height: usize) {
+        if let Some((document, _)) = self.buffer_document(pane.buffer) {
+            // the selection and search matches only apply to the focused pane
+            let focused_document = pane.buffer == self.current_buffer;
+            self.draw_pane_rows(document, &pane.offset, height, focused_document);
+        }
+    }
+
+    /// Draw the rows of the document fitting in a pane of the provided height, starting
+    /// from the viewport offset
+    fn draw_pane_rows(
+        &self,
+        document: &Document,
+        offset: &ViewportOffset,
+        height: usize,
+        highlight_matches: bool,
+    ) {
+        let highlighter = if document.is_binary() {
+            None
+        } else {
+            Highlighter::for_extension(document.extension())
+        };
+        for terminal_row_idx in offset.rows..offset.rows.saturating_add(height) {
             let line_number = terminal_row_idx.saturating_add(1);
             self.terminal.clear_current_line();
-            if let Some(row) = self.get_row(terminal_row_idx) {
-                self.draw_row(row, line_number);
+            if let Some(row) = document.get_row(terminal_row_idx) {
+                self.draw_row(
+                    row,
+                    line_number,
+                    offset.columns,
+                    highlighter.as_ref(),
+                    highlight_matches,
+                );
             } else if terminal_row_idx == self.terminal.middle_of_screen_line_number()
-                && self.document.filename.is_none()
-                && self.get_row(0).unwrap_or(&Row::default()).is_empty()
+                && self.split.is_none()
+                && document.filename.is_none()
+                && document.get_row(0).unwrap_or(&Row::default()).is_empty()
             {
                 self.display_welcome_message();
             } else {
@@ -1088,21 +4424,114 @@ impl Editor {
         }
     }
 
-    fn draw_row(&self, row: &Row, line_number: usize) {
-        let row_visible_start = self.offset.columns;
-        let mut row_visible_end = self.terminal.size().width as usize + self.offset.columns;
-        if self.row_prefix_length > 0 {
-            row_visible_end = row_visible_end
-                .saturating_sub(self.row_prefix_length as usize)
-                .saturating_sub(1);
+    fn draw_row(
+        &self,
+        row: &Row,
+        line_number: usize,
+        offset_columns: usize,
+        highlighter: Option<&Highlighter>,
+        highlight_matches: bool,
+    ) {
+        let row_visible_start = offset_columns;
+        let row_visible_end = self.text_area_width() + offset_columns;
+        let row_index = line_number.saturating_sub(1);
+        // the visual selection takes precedence over the search matches
+        let (mut highlighted_ranges, mut highlight_color) = if !highlight_matches {
+            (vec![], SEARCH_MATCH_BG_COLOR)
+        } else if let Some(range) = self.selected_range_in_row(row_index, row) {
+            (vec![range], SELECTION_BG_COLOR)
+        } else {
+            (
+                self.search_match_ranges_in_row(row_index),
+                SEARCH_MATCH_BG_COLOR,
+            )
+        };
+        // the colorcolumns are only highlighted where nothing else is
+        if let (Some((ranges, color)), true) = (
+            self.colorcolumn_ranges_in_row(row),
+            highlighted_ranges.is_empty(),
+        ) {
+            highlighted_ranges = ranges;
+            highlight_color = color;
         }
-        let rendered_row = row.render(
+        let highlight = if highlighted_ranges.is_empty() {
+            None
+        } else {
+            Some((highlighted_ranges.as_slice(), highlight_color))
+        };
+        let syntax = highlighter.map_or_else(Vec::new, |highlighter| highlighter.highlight(row));
+        let rendered_row = row.render_with_highlight(
             row_visible_start,
             row_visible_end,
             line_number,
             self.row_prefix_length as usize,
+            self.config.tab_width,
+            highlight,
+            &syntax,
+            self.config.list,
         );
-        println!("{}\r", rendered_row);
+        let guide_padding = self.colorcolumn_padding(row, row_visible_start, row_visible_end);
+        println!("{rendered_row}{guide_padding}\r");
+    }
+
+    /// Return the ranges of the graphemes of the row highlighted by the colorcolumns, and
+    /// their color: the graphemes displayed on the colorcolumns, or the ones reaching the
+    /// first one with `colorcolumn_overflow`.
+    fn colorcolumn_ranges_in_row(&self, row: &Row) -> Option<(Vec<Range<usize>>, color::Rgb)> {
+        let columns = self.config.colorcolumns();
+        let tab_width = self.config.tab_width;
+        if self.config.colorcolumn_overflow {
+            let index = row.index_at_display_column(*columns.first()?, tab_width);
+            let overflow = index..row.len();
+            return (!overflow.is_empty()).then(|| (vec![overflow], COLORCOLUMN_OVERFLOW_BG_COLOR));
+        }
+        let mut indices: Vec<usize> = columns
+            .iter()
+            .map(|column| row.index_at_display_column(*column, tab_width))
+            .filter(|index| *index < row.len())
+            .collect();
+        // several columns can be displayed on the same tab
+        indices.dedup();
+        let ranges: Vec<Range<usize>> = indices
+            .into_iter()
+            .map(|index| index..index.saturating_add(1))
+            .collect();
+        (!ranges.is_empty()).then_some((ranges, COLORCOLUMN_BG_COLOR))
+    }
+
+    /// Return the text to display after a row ending before some colorcolumns, for their
+    /// guides to be drawn when they're visible (eg: spaces followed by a highlighted space).
+    fn colorcolumn_padding(&self, row: &Row, visible_start: usize, visible_end: usize) -> String {
+        if self.config.colorcolumn_overflow {
+            return String::new();
+        }
+        let tab_width = self.config.tab_width;
+        // the columns located past the end of the row are 1 grapheme wide
+        let first_visible_column = row
+            .display_column(visible_start, tab_width)
+            .saturating_add(visible_start.saturating_sub(row.len()));
+        let last_visible_column = first_visible_column
+            .saturating_add(visible_end.saturating_sub(visible_start))
+            .saturating_sub(1);
+        let row_end = cmp::max(
+            row.display_column(row.len(), tab_width),
+            first_visible_column,
+        );
+        let mut padding = String::new();
+        let mut padding_end = row_end;
+        for column in self
+            .config
+            .colorcolumns()
+            .into_iter()
+            .filter(|column| (row_end..=last_visible_column).contains(column))
+        {
+            padding.push_str(&" ".repeat(column.saturating_sub(padding_end)));
+            padding.push_str(&color::Bg(COLORCOLUMN_BG_COLOR).to_string());
+            padding.push(' ');
+            padding.push_str(&color::Bg(color::Reset).to_string());
+            padding_end = column.saturating_add(1);
+        }
+        padding
     }
 }
 
@@ -1,16 +1,30 @@
-use super::SPACES_PER_TAB;
-use crate::{AnsiPosition, Console, Document, Editor, Mode, Position, Row, Size};
+use super::{COLORCOLUMN_BG_COLOR, COLORCOLUMN_OVERFLOW_BG_COLOR, SWAP_SAVE_EVERY};
+use crate::{
+    clipboard, utils, AnsiPosition, ColorColumn, Config, Console, Document, Editor, FileFormat,
+    History, Mode, Position, Register, Row, Session, SessionBuffer, SessionPane, Size, Tag,
+    TagAddress,
+};
+use std::cell::Cell;
+use std::env;
 use std::fmt;
 use std::fs;
 use std::io::Error;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::slice;
+use std::time::{Duration, Instant};
 use tempfile::NamedTempFile;
 use termion::color;
-use termion::event::{Event, Key, MouseEvent};
+use termion::event::{Event, Key, MouseButton, MouseEvent};
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Default)]
-struct MockConsole {}
+struct MockConsole {
+    size: Size,
+    /// Last position the cursor was moved to in the text area, shared with the test
+    cursor_position: Rc<Cell<Position>>,
+}
 
 impl Console for MockConsole {
     fn read_event(&mut self) -> Result<Event, Error> {
@@ -50,25 +64,36 @@ impl Console for MockConsole {
 
     fn set_cursor_as_steady_block(&self) {}
 
+    fn set_cursor_as_steady_underline(&self) {}
+
     fn size(&self) -> Size {
-        Size::default()
+        Size {
+            height: self.size.height,
+            width: self.size.width,
+        }
     }
 
     fn middle_of_screen_line_number(&self) -> usize {
         self.size().height as usize / 2
     }
 
-    fn set_cursor_position_in_text_area(&self, _position: &Position, _row_prefix_length: u8) {}
+    fn set_cursor_position_in_text_area(&self, position: &Position, _row_prefix_length: u8) {
+        self.cursor_position.set(*position);
+    }
 
     fn set_cursor_position_anywhere(&self, _position: &Position) {}
 
     #[must_use]
     fn get_cursor_index_from_mouse_event(
         &self,
-        _mouse_event: MouseEvent,
+        mouse_event: MouseEvent,
         _x_offset: u8,
     ) -> Position {
-        Position::default()
+        match mouse_event {
+            MouseEvent::Press(_, x, y) | MouseEvent::Hold(x, y) | MouseEvent::Release(x, y) => {
+                Position::from(AnsiPosition { x, y })
+            }
+        }
     }
 }
 
@@ -95,9 +120,15 @@ fn get_long_document() -> Document {
     Document::new(rows, PathBuf::from("test"))
 }
 
+/// Create an editor with the default config and an empty history, whatever the user
+/// files contain
+fn new_test_editor(filename: Option<String>, console: Box<dyn Console>) -> Editor {
+    Editor::with_config(filename, console, Config::default(), History::default())
+}
+
 fn get_test_editor() -> Editor {
     let console = Box::new(MockConsole::default());
-    let mut editor = Editor::new(None, console);
+    let mut editor = new_test_editor(None, console);
     editor.document = get_short_document();
     editor.last_saved_hash = editor.document.hashed();
     editor
@@ -105,7 +136,7 @@ fn get_test_editor() -> Editor {
 
 fn get_test_editor_with_long_document() -> Editor {
     let console = Box::new(MockConsole::default());
-    let mut editor = Editor::new(None, console);
+    let mut editor = new_test_editor(None, console);
     editor.document = get_long_document();
     editor.last_saved_hash = editor.document.hashed();
     editor
@@ -247,9 +278,9 @@ fn test_editor_search() {
     assert_eq!(
         editor.search_matches,
         vec![
-            (Position { x: 6, y: 1 }, Position { x: 12, y: 1 }),
-            (Position { x: 6, y: 2 }, Position { x: 12, y: 2 }),
-            (Position { x: 6, y: 3 }, Position { x: 12, y: 3 })
+            (Position { x: 6, y: 0 }, Position { x: 11, y: 0 }),
+            (Position { x: 6, y: 1 }, Position { x: 11, y: 1 }),
+            (Position { x: 6, y: 2 }, Position { x: 11, y: 2 })
         ]
     );
     assert_eq!(editor.message, "Match 1/3");
@@ -276,6 +307,329 @@ fn test_editor_search() {
     assert_eq!(editor.current_search_match_index, 0);
 }
 
+#[test]
+fn test_editor_search_match_bounds() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, "/H");
+    // the match end is exclusive, and both bounds are document indices
+    assert_eq!(
+        editor.search_matches[0],
+        (Position { x: 0, y: 0 }, Position { x: 1, y: 0 })
+    );
+    editor.config.hlsearch = true;
+    assert_eq!(
+        editor.search_match_ranges_in_row(2),
+        slice::from_ref(&(0..1))
+    );
+}
+
+#[test]
+fn test_editor_regex_search() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, "/world!+$");
+    assert_eq!(editor.search_matches.len(), 2);
+    assert_position_is(&editor, 6, 1);
+    editor.process_keystroke(Key::Char('n'));
+    assert_position_is(&editor, 6, 2);
+
+    process_command(&mut editor, "/^H|d$");
+    assert_eq!(editor.search_matches.len(), 4);
+    assert_eq!(
+        editor.search_matches[1],
+        (Position { x: 10, y: 0 }, Position { x: 11, y: 0 })
+    );
+}
+
+#[test]
+fn test_editor_search_finds_every_match_of_a_line() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("the other theme"), Row::from("aaaa")],
+        PathBuf::from("test"),
+    );
+    process_command(&mut editor, ":set hls");
+    process_command(&mut editor, "/the");
+    assert_eq!(editor.search_matches.len(), 3);
+    assert_eq!(
+        editor.search_match_ranges_in_row(0),
+        vec![0..3, 5..8, 10..13]
+    );
+    editor.process_keystroke(Key::Char('n'));
+    editor.process_keystroke(Key::Char('n'));
+    assert_position_is(&editor, 10, 0);
+    assert_eq!(editor.message, "Match 3/3");
+
+    // matches don't overlap
+    process_command(&mut editor, "/aa");
+    assert_eq!(editor.search_match_ranges_in_row(1), vec![0..2, 2..4]);
+}
+
+#[test]
+fn test_editor_invalid_regex_search_falls_back_to_literal_search() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("call f(x")], PathBuf::from("test"));
+    process_command(&mut editor, "/f(x");
+    assert_eq!(editor.search_matches.len(), 1);
+    assert_position_is(&editor, 5, 0);
+    assert_eq!(
+        editor.message,
+        "Invalid regex, searched for the literal text instead: 1 matches"
+    );
+}
+
+#[test]
+fn test_editor_search_matches_are_refreshed_after_edit() {
+    let mut editor = get_test_editor();
+
+    process_command(&mut editor, "/world");
+    assert_position_is(&editor, 6, 0);
+    // insert a line above the first match, shifting all matches down by one line
+    editor.process_keystroke(Key::Char('O'));
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(editor.document.line_count(), 4);
+
+    editor.process_keystroke(Key::Char('n'));
+    assert_eq!(editor.current_search_match_index, 1);
+    assert_position_is(&editor, 6, 2);
+    assert_current_line_is(&editor, "Hello world!");
+    assert_eq!(
+        editor.search_matches.first(),
+        Some(&(Position { x: 6, y: 1 }, Position { x: 11, y: 1 }))
+    );
+
+    editor.process_keystroke(Key::Char('N'));
+    assert_position_is(&editor, 6, 1);
+    assert_current_line_is(&editor, "Hello world");
+
+    // undoing isn't an edit, but moves the matches back up as well
+    editor.process_keystroke(Key::Char('u'));
+    editor.process_keystroke(Key::Char('n'));
+    assert_eq!(
+        editor.search_matches.first(),
+        Some(&(Position { x: 6, y: 0 }, Position { x: 11, y: 0 }))
+    );
+}
+
+#[test]
+fn test_editor_hlsearch() {
+    let mut editor = get_test_editor();
+
+    // without hlsearch, the matches are not highlighted and Esc clears them
+    process_command(&mut editor, "/world");
+    assert!(editor.search_match_ranges_in_row(0).is_empty());
+    editor.process_keystroke(Key::Esc);
+    assert!(editor.search_matches.is_empty());
+
+    process_command(&mut editor, ":set hls");
+    assert!(editor.config.hlsearch);
+    process_command(&mut editor, "/world");
+    assert_eq!(editor.search_match_ranges_in_row(0), vec![6..11]);
+    assert_eq!(editor.search_match_ranges_in_row(2), vec![6..11]);
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(editor.search_matches.len(), 3);
+    assert_eq!(editor.search_match_ranges_in_row(1), vec![6..11]);
+
+    process_command(&mut editor, ":noh");
+    assert!(editor.search_match_ranges_in_row(1).is_empty());
+    editor.process_keystroke(Key::Char('n'));
+    assert_eq!(editor.search_match_ranges_in_row(1), vec![6..11]);
+
+    process_command(&mut editor, ":set nohlsearch");
+    assert!(!editor.config.hlsearch);
+    assert!(editor.search_match_ranges_in_row(1).is_empty());
+}
+
+#[test]
+fn test_editor_set_file_format() {
+    let mut editor = get_test_editor();
+
+    process_command(&mut editor, ":set ff");
+    assert_eq!(editor.message, "fileformat=unix");
+    process_command(&mut editor, ":set ff=dos");
+    assert_eq!(editor.document.file_format(), FileFormat::Dos);
+    assert!(editor.is_dirty());
+    process_command(&mut editor, ":set fileformat=unix");
+    assert_eq!(editor.document.file_format(), FileFormat::Unix);
+    assert!(!editor.is_dirty());
+    process_command(&mut editor, ":set ff=mac");
+    assert_eq!(editor.message, utils::red("Invalid value for 'ff'"));
+    assert_eq!(editor.document.file_format(), FileFormat::Unix);
+}
+
+#[test]
+fn test_editor_hex_view() {
+    let mut editor = get_test_editor();
+
+    process_command(&mut editor, ":hex");
+    assert!(editor.alternate_screen);
+    assert_eq!(
+        editor.alternate_screen_text.as_deref(),
+        Some(
+            "00000000: 4865 6c6c 6f20 776f 726c 640a 4865 6c6c  Hello world.Hell\n\
+             00000010: 6f20 776f 726c 6421 0a48 656c 6c6f 2077  o world!.Hello w\n\
+             00000020: 6f72 6c64 2121 0a                        orld!!."
+        )
+    );
+
+    // the hex view is read-only
+    process_keystrokes(&mut editor, vec!['i', 'x']);
+    assert_nth_row_is(&editor, 0, "Hello world");
+    assert_eq!(editor.mode, Mode::Normal);
+
+    process_command(&mut editor, ":hex");
+    assert!(!editor.alternate_screen);
+    assert_eq!(editor.alternate_screen_text, None);
+}
+
+fn get_test_editor_with_ragged_lines() -> Editor {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![
+            Row::from("A rather long line"),
+            Row::from("Short"),
+            Row::from("Another long line"),
+        ],
+        PathBuf::from("test"),
+    );
+    editor
+}
+
+#[test]
+fn test_editor_sticky_column_through_short_line() {
+    let mut editor = get_test_editor_with_ragged_lines();
+
+    process_keystrokes(&mut editor, vec!['1', '0', 'l']);
+    assert_position_is(&editor, 10, 0);
+    editor.process_keystroke(Key::Char('j'));
+    assert_position_is(&editor, 4, 1);
+    editor.process_keystroke(Key::Char('j'));
+    assert_position_is(&editor, 10, 2);
+    editor.process_keystroke(Key::Char('k'));
+    assert_position_is(&editor, 4, 1);
+    editor.process_keystroke(Key::Char('k'));
+    assert_position_is(&editor, 10, 0);
+
+    // a horizontal move resets the sticky column
+    editor.process_keystroke(Key::Char('j'));
+    editor.process_keystroke(Key::Char('h'));
+    assert_position_is(&editor, 3, 1);
+    editor.process_keystroke(Key::Char('j'));
+    assert_position_is(&editor, 3, 2);
+}
+
+#[test]
+fn test_editor_typing_resets_sticky_column() {
+    let mut editor = get_test_editor_with_ragged_lines();
+    process_keystrokes(&mut editor, vec!['1', '0', 'l', 'j', 'i', '-']);
+    editor.process_keystroke(Key::Esc);
+    assert_current_line_is(&editor, "Shor-t");
+    assert_position_is(&editor, 5, 1);
+    editor.process_keystroke(Key::Char('j'));
+    assert_position_is(&editor, 5, 2);
+
+    // so does an edit made in normal mode
+    process_keystrokes(&mut editor, vec!['k', 'k', '$', 'j', 'x', 'j']);
+    assert_nth_row_is(&editor, 1, "Shor-");
+    assert_position_is(&editor, 4, 2);
+}
+
+#[test]
+fn test_editor_sticky_end_of_line_column() {
+    let mut editor = get_test_editor_with_ragged_lines();
+
+    editor.process_keystroke(Key::Char('$'));
+    assert_position_is(&editor, 17, 0);
+    editor.process_keystroke(Key::Char('j'));
+    assert_position_is(&editor, 4, 1);
+    editor.process_keystroke(Key::Char('j'));
+    assert_position_is(&editor, 16, 2);
+}
+
+#[test]
+fn test_editor_sentence_motions() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("One. Two! Three"), Row::from("continued? Four.")],
+        PathBuf::from("test"),
+    );
+
+    editor.process_keystroke(Key::Char(')'));
+    assert_position_is(&editor, 5, 0);
+    process_keystrokes(&mut editor, vec!['2', ')']);
+    assert_position_is(&editor, 11, 1);
+    editor.process_keystroke(Key::Char('('));
+    assert_position_is(&editor, 10, 0);
+    process_keystrokes(&mut editor, vec!['3', '(']);
+    assert_position_is(&editor, 0, 0);
+}
+
+fn get_test_editor_with_three_sentences() -> Editor {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("First one. Second one! Third one?")],
+        PathBuf::from("test"),
+    );
+    editor.goto_x_y(14, 0);
+    editor
+}
+
+#[test]
+fn test_editor_delete_inner_sentence() {
+    let mut editor = get_test_editor_with_three_sentences();
+    process_keystrokes(&mut editor, vec!['d', 'i', 's']);
+    assert_nth_row_is(&editor, 0, "First one.  Third one?");
+    assert_position_is(&editor, 11, 0);
+    assert_eq!(editor.registers.get(None).unwrap().text, "Second one!");
+    assert_eq!(editor.mode, Mode::Normal);
+}
+
+#[test]
+fn test_editor_delete_around_sentence() {
+    let mut editor = get_test_editor_with_three_sentences();
+    process_keystrokes(&mut editor, vec!['d', 'a', 's']);
+    assert_nth_row_is(&editor, 0, "First one. Third one?");
+    assert_position_is(&editor, 11, 0);
+}
+
+#[test]
+fn test_editor_change_and_yank_inner_sentence() {
+    let mut editor = get_test_editor_with_three_sentences();
+    process_keystrokes(&mut editor, vec!['y', 'i', 's']);
+    assert_eq!(editor.registers.get(None).unwrap().text, "Second one!");
+    assert_nth_row_is(&editor, 0, "First one. Second one! Third one?");
+    assert_position_is(&editor, 11, 0);
+
+    process_keystrokes(&mut editor, vec!['c', 'i', 's', 'H', 'i', '!']);
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_nth_row_is(&editor, 0, "First one. Hi! Third one?");
+}
+
+#[test]
+fn test_editor_goto_end_of_previous_word() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("fn main() {"), Row::from("    let x = foo.bar;")],
+        PathBuf::from("test"),
+    );
+    editor.goto_x_y(16, 1); // on "bar"
+    process_keystrokes(&mut editor, vec!['g', 'e']);
+    assert_position_is(&editor, 15, 1); // the "." punctuation
+    process_keystrokes(&mut editor, vec!['2', 'g', 'e']);
+    assert_position_is(&editor, 10, 1); // "="
+    process_keystrokes(&mut editor, vec!['g', 'E']);
+    assert_position_is(&editor, 8, 1); // "x"
+    process_keystrokes(&mut editor, vec!['2', 'g', 'E']);
+    // at the start of a line, we move to the end of the previous one
+    assert_position_is(&editor, 10, 0);
+    process_keystrokes(&mut editor, vec!['g', 'E']);
+    assert_position_is(&editor, 8, 0); // "main()"
+    process_keystrokes(&mut editor, vec!['g', 'e']);
+    assert_position_is(&editor, 6, 0); // "main"
+    process_keystrokes(&mut editor, vec!['3', 'g', 'e']);
+    // we stay at the start of the document
+    assert_position_is(&editor, 0, 0);
+}
+
 #[test]
 fn test_editor_unknown_command() {
     let mut editor = get_test_editor();
@@ -296,7 +650,7 @@ fn test_editor_navigation() {
     editor.process_keystroke(Key::Char('G'));
     assert_position_is(&editor, 0, 2);
 
-    editor.process_keystroke(Key::Char('g'));
+    process_keystrokes(&mut editor, vec!['g', 'g']);
     assert_position_is(&editor, 0, 0);
 
     editor.process_keystroke(Key::Char('$'));
@@ -356,7 +710,7 @@ fn test_editor_edition() {
 
     editor.process_keystroke(Key::Esc);
     assert_eq!(editor.document.num_rows(), 5);
-    editor.process_keystroke(Key::Char('d'));
+    process_keystrokes(&mut editor, vec!['d', 'd']);
     assert_eq!(editor.document.num_rows(), 4);
 
     editor.goto_x_y(0, 1);
@@ -400,96 +754,506 @@ fn test_editor_insert_spaces_for_tab() {
     let mut editor = get_test_editor();
 
     process_keystrokes(&mut editor, vec!['i', '\t']);
-    assert_position_is(&editor, SPACES_PER_TAB, 0);
+    assert_position_is(&editor, 4, 0);
     assert_nth_row_is(&editor, 0, "    Hello world");
 }
 
 #[test]
-fn test_editor_move_cursor_to_position_x() {
+fn test_editor_find_char_motions() {
     let mut editor = get_test_editor();
-
-    assert_position_is(&editor, 0, 0);
-    editor.move_cursor_to_position_x(1);
+    editor.document = Document::new(vec![Row::from("f(a, b), g(c)")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['f', ',']);
+    assert_position_is(&editor, 3, 0);
+    process_keystrokes(&mut editor, vec!['2', 'f', '(']);
+    assert_position_is(&editor, 3, 0); // there is a single ( after the cursor
+    process_keystrokes(&mut editor, vec!['t', '(']);
+    assert_position_is(&editor, 9, 0);
+    process_keystrokes(&mut editor, vec!['F', 'a']);
+    assert_position_is(&editor, 2, 0);
+    process_keystrokes(&mut editor, vec!['$', 'T', '(']);
+    assert_position_is(&editor, 11, 0);
+    process_keystrokes(&mut editor, vec!['2', 'F', '(']);
     assert_position_is(&editor, 1, 0);
-    assert_eq!(editor.offset.columns, 0);
-
-    editor.move_cursor_to_position_x(140);
-    assert_position_is(&editor, 119, 0);
-    assert_eq!(editor.offset.columns, 21);
 }
 
 #[test]
-fn test_editor_move_cursor_to_position_y() {
-    let mut editor = get_test_editor_with_long_document();
-
-    assert_position_is(&editor, 0, 0);
-    assert_eq!(editor.offset.rows, 0);
-
-    editor.move_cursor_to_position_y(10);
-    assert_position_is(&editor, 0, 10);
-    assert_eq!(editor.offset.rows, 0);
-
-    editor.move_cursor_to_position_y(200);
-    assert_position_is(&editor, 0, 80);
-    assert_eq!(editor.offset.rows, 120);
-
-    editor.move_cursor_to_position_y(110);
-    assert_position_is(&editor, 0, 40);
-    assert_eq!(editor.offset.rows, 70);
+fn test_editor_repeat_find_char_motions() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("a, b, c, d")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['f', ',', ';']);
+    assert_position_is(&editor, 4, 0);
+    process_keystrokes(&mut editor, vec!['2', ';']);
+    assert_position_is(&editor, 4, 0); // there is a single , after the cursor
+    process_keystrokes(&mut editor, vec![',']);
+    assert_position_is(&editor, 1, 0);
+    // t doesn't get stuck right before the character it stopped before
+    process_keystrokes(&mut editor, vec!['0', 't', ',', ';']);
+    assert_position_is(&editor, 3, 0);
+    process_keystrokes(&mut editor, vec!['$', 'T', ',', ';']);
+    assert_position_is(&editor, 5, 0);
+}
 
-    editor.move_cursor_to_position_y(112);
-    assert_position_is(&editor, 0, 42);
-    assert_eq!(editor.offset.rows, 70);
+#[test]
+fn test_editor_repeat_find_char_across_lines() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("a, b"), Row::from("c"), Row::from("d, e")],
+        PathBuf::from("test"),
+    );
+    process_keystrokes(&mut editor, vec!['f', ',', ';']);
+    // off by default: the character isn't found on the current line
+    assert_position_is(&editor, 1, 0);
 
-    editor.move_cursor_to_position_y(180);
-    assert_position_is(&editor, 0, 60);
-    assert_eq!(editor.offset.rows, 120);
+    editor.config.find_across_lines = true;
+    process_keystrokes(&mut editor, vec![';']);
+    assert_position_is(&editor, 1, 2);
+    process_keystrokes(&mut editor, vec![',']);
+    assert_position_is(&editor, 1, 0);
 }
 
 #[test]
-fn test_editor_goto_percentage_in_document() {
-    let mut editor = get_test_editor_with_long_document();
+fn test_editor_delete_to_char_forward() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("call(a, \"b\", c)")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['d', 't', ')']);
+    assert_current_line_is(&editor, ")");
+    assert_eq!(
+        *editor.registers.get(None).unwrap(),
+        Register::charwise("call(a, \"b\", c".to_string())
+    );
 
-    process_keystrokes(&mut editor, vec!['1', '0', '%']);
-    assert_position_is(&editor, 0, 19); // line 20
+    editor.document = Document::new(vec![Row::from("call(a, \"b\", c)")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['d', 'f', '"']);
+    assert_current_line_is(&editor, "b\", c)");
+    // a count finds the nth occurrence of the character
+    editor.document = Document::new(vec![Row::from("a, b, c, d")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['d', '2', 'f', ',']);
+    assert_current_line_is(&editor, " c, d");
+    process_keystrokes(&mut editor, vec!['d', 'f', 'z']);
+    assert_current_line_is(&editor, " c, d");
 }
 
 #[test]
-fn test_editor_navigate_long_document() {
-    let mut editor = get_test_editor_with_long_document();
+fn test_editor_delete_to_char_backward() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("a, b, c, d")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['$', 'd', 'F', 'b']);
+    // the character under the cursor is kept
+    assert_current_line_is(&editor, "a, d");
+    assert_position_is(&editor, 3, 0);
+
+    editor.document = Document::new(vec![Row::from("a, b, c, d")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['$', '2', 'd', 'T', ',']);
+    assert_current_line_is(&editor, "a, b,d");
+    process_keystrokes(&mut editor, vec!['c', 'T', ' ', 'X']);
+    assert_current_line_is(&editor, "a, Xd");
+    assert_eq!(editor.mode, Mode::Insert);
+}
 
-    editor.move_cursor_to_position_y(110);
-    assert_position_is(&editor, 0, 40);
-    assert_eq!(editor.offset.rows, 70);
+#[test]
+fn test_editor_document_without_rows() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![], PathBuf::from("test"));
+    for c in ['j', 'k', 'l', 'h', '$', '0', 'w', 'b', 'x', 'G', 'o', 'O'] {
+        editor.process_keystroke(Key::Char(c));
+        editor.process_keystroke(Key::Esc);
+    }
+    process_keystrokes(&mut editor, vec!['d', 'd', 'A', 'a']);
+    assert_current_line_is(&editor, "a");
+}
 
-    editor.process_keystroke(Key::Char('H'));
-    assert_position_is(&editor, 0, 0);
-    assert_eq!(editor.offset.rows, 70);
+#[test]
+fn test_editor_autoindent() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("fn main() {"), Row::from("    let a = 1;")],
+        PathBuf::from("test"),
+    );
+    process_keystrokes(&mut editor, vec!['j', 'A', '\n', 'b']);
+    assert_nth_row_is(&editor, 2, "    b");
+    assert_position_is(&editor, 5, 2);
+    editor.process_keystroke(Key::Esc);
 
-    editor.process_keystroke(Key::Char('M'));
-    assert_position_is(&editor, 0, 40);
-    assert_eq!(editor.offset.rows, 70);
+    process_keystrokes(&mut editor, vec!['o', 'c']);
+    assert_nth_row_is(&editor, 3, "    c");
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['O', 'd']);
+    assert_nth_row_is(&editor, 3, "    d");
+    assert_nth_row_is(&editor, 4, "    c");
+    assert_position_is(&editor, 5, 3);
+    editor.process_keystroke(Key::Esc);
 
-    editor.process_keystroke(Key::Char('L'));
-    assert_position_is(&editor, 0, 80);
-    assert_eq!(editor.offset.rows, 70);
+    // the whole new line is undone at once, indentation included
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(editor.document.line_count(), 4);
+    assert_nth_row_is(&editor, 3, "    c");
 }
 
 #[test]
-fn test_editor_simple_utilities() {
-    let editor = get_test_editor();
-    assert_eq!(editor.current_row_index(), 0);
-    assert_eq!(editor.current_line_number(), 1);
-    assert_eq!(editor.current_x_position(), 0);
-    assert_eq!(editor.current_grapheme(), "H");
-    assert_eq!(editor.current_row().string, "Hello world");
+fn test_editor_noautoindent() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("  a")], PathBuf::from("test"));
+    process_command(&mut editor, ":set noai");
+    assert!(!editor.config.autoindent);
+    process_keystrokes(&mut editor, vec!['o', 'b']);
+    assert_nth_row_is(&editor, 1, "b");
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":set autoindent");
+    process_keystrokes(&mut editor, vec!['k', 'o', 'c']);
+    assert_nth_row_is(&editor, 1, "  c");
 }
 
 #[test]
-fn test_editor_status() {
+fn test_editor_insert_digraph() {
     let mut editor = get_test_editor();
-
-    assert_eq!(
+    editor.process_keystroke(Key::Char('i'));
+    editor.process_keystroke(Key::Ctrl('k'));
+    process_keystrokes(&mut editor, vec!['a', ':']);
+    editor.process_keystroke(Key::Ctrl('k'));
+    // the characters of a digraph can be typed in any order
+    process_keystrokes(&mut editor, vec!['\'', 'e']);
+    assert_nth_row_is(&editor, 0, "äéHello world");
+    assert_position_is(&editor, 2, 0);
+
+    // an unknown digraph inserts its second character
+    editor.process_keystroke(Key::Ctrl('k'));
+    process_keystrokes(&mut editor, vec!['q', 'z']);
+    assert_nth_row_is(&editor, 0, "äézHello world");
+    // any other key cancels the digraph
+    editor.process_keystroke(Key::Ctrl('k'));
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['!']);
+    assert_nth_row_is(&editor, 0, "äéz!Hello world");
+    assert_eq!(editor.mode, Mode::Insert);
+}
+
+#[test]
+fn test_editor_insert_code_point() {
+    let mut editor = get_test_editor();
+    editor.process_keystroke(Key::Char('i'));
+    // a BMP code point
+    editor.process_keystroke(Key::Ctrl('v'));
+    process_keystrokes(&mut editor, vec!['u', '0', '0', 'e', '9']);
+    // an astral plane code point
+    editor.process_keystroke(Key::Ctrl('v'));
+    process_keystrokes(
+        &mut editor,
+        vec!['U', '0', '0', '0', '1', 'F', '6', '0', '0'],
+    );
+    assert_nth_row_is(&editor, 0, "é😀Hello world");
+    assert_position_is(&editor, 2, 0);
+
+    // a decimal value, followed by a non-digit key
+    editor.process_keystroke(Key::Ctrl('v'));
+    process_keystrokes(&mut editor, vec!['6', '5', '!']);
+    assert_nth_row_is(&editor, 0, "é😀A!Hello world");
+    // invalid code points are ignored, and other keys are inserted literally
+    editor.process_keystroke(Key::Ctrl('v'));
+    process_keystrokes(&mut editor, vec!['u', 'd', '8', '0', '0']);
+    editor.process_keystroke(Key::Ctrl('v'));
+    editor.process_keystroke(Key::Char('\t'));
+    assert_nth_row_is(&editor, 0, "é😀A!\tHello world");
+}
+
+#[test]
+fn test_editor_insert_at_last_insert_position() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['A', '!']);
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['j', 'j', '0', 'g', 'i', '?']);
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_nth_row_is(&editor, 0, "Hello world!?");
+    assert_position_is(&editor, 13, 0);
+}
+
+#[test]
+fn test_editor_backspace_deletes_soft_tab() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['i', '\t', '\t']);
+    assert_nth_row_is(&editor, 0, "        Hello world");
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "    Hello world");
+    assert_position_is(&editor, 4, 0);
+
+    // outside of a tab stop, a single space is deleted
+    process_keystrokes(&mut editor, vec![' ', ' ']);
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "     Hello world");
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "    Hello world");
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "Hello world");
+}
+
+#[test]
+fn test_editor_backspace_after_text_deletes_single_space() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("ab      c")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['$', 'i']);
+    assert_position_is(&editor, 8, 0);
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "ab     c");
+
+    // with noexpandtab, Backspace always deletes a single grapheme
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['i', '\t']);
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":set noet");
+    process_keystrokes(&mut editor, vec!['i']);
+    editor.process_keystroke(Key::Backspace);
+    assert_nth_row_is(&editor, 0, "   Hello world");
+}
+
+#[test]
+fn test_editor_set_tab_width() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":set tabwidth 2");
+    assert_eq!(editor.config.tab_width, 2);
+    process_keystrokes(&mut editor, vec!['i', '\t']);
+    assert_position_is(&editor, 2, 0);
+    assert_nth_row_is(&editor, 0, "  Hello world");
+    editor.process_keystroke(Key::Esc);
+
+    process_command(&mut editor, ":set tabwidth=8");
+    assert_eq!(editor.config.tab_width, 8);
+    process_command(&mut editor, ":set tabwidth 0");
+    assert_eq!(editor.message, utils::red("Invalid value for 'tabwidth'"));
+    assert_eq!(editor.config.tab_width, 8);
+}
+
+#[test]
+fn test_editor_noexpandtab() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":set noexpandtab");
+    process_keystrokes(&mut editor, vec!['i', '\t', '\t']);
+    assert_nth_row_is(&editor, 0, "\t\tHello world");
+    assert_position_is(&editor, 2, 0);
+    // the cursor is displayed after the expanded tabs
+    assert_eq!(editor.cursor_display_x(), 8);
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":set tabwidth 3");
+    assert_eq!(editor.cursor_display_x(), 6);
+
+    process_command(&mut editor, ":set expandtab");
+    process_keystrokes(&mut editor, vec!['i', '\t']);
+    assert_nth_row_is(&editor, 0, "\t\t   Hello world");
+}
+
+#[test]
+fn test_editor_scroll_to_display_wide_tabs() {
+    let console = Box::new(MockConsole {
+        size: Size {
+            height: 10,
+            width: 10,
+        },
+        ..MockConsole::default()
+    });
+    let mut editor = new_test_editor(None, console);
+    editor.document = Document::new(vec![Row::from("\t\t\tabc")], PathBuf::from("test"));
+    process_keystrokes(&mut editor, vec!['l', 'l']);
+    assert_position_is(&editor, 2, 0);
+    assert_eq!(editor.cursor_display_x(), 8);
+    // the third tab is displayed after the last column, so the view is scrolled
+    editor.process_keystroke(Key::Char('l'));
+    assert_eq!(editor.offset.columns, 1);
+    assert_position_is(&editor, 2, 0);
+    assert_eq!(editor.cursor_display_x(), 8);
+}
+
+#[test]
+fn test_editor_move_cursor_to_position_x() {
+    let mut editor = get_test_editor();
+
+    assert_position_is(&editor, 0, 0);
+    editor.move_cursor_to_position_x(1);
+    assert_position_is(&editor, 1, 0);
+    assert_eq!(editor.offset.columns, 0);
+
+    editor.move_cursor_to_position_x(140);
+    assert_position_is(&editor, 119, 0);
+    assert_eq!(editor.offset.columns, 21);
+}
+
+#[test]
+fn test_editor_move_cursor_to_position_y() {
+    let mut editor = get_test_editor_with_long_document();
+
+    assert_position_is(&editor, 0, 0);
+    assert_eq!(editor.offset.rows, 0);
+
+    editor.move_cursor_to_position_y(10);
+    assert_position_is(&editor, 0, 10);
+    assert_eq!(editor.offset.rows, 0);
+
+    editor.move_cursor_to_position_y(200);
+    assert_position_is(&editor, 0, 80);
+    assert_eq!(editor.offset.rows, 120);
+
+    editor.move_cursor_to_position_y(110);
+    assert_position_is(&editor, 0, 40);
+    assert_eq!(editor.offset.rows, 70);
+
+    editor.move_cursor_to_position_y(112);
+    assert_position_is(&editor, 0, 42);
+    assert_eq!(editor.offset.rows, 70);
+
+    editor.move_cursor_to_position_y(180);
+    assert_position_is(&editor, 0, 60);
+    assert_eq!(editor.offset.rows, 120);
+}
+
+#[test]
+fn test_editor_goto_percentage_in_document() {
+    let mut editor = get_test_editor_with_long_document();
+
+    process_keystrokes(&mut editor, vec!['1', '0', '%']);
+    assert_position_is(&editor, 0, 19); // line 20
+
+    for (line_count, expected_lines) in [(1, [1, 1, 1]), (3, [1, 2, 3]), (1000, [1, 500, 1000])] {
+        let lines = vec!["line"; line_count];
+        let mut editor = get_test_editor_with_lines(&lines);
+        for (percent, expected_line) in [0, 50, 100].iter().zip(expected_lines.iter()) {
+            editor.goto_percentage_in_document(*percent);
+            assert_eq!(editor.current_line_number(), *expected_line);
+        }
+    }
+    // the percentage is rounded to the nearest line
+    let mut editor = get_test_editor_with_lines(&["line"; 3]);
+    process_keystrokes(&mut editor, vec!['1', '0', '%']);
+    assert_eq!(editor.current_line_number(), 1);
+    process_keystrokes(&mut editor, vec!['8', '0', '%']);
+    assert_eq!(editor.current_line_number(), 2);
+    process_keystrokes(&mut editor, vec!['9', '0', '%']);
+    assert_eq!(editor.current_line_number(), 3);
+}
+
+#[test]
+fn test_editor_navigate_long_document() {
+    let mut editor = get_test_editor_with_long_document();
+
+    editor.move_cursor_to_position_y(110);
+    assert_position_is(&editor, 0, 40);
+    assert_eq!(editor.offset.rows, 70);
+
+    editor.process_keystroke(Key::Char('H'));
+    assert_position_is(&editor, 0, 0);
+    assert_eq!(editor.offset.rows, 70);
+
+    editor.process_keystroke(Key::Char('M'));
+    assert_position_is(&editor, 0, 40);
+    assert_eq!(editor.offset.rows, 70);
+
+    editor.process_keystroke(Key::Char('L'));
+    assert_position_is(&editor, 0, 79);
+    assert_eq!(editor.offset.rows, 70);
+}
+
+#[test]
+fn test_editor_goto_last_line_of_terminal_in_short_document() {
+    let console = Box::new(MockConsole {
+        size: Size {
+            height: 40,
+            width: 120,
+        },
+        ..MockConsole::default()
+    });
+    let mut editor = new_test_editor(None, console);
+    editor.document = Document::new(
+        (1..=5)
+            .map(|n| Row::from(format!("Line {n}").as_str()))
+            .collect(),
+        PathBuf::from("test"),
+    );
+    editor.process_keystroke(Key::Char('L'));
+    assert_eq!(editor.current_line_number(), 5);
+    assert_position_is(&editor, 0, 4);
+    assert_eq!(editor.offset.rows, 0);
+    process_keystrokes(&mut editor, vec!['2', 'L']);
+    assert_eq!(editor.current_line_number(), 4);
+    assert_eq!(editor.offset.rows, 0);
+}
+
+#[test]
+fn test_editor_navigate_screen_with_count() {
+    let mut editor = get_test_editor_with_long_document();
+
+    editor.move_cursor_to_position_y(110);
+    process_keystrokes(&mut editor, vec!['3', 'H']);
+    assert_position_is(&editor, 0, 2);
+    assert_eq!(editor.offset.rows, 70);
+
+    process_keystrokes(&mut editor, vec!['2', 'L']);
+    assert_position_is(&editor, 0, 78);
+    assert_eq!(editor.offset.rows, 70);
+
+    // the count is ignored by M
+    process_keystrokes(&mut editor, vec!['3', 'M']);
+    assert_position_is(&editor, 0, 40);
+    editor.process_keystroke(Key::Char('j'));
+    assert_position_is(&editor, 0, 41);
+
+    // the count is clamped to the visible lines
+    process_keystrokes(&mut editor, vec!['5', '0', '0', 'H']);
+    assert_eq!(editor.offset.rows, 70);
+    assert_position_is(&editor, 0, 79);
+}
+
+#[test]
+fn test_editor_navigate_screen_with_count_in_short_document() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['5', 'H']);
+    assert_position_is(&editor, 0, 2);
+}
+
+#[test]
+fn test_editor_simple_utilities() {
+    let editor = get_test_editor();
+    assert_eq!(editor.current_row_index(), 0);
+    assert_eq!(editor.current_line_number(), 1);
+    assert_eq!(editor.current_x_position(), 0);
+    assert_eq!(editor.current_grapheme(), "H");
+    assert_eq!(editor.current_row().string, "Hello world");
+}
+
+#[test]
+fn test_editor_status_path() {
+    let mut editor = get_test_editor();
+    editor.document.filename = Some(env::current_dir().unwrap().join("src").join("main.rs"));
+    assert!(editor.generate_status().starts_with("[src/main.rs] NORMAL"));
+    process_command(&mut editor, ":set statuslinepath=basename");
+    assert!(editor.generate_status().starts_with("[main.rs] NORMAL"));
+    process_command(&mut editor, ":set statuslinepath");
+    assert_eq!(editor.message, "statuslinepath=basename");
+    process_command(&mut editor, ":set statuslinepath=full");
+    assert_eq!(
+        editor.message,
+        utils::red("Invalid value for 'statuslinepath'")
+    );
+
+    // the start of a path too long for the status bar is cut
+    process_command(&mut editor, ":set statuslinepath=absolute");
+    let long_path = format!("/{}end.rs", "directory/".repeat(20));
+    editor.document.filename = Some(PathBuf::from(&long_path));
+    let status = editor.generate_status();
+    assert_eq!(status.chars().count(), 121);
+    assert!(status.starts_with("[<"));
+    assert!(status.ends_with("directory/end.rs] NORMAL Ln 1, Col 1\r"));
+
+    // the path is cut according to the width of its characters, whatever their length
+    let wide_path = format!("/{}end.rs", "\u{6587}\u{66f8}/".repeat(30));
+    editor.document.filename = Some(PathBuf::from(&wide_path));
+    let status = editor.generate_status();
+    assert_eq!(status.trim_end_matches('\r').width(), 120);
+    assert!(status.starts_with("[</\u{6587}"));
+    assert!(status.contains("\u{66f8}/end.rs] NORMAL "));
+    assert!(status.ends_with(" Ln 1, Col 1\r"));
+}
+
+#[test]
+fn test_editor_status() {
+    let mut editor = get_test_editor();
+
+    assert_eq!(
         editor.generate_status(),
         format!("[test] NORMAL{}Ln 1, Col 1\r", " ".repeat(96))
     );
@@ -556,6 +1320,28 @@ fn test_editor_join_lines() {
     process_keystrokes(&mut editor, vec!['$', 'J']);
     assert_nth_row_is(&editor, 0, "Hello world Hello world!");
     assert_eq!(editor.document.num_rows(), 2);
+    // the cursor goes on the space separating the joined lines
+    assert_position_is(&editor, 11, 0);
+}
+
+#[test]
+fn test_editor_join_indented_and_empty_lines() {
+    let mut editor = get_test_editor_with_lines(&["if a {", "    b();", "", "}", "end"]);
+    process_keystrokes(&mut editor, vec!['3', 'J']);
+    assert_nth_row_is(&editor, 0, "if a { b();");
+    assert_position_is(&editor, 10, 0);
+    assert_eq!(editor.document.num_rows(), 3);
+
+    // joining an empty line adds no space
+    process_keystrokes(&mut editor, vec!['u', 'j', 'j', 'J']);
+    assert_nth_row_is(&editor, 1, "    b();");
+    assert_nth_row_is(&editor, 2, "}");
+    assert_position_is(&editor, 0, 2);
+    process_keystrokes(&mut editor, vec!['k', 'J']);
+    assert_nth_row_is(&editor, 1, "    b(); }");
+    // on the last line, there's nothing to join
+    process_keystrokes(&mut editor, vec!['G', 'J']);
+    assert_nth_row_is(&editor, 2, "end");
 }
 
 #[test]
@@ -615,8 +1401,12 @@ fn test_editor_serialize() {
   "search_matches": [],
   "current_search_match_index": 0,
   "unsaved_edits": 0,
-  "last_saved_hash": 6894519061004685273,
+  "last_saved_hash": 1900129789415963257,
   "row_prefix_length": 0,
+  "registers": {
+    "contents": {},
+    "last_used": null
+  },
   "document": {
     "rows": [
       {
@@ -629,7 +1419,8 @@ fn test_editor_serialize() {
         "string": "Hello world!!"
       }
     ],
-    "filename": "test"
+    "filename": "test",
+    "file_format": "unix"
   }
 }"#
     );
@@ -642,10 +1433,31 @@ fn test_open_existing_file() {
     f.write_all("Hello\nHello!\nHello!!\n".as_bytes()).unwrap();
     let f_name_pathbuf: PathBuf = f.path().to_path_buf();
     let f_name_str: String = f_name_pathbuf.to_str().unwrap().to_string(); // gawd
-    let editor = Editor::new(Some(f_name_str), console);
+    let editor = new_test_editor(Some(f_name_str), console);
     assert_eq!(editor.document.filename, Some(f_name_pathbuf));
 }
 
+#[test]
+fn test_open_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir_name = dir.path().to_str().unwrap().to_string();
+    let mut editor = new_test_editor(Some(dir_name.clone()), Box::new(MockConsole::default()));
+    // an empty unnamed buffer is opened instead
+    assert_eq!(editor.document.filename, None);
+    assert_eq!(
+        editor.message,
+        utils::red(&format!("{dir_name} is a directory"))
+    );
+
+    process_command(&mut editor, &format!(":o {dir_name}"));
+    assert_eq!(editor.buffer_count(), 1);
+    assert_eq!(editor.document.filename, None);
+    assert_eq!(
+        editor.message,
+        utils::red(&format!("{dir_name} is a directory"))
+    );
+}
+
 #[test]
 fn test_stop_receiving_command_after_processing_esc_key() {
     let mut editor = get_test_editor();
@@ -688,7 +1500,7 @@ fn test_save_file() {
     let f = NamedTempFile::new().unwrap();
     let f_name_pathbuf: PathBuf = f.path().to_path_buf();
     let f_name_str: String = f_name_pathbuf.to_str().unwrap().to_string(); // gawd
-    let mut editor = Editor::new(Some(f_name_str), console);
+    let mut editor = new_test_editor(Some(f_name_str), console);
 
     process_keystrokes(&mut editor, vec!['i', 'h', 'e', 'l', 'l', 'o']);
     editor.process_keystroke(Key::Esc);
@@ -699,13 +1511,85 @@ fn test_save_file() {
     assert_eq!(content, "hello\n");
 }
 
+#[test]
+fn test_save_file_in_binary_mode() {
+    let console = Box::new(MockConsole::default());
+    let mut f = NamedTempFile::new().unwrap();
+    f.write_all(b"a\r\nb  \nc").unwrap();
+    let f_name_str: String = f.path().to_str().unwrap().to_string();
+    let mut editor = new_test_editor(Some(f_name_str), console);
+
+    process_command(&mut editor, ":set binary");
+    assert!(editor.document.is_binary());
+    assert!(editor.generate_status().contains("[binary]"));
+    assert_nth_row_is(&editor, 0, "a\r");
+    editor.process_keystroke(Key::Char('i'));
+    editor.process_keystroke(Key::Char('\t'));
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":w");
+
+    // no tab expansion, no whitespace trimming, no added trailing newline
+    let content = fs::read_to_string(f.path()).unwrap();
+    assert_eq!(content, "\ta\r\nb  \nc");
+
+    process_command(&mut editor, ":set nobinary");
+    assert!(!editor.document.is_binary());
+    assert!(!editor.generate_status().contains("[binary]"));
+}
+
+#[test]
+fn test_save_invalid_utf8_file_in_binary_mode() {
+    let console = Box::new(MockConsole::default());
+    let mut f = NamedTempFile::new().unwrap();
+    let contents = b"\x7fELF\x02\xff\r\n\xe2\x82 text\n\xc3";
+    f.write_all(contents).unwrap();
+    let f_name_str: String = f.path().to_str().unwrap().to_string();
+    let mut editor = new_test_editor(Some(f_name_str), console);
+    assert!(editor
+        .message
+        .contains("isn't valid UTF-8: :set binary to edit it verbatim"));
+
+    // the file isn't overwritten by the empty document
+    process_command(&mut editor, ":w");
+    assert!(editor
+        .message
+        .contains("isn't valid UTF-8: :set binary to save it verbatim"));
+    assert_eq!(fs::read(f.path()).unwrap(), contents);
+
+    process_command(&mut editor, ":set binary");
+    assert_eq!(editor.document.line_count(), 3);
+    process_command(&mut editor, ":w");
+    assert_eq!(fs::read(f.path()).unwrap(), contents);
+
+    process_keystrokes(&mut editor, vec!['j', 'A', '!']);
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":w");
+    assert_eq!(
+        fs::read(f.path()).unwrap(),
+        b"\x7fELF\x02\xff\r\n\xe2\x82 text!\n\xc3"
+    );
+}
+
+#[test]
+fn test_set_binary_with_unsaved_changes() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['i', 'a']);
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":set binary");
+    assert!(!editor.document.is_binary());
+    assert_eq!(
+        editor.message,
+        utils::red("Unsaved changes! Save them before switching to binary mode")
+    );
+}
+
 #[test]
 fn test_save_file_trim_whitespaces() {
     let console = Box::new(MockConsole::default());
     let f = NamedTempFile::new().unwrap();
     let f_name_pathbuf: PathBuf = f.path().to_path_buf();
     let f_name_str: String = f_name_pathbuf.to_str().unwrap().to_string(); // gawd
-    let mut editor = Editor::new(Some(f_name_str), console);
+    let mut editor = new_test_editor(Some(f_name_str), console);
 
     process_keystrokes(&mut editor, vec!['i', ' ', 'h', 'e', 'l', 'l', 'o', ' ']);
     editor.process_keystroke(Key::Esc);
@@ -716,6 +1600,44 @@ fn test_save_file_trim_whitespaces() {
     assert_eq!(content, " hello\n"); // trailing whitespace has been removed
 }
 
+#[test]
+fn test_save_file_keeps_cursor_position() {
+    let console = Box::new(MockConsole::default());
+    let mut f = NamedTempFile::new().unwrap();
+    f.write_all(b"Hello world   \nHi  \n").unwrap();
+    let f_name_str: String = f.path().to_str().unwrap().to_string();
+    let mut editor = new_test_editor(Some(f_name_str), console);
+
+    // the cursor stays where it was when it's not located in the trimmed whitespace
+    process_keystrokes(&mut editor, vec!['l', 'l', 'l']);
+    process_command(&mut editor, ":w");
+    assert_current_line_is(&editor, "Hello world");
+    assert_position_is(&editor, 3, 0);
+
+    // it is otherwise moved back to the last character of the line
+    process_keystrokes(&mut editor, vec!['j', 'A', ' ', ' ']);
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":w");
+    assert_current_line_is(&editor, "Hi");
+    assert_position_is(&editor, 1, 1);
+}
+
+#[test]
+fn test_save_file_without_trimming_whitespaces() {
+    let console = Box::new(MockConsole::default());
+    let f = NamedTempFile::new().unwrap();
+    let f_name_str: String = f.path().to_str().unwrap().to_string();
+    let mut editor = new_test_editor(Some(f_name_str), console);
+
+    process_command(&mut editor, ":set notrim");
+    process_keystrokes(&mut editor, vec!['i', 'h', 'i', ' ', ' ']);
+    editor.process_keystroke(Key::Esc);
+    let position = editor.cursor_position;
+    process_command(&mut editor, ":w");
+    assert_eq!(editor.cursor_position, position);
+    assert_eq!(fs::read_to_string(f.path()).unwrap(), "hi  \n");
+}
+
 #[test]
 fn test_display_line_numbers() {
     let mut editor = get_test_editor();
@@ -755,7 +1677,7 @@ fn test_goto_matching_closing_symbol() {
     assert_eq!(first_line_content.chars().nth(14), Some(')'));
     editor.cursor_position = Position { x: 11, y: 0 }; // first paren
     editor.process_keystroke(Key::Esc);
-    editor.process_keystroke(Key::Char('m'));
+    editor.process_keystroke(Key::Char('%'));
     assert_position_is(&editor, 14, 0);
 }
 
@@ -775,7 +1697,2235 @@ fn test_delete_last_line() {
     assert_eq!(editor.document.num_rows(), 3);
     editor.process_keystroke(Key::Char('G'));
     assert_position_is(&editor, 0, 2);
-    editor.process_keystroke(Key::Char('d'));
+    process_keystrokes(&mut editor, vec!['d', 'd']);
     assert_eq!(editor.document.num_rows(), 2);
     assert_position_is(&editor, 0, 1);
 }
+
+#[test]
+fn test_goto_start_line() {
+    let mut editor = get_test_editor_with_long_document();
+    editor.goto_start_line(120);
+    assert_eq!(editor.current_line_number(), 120);
+
+    let mut editor = get_test_editor_with_long_document();
+    editor.goto_start_line(usize::MAX);
+    assert_eq!(editor.current_line_number(), 200);
+
+    let mut editor = get_test_editor();
+    editor.goto_start_line(12);
+    assert_eq!(editor.current_line_number(), 3);
+}
+
+#[test]
+fn test_gutter_click() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":ln");
+    editor.cursor_position = Position { x: 5, y: 0 };
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 2, 3));
+    assert_position_is(&editor, 0, 2);
+    assert!(editor.mouse_event_buffer.is_empty());
+
+    // clicking below the last line of the document is a no-op
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 2, 10));
+    assert_position_is(&editor, 0, 2);
+}
+
+#[test]
+fn test_double_click_selects_word() {
+    let mut editor = get_test_editor();
+    for _ in 0..2 {
+        editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 1, 1));
+        editor.process_mouse_event(MouseEvent::Release(1, 1));
+    }
+    assert_eq!(editor.mode, Mode::Visual);
+    assert_eq!(editor.selection_anchor, Some(Position { x: 0, y: 0 }));
+    assert_position_is(&editor, 4, 0);
+    assert_eq!(
+        editor.selected_range_in_row(0, editor.current_row()),
+        Some(0..5)
+    );
+    assert_eq!(editor.selected_range_in_row(1, editor.current_row()), None);
+
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(editor.selection_anchor, None);
+}
+
+#[test]
+fn test_triple_click_selects_line() {
+    let mut editor = get_test_editor();
+    for _ in 0..3 {
+        editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 1, 1));
+        editor.process_mouse_event(MouseEvent::Release(1, 1));
+    }
+    assert_eq!(editor.mode, Mode::Visual);
+    assert_eq!(editor.selection_anchor, Some(Position { x: 0, y: 0 }));
+    assert_position_is(&editor, 10, 0);
+
+    // a fourth click starts over as a simple click
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 1, 1));
+    editor.process_mouse_event(MouseEvent::Release(1, 1));
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_position_is(&editor, 0, 0);
+}
+
+#[test]
+fn test_clicks_far_apart_are_not_a_double_click() {
+    let mut editor = get_test_editor();
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 1, 1));
+    editor.process_mouse_event(MouseEvent::Release(1, 1));
+    let long_ago = Instant::now().checked_sub(Duration::from_secs(1)).unwrap();
+    editor.last_click = Some((long_ago, Position::default()));
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 1, 1));
+    editor.process_mouse_event(MouseEvent::Release(1, 1));
+    assert_eq!(editor.mode, Mode::Normal);
+}
+
+#[test]
+fn test_drag_selection() {
+    let mut editor = get_test_editor();
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Left, 3, 1));
+    editor.process_mouse_event(MouseEvent::Hold(4, 1));
+    assert_eq!(editor.mode, Mode::Visual);
+    assert_eq!(editor.selection_anchor, Some(Position { x: 2, y: 0 }));
+    assert_position_is(&editor, 3, 0);
+
+    // dragging past the end of a line and of the document stays within bounds
+    editor.process_mouse_event(MouseEvent::Hold(50, 50));
+    assert_position_is(&editor, 12, 2);
+    editor.process_mouse_event(MouseEvent::Hold(5, 2));
+    assert_position_is(&editor, 4, 1);
+
+    editor.process_mouse_event(MouseEvent::Release(5, 2));
+    assert_eq!(editor.mode, Mode::Visual);
+    assert_eq!(editor.selection_anchor, Some(Position { x: 2, y: 0 }));
+    assert_position_is(&editor, 4, 1);
+    assert_eq!(
+        editor.selected_range_in_row(0, editor.get_row(0).unwrap()),
+        Some(2..11)
+    );
+    assert_eq!(
+        editor.selected_range_in_row(1, editor.get_row(1).unwrap()),
+        Some(0..5)
+    );
+}
+
+#[test]
+fn test_visual_mode_delete_and_yank() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['v', 'l', 'l']);
+    assert_eq!(editor.mode, Mode::Visual);
+    assert_eq!(editor.selection_anchor, Some(Position { x: 0, y: 0 }));
+    editor.process_keystroke(Key::Char('y'));
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(editor.selection_anchor, None);
+    assert_eq!(editor.registers.get(None).unwrap().text, "Hel");
+    assert_current_line_is(&editor, "Hello world");
+
+    process_keystrokes(&mut editor, vec!['v', 'w', 'x']);
+    assert_current_line_is(&editor, "orld");
+    assert_eq!(editor.mode, Mode::Normal);
+
+    // Esc cancels the selection without editing
+    process_keystrokes(&mut editor, vec!['v', 'l']);
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(editor.mode, Mode::Normal);
+    assert_eq!(editor.selection_anchor, None);
+    assert_current_line_is(&editor, "orld");
+}
+
+#[test]
+fn test_visual_mode_delete_across_lines() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['l', 'l', 'v', 'j', 'j', 'k', 'd']);
+    assert_eq!(editor.document.line_count(), 2);
+    assert_nth_row_is(&editor, 0, "Helo world!");
+    assert_nth_row_is(&editor, 1, "Hello world!!");
+    assert_eq!(editor.registers.get(None).unwrap().text, "llo world\nHel");
+    assert_position_is(&editor, 2, 0);
+}
+
+#[test]
+fn test_middle_click_moves_cursor_and_pastes() {
+    let mut editor = get_test_editor();
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Middle, 3, 2));
+    assert_position_is(&editor, 2, 1);
+
+    editor.insert_text_at_cursor("yy\nzz");
+    assert_nth_row_is(&editor, 1, "Heyy");
+    assert_nth_row_is(&editor, 2, "zzllo world!");
+    assert_position_is(&editor, 1, 2);
+}
+
+#[test]
+fn test_middle_click_pastes_unnamed_register_without_clipboard() {
+    let mut editor = get_test_editor();
+    // the tests are run without a clipboard backend, so there's no primary selection
+    assert_eq!(clipboard::get_primary_selection(), None);
+    process_keystrokes(&mut editor, vec!['y', 'w']);
+    editor.process_mouse_event(MouseEvent::Press(MouseButton::Middle, 3, 2));
+    assert_nth_row_is(&editor, 1, "HeHello llo world!");
+    assert_position_is(&editor, 7, 1);
+}
+
+#[test]
+fn test_lookup_word_under_cursor() {
+    let mut editor = get_test_editor();
+    editor.config.keyword_program = String::from("echo doc for %s");
+    process_keystrokes(&mut editor, vec!['w', 'K']);
+    assert!(editor.alternate_screen);
+    assert_eq!(
+        editor.alternate_screen_text,
+        Some(String::from("doc for world\n"))
+    );
+
+    // cursor movement keys scroll the text instead of moving the cursor
+    editor.process_keystroke(Key::Char('j'));
+    assert_position_is(&editor, 6, 0);
+    editor.process_keystroke(Key::Char('q'));
+    assert!(!editor.alternate_screen);
+    assert!(editor.alternate_screen_text.is_none());
+
+    editor.config.keyword_program = String::from("false");
+    editor.process_keystroke(Key::Char('K'));
+    assert!(!editor.alternate_screen);
+    assert_eq!(
+        editor.message,
+        utils::red("false exited with exit status: 1")
+    );
+}
+
+#[test]
+fn test_alternate_screen_scroll() {
+    let mut editor = get_test_editor();
+    let text: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+    editor.display_in_alternate_screen(text.join("\n"));
+    process_keystrokes(&mut editor, vec!['j', 'j', 'k']);
+    assert_eq!(editor.alternate_screen_scroll, 1);
+    editor.process_keystroke(Key::Char('G'));
+    assert_eq!(editor.alternate_screen_scroll, 22); // 100 lines - 78 visible lines
+    editor.process_keystroke(Key::Char('j'));
+    assert_eq!(editor.alternate_screen_scroll, 22);
+    editor.process_keystroke(Key::Char('g'));
+    assert_eq!(editor.alternate_screen_scroll, 0);
+}
+
+#[test]
+fn test_commands_and_searches_are_recorded_in_history() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":stats");
+    process_command(&mut editor, "/world");
+    process_command(&mut editor, ":2");
+    process_command(&mut editor, ":stats");
+    assert_eq!(editor.history.commands, vec!["2", "stats"]);
+    assert_eq!(editor.history.searches, vec!["world"]);
+}
+
+#[test]
+fn test_scrolloff() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":set so=5");
+    assert_eq!(editor.config.scrolloff, 5);
+    process_keystrokes(&mut editor, vec!['8', '0', 'j']);
+    assert_position_is(&editor, 0, 74);
+    assert_eq!(editor.offset.rows, 6);
+    process_keystrokes(&mut editor, vec!['7', '0', 'k']);
+    assert_position_is(&editor, 0, 5);
+    assert_eq!(editor.offset.rows, 5);
+}
+
+#[test]
+fn test_scrolloff_keeps_cursor_centered() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":set so=999");
+    for line_number in 2..=200 {
+        editor.process_keystroke(Key::Char('j'));
+        assert_eq!(editor.current_line_number(), line_number);
+        if (41..=160).contains(&line_number) {
+            // the cursor stays in the middle of the screen, except near the document boundaries
+            assert_eq!(editor.cursor_position.y, 40);
+        }
+    }
+    assert_eq!(editor.offset.rows, 120);
+    for line_number in (1..200).rev() {
+        editor.process_keystroke(Key::Char('k'));
+        assert_eq!(editor.current_line_number(), line_number);
+        if (41..=160).contains(&line_number) {
+            assert_eq!(editor.cursor_position.y, 40);
+        }
+    }
+    assert_eq!(editor.offset.rows, 0);
+}
+
+#[test]
+fn test_set_unknown_option() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":set derp=1");
+    assert_eq!(editor.message, utils::red("Unknown option 'derp'"));
+    process_command(&mut editor, ":set so=a");
+    assert_eq!(editor.message, utils::red("Invalid value for 'so'"));
+    process_command(&mut editor, ":set nolist=1");
+    assert_eq!(editor.message, utils::red("Option 'nolist' takes no value"));
+    process_command(&mut editor, ":set list=1");
+    assert_eq!(editor.message, utils::red("Option 'list' takes no value"));
+    process_command(&mut editor, ":set noderp");
+    assert_eq!(editor.message, utils::red("Unknown option 'noderp'"));
+}
+
+#[test]
+fn test_set_boolean_and_number_options() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":set nu");
+    assert!(editor.config.display_line_numbers);
+    assert!(editor.row_prefix_length > 0);
+    process_command(&mut editor, ":set nonumber");
+    assert!(!editor.config.display_line_numbers);
+    assert_eq!(editor.row_prefix_length, 0);
+    process_command(&mut editor, ":ln");
+    assert!(editor.config.display_line_numbers);
+
+    process_command(&mut editor, ":set stats");
+    assert!(editor.config.display_stats);
+    process_command(&mut editor, ":set stats!");
+    assert!(!editor.config.display_stats);
+    process_command(&mut editor, ":set invstats");
+    assert!(editor.config.display_stats);
+    process_command(&mut editor, ":set stats?");
+    assert_eq!(editor.message, "stats");
+    process_command(&mut editor, ":set noai");
+    process_command(&mut editor, ":set autoindent?");
+    assert_eq!(editor.message, "noautoindent");
+
+    process_command(&mut editor, ":set ts=2");
+    assert_eq!(editor.config.tab_width, 2);
+    process_command(&mut editor, ":set tabwidth");
+    assert_eq!(editor.message, "tabwidth=2");
+    process_command(&mut editor, ":set so=0");
+    assert_eq!(editor.config.scrolloff, 0);
+}
+
+#[test]
+fn test_reposition_view_around_cursor() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":100");
+    assert_position_is(&editor, 0, 40);
+    assert_eq!(editor.offset.rows, 59);
+
+    process_keystrokes(&mut editor, vec!['z', 't']);
+    assert_position_is(&editor, 0, 0);
+    assert_eq!(editor.offset.rows, 99);
+    assert_eq!(editor.current_line_number(), 100);
+
+    process_keystrokes(&mut editor, vec!['z', 'b']);
+    assert_position_is(&editor, 0, 79);
+    assert_eq!(editor.offset.rows, 20);
+    assert_eq!(editor.current_line_number(), 100);
+
+    process_keystrokes(&mut editor, vec!['z', 'z']);
+    assert_position_is(&editor, 0, 40);
+    assert_eq!(editor.offset.rows, 59);
+    assert_eq!(editor.current_line_number(), 100);
+}
+
+#[test]
+fn test_reposition_view_around_cursor_near_document_boundaries() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":5");
+    process_keystrokes(&mut editor, vec!['z', 'z']);
+    assert_position_is(&editor, 0, 4);
+    assert_eq!(editor.offset.rows, 0);
+    process_keystrokes(&mut editor, vec!['z', 'b']);
+    assert_position_is(&editor, 0, 4);
+    assert_eq!(editor.offset.rows, 0);
+    process_keystrokes(&mut editor, vec!['z', 't']);
+    assert_position_is(&editor, 0, 0);
+    assert_eq!(editor.offset.rows, 4);
+
+    process_command(&mut editor, ":195");
+    process_keystrokes(&mut editor, vec!['z', 't']);
+    assert_eq!(editor.offset.rows, 120);
+    assert_eq!(editor.current_line_number(), 195);
+    process_keystrokes(&mut editor, vec!['z', 'z']);
+    assert_eq!(editor.offset.rows, 120);
+    assert_eq!(editor.current_line_number(), 195);
+
+    // any other key cancels the command
+    process_keystrokes(&mut editor, vec!['z', 'x', 'k']);
+    assert_eq!(editor.current_line_number(), 194);
+    assert_eq!(editor.document.num_rows(), 200);
+}
+
+#[test]
+fn test_editor_undo_redo() {
+    let mut editor = get_test_editor();
+    // everything typed in a single insert session is undone at once
+    process_keystrokes(&mut editor, vec!['A', 'o', 'k', '\t']);
+    editor.process_keystroke(Key::Esc);
+    assert_nth_row_is(&editor, 0, "Hello worldok    ");
+    process_keystrokes(&mut editor, vec!['j', 'd', 'd', '0', 'l', 'x']);
+    assert_nth_row_is(&editor, 1, "Hllo world!!");
+
+    editor.process_keystroke(Key::Char('u'));
+    assert_nth_row_is(&editor, 1, "Hello world!!");
+    assert_position_is(&editor, 1, 1);
+    editor.process_keystroke(Key::Char('u'));
+    assert_nth_row_is(&editor, 1, "Hello world!");
+    assert_eq!(editor.document.line_count(), 3);
+    editor.process_keystroke(Key::Char('u'));
+    assert_current_line_is(&editor, "Hello world");
+    assert_position_is(&editor, 10, 0);
+    // undoing past the oldest change is a no-op
+    editor.process_keystroke(Key::Char('u'));
+    assert_current_line_is(&editor, "Hello world");
+
+    editor.process_keystroke(Key::Ctrl('r'));
+    assert_current_line_is(&editor, "Hello worldok    ");
+    editor.process_keystroke(Key::Ctrl('r'));
+    assert_eq!(editor.document.line_count(), 2);
+    assert_nth_row_is(&editor, 1, "Hello world!!");
+}
+
+#[test]
+fn test_editor_goto_undo_state() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['x', 'x', 'x']);
+    assert_current_line_is(&editor, "lo world");
+    assert_eq!(editor.document.undo_state(), 3);
+
+    // jump back to an earlier state, then forward to a later one
+    process_command(&mut editor, ":undo 1");
+    assert_current_line_is(&editor, "ello world");
+    assert_eq!(editor.message, "Undo state 1");
+    process_command(&mut editor, ":undo 3");
+    assert_current_line_is(&editor, "lo world");
+    process_command(&mut editor, ":undo 0");
+    assert_current_line_is(&editor, "Hello world");
+    process_command(&mut editor, ":undo 4");
+    assert_eq!(editor.message, utils::red("Undo number 4 not found"));
+
+    process_command(&mut editor, ":later 2");
+    assert_current_line_is(&editor, "llo world");
+    process_command(&mut editor, ":earlier");
+    assert_current_line_is(&editor, "ello world");
+    process_command(&mut editor, ":later 1h");
+    assert_current_line_is(&editor, "lo world");
+    process_command(&mut editor, ":earlier 1h");
+    assert_current_line_is(&editor, "Hello world");
+    process_command(&mut editor, ":earlier 1y");
+    assert_eq!(editor.message, utils::red("Invalid time span '1y'"));
+
+    // a change made after undoing gets a new number, on a new branch of the undo tree
+    process_keystrokes(&mut editor, vec!['$', 'x']);
+    assert_eq!(editor.document.undo_state(), 4);
+    assert_current_line_is(&editor, "Hello worl");
+    process_command(&mut editor, ":undo 2");
+    assert_current_line_is(&editor, "llo world");
+    process_command(&mut editor, ":undo 4");
+    assert_current_line_is(&editor, "Hello worl");
+}
+
+#[test]
+fn test_editor_undo_tree_branches() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['x', 'x', 'u']);
+    assert_current_line_is(&editor, "ello world");
+    // a new change after undoing starts a new branch
+    process_keystrokes(&mut editor, vec!['$', 'x']);
+    assert_current_line_is(&editor, "ello worl");
+    assert_eq!(editor.document.undo_state(), 3);
+
+    // u and Ctrl-R follow the current branch
+    process_keystrokes(&mut editor, vec!['u', 'u']);
+    assert_current_line_is(&editor, "Hello world");
+    editor.process_keystroke(Key::Ctrl('r'));
+    editor.process_keystroke(Key::Ctrl('r'));
+    assert_current_line_is(&editor, "ello worl");
+    editor.process_keystroke(Key::Ctrl('r'));
+    assert_eq!(editor.message, "Already at newest change");
+
+    // g- and g+ go through the states in the order they were created, across branches
+    process_keystrokes(&mut editor, vec!['g', '-']);
+    assert_current_line_is(&editor, "llo world");
+    assert_eq!(editor.document.undo_state(), 2);
+    process_keystrokes(&mut editor, vec!['g', '-']);
+    assert_current_line_is(&editor, "ello world");
+    process_keystrokes(&mut editor, vec!['2', 'g', '+']);
+    assert_current_line_is(&editor, "ello worl");
+    process_keystrokes(&mut editor, vec!['3', 'g', '-']);
+    assert_current_line_is(&editor, "Hello world");
+    process_keystrokes(&mut editor, vec!['g', '+', 'g', '+']);
+    assert_current_line_is(&editor, "llo world");
+    // the abandoned branch recovered with g+ is now the one redone
+    process_keystrokes(&mut editor, vec!['u', 'u']);
+    editor.process_keystroke(Key::Ctrl('r'));
+    editor.process_keystroke(Key::Ctrl('r'));
+    assert_current_line_is(&editor, "llo world");
+}
+
+#[test]
+fn test_editor_undo_change_operator() {
+    let mut editor = get_test_editor_with_three_sentences();
+    let first_line = editor.current_row().string.clone();
+    process_keystrokes(&mut editor, vec!['c', 'i', 's', 'N', 'e', 'w', '.']);
+    editor.process_keystroke(Key::Esc);
+    assert_ne!(editor.current_row().string, first_line);
+    editor.process_keystroke(Key::Char('u'));
+    assert_current_line_is(&editor, &first_line);
+}
+
+#[test]
+fn test_editor_replace_mode() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['w', 'R']);
+    assert_eq!(editor.mode, Mode::Replace);
+    // overtyping past the end of the line extends it
+    process_keystrokes(&mut editor, vec!['W', 'O', 'R', 'L', 'D', '!', '?']);
+    assert_current_line_is(&editor, "Hello WORLD!?");
+    assert_position_is(&editor, 13, 0);
+
+    // Backspace restores the replaced characters, and removes the appended ones
+    for _ in 0..4 {
+        editor.process_keystroke(Key::Backspace);
+    }
+    assert_current_line_is(&editor, "Hello WORld");
+    assert_position_is(&editor, 9, 0);
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(editor.mode, Mode::Normal);
+
+    // the whole replace session is undone at once
+    editor.process_keystroke(Key::Char('u'));
+    assert_current_line_is(&editor, "Hello world");
+}
+
+#[test]
+fn test_editor_replace_mode_newline() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['R', 'J', '\n', 'y']);
+    assert_nth_row_is(&editor, 0, "J");
+    assert_nth_row_is(&editor, 1, "yllo world");
+    // Backspace doesn't go back past the inserted line break
+    for _ in 0..3 {
+        editor.process_keystroke(Key::Backspace);
+    }
+    assert_nth_row_is(&editor, 1, "ello world");
+    assert_position_is(&editor, 0, 1);
+}
+
+#[test]
+fn test_editor_yank_and_paste_lines() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['y', 'y', 'p']);
+    assert_eq!(
+        *editor.registers.get(None).unwrap(),
+        Register::linewise("Hello world".to_string())
+    );
+    assert_eq!(editor.document.line_count(), 4);
+    assert_nth_row_is(&editor, 1, "Hello world");
+    assert_nth_row_is(&editor, 2, "Hello world!");
+    assert_position_is(&editor, 0, 1);
+
+    // the count applies to both the yanked lines and the pasted copies
+    process_keystrokes(&mut editor, vec!['j', '2', 'y', 'y', '2', 'P']);
+    assert_eq!(
+        *editor.registers.get(None).unwrap(),
+        Register::linewise("Hello world!\nHello world!!".to_string())
+    );
+    assert_eq!(editor.document.line_count(), 8);
+    assert_nth_row_is(&editor, 2, "Hello world!");
+    assert_nth_row_is(&editor, 3, "Hello world!!");
+    assert_nth_row_is(&editor, 4, "Hello world!");
+    assert_nth_row_is(&editor, 5, "Hello world!!");
+    assert_nth_row_is(&editor, 6, "Hello world!");
+    assert_position_is(&editor, 0, 2);
+
+    // the count is clamped to the end of the document
+    process_keystrokes(&mut editor, vec!['G', '5', 'y', 'y']);
+    assert_eq!(
+        *editor.registers.get(None).unwrap(),
+        Register::linewise("Hello world!!".to_string())
+    );
+
+    // the register survives mode changes
+    process_keystrokes(&mut editor, vec!['i', 'a']);
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(
+        *editor.registers.get(None).unwrap(),
+        Register::linewise("Hello world!!".to_string())
+    );
+}
+
+#[test]
+fn test_editor_paste_charwise() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['v', 'l', 'y', 'p']);
+    assert_eq!(
+        *editor.registers.get(None).unwrap(),
+        Register::charwise("He".to_string())
+    );
+    assert_current_line_is(&editor, "HHeello world");
+    assert_position_is(&editor, 2, 0);
+    process_keystrokes(&mut editor, vec!['2', 'P']);
+    assert_current_line_is(&editor, "HHHeHeeello world");
+    assert_position_is(&editor, 5, 0);
+    editor.process_keystroke(Key::Char('u'));
+    assert_current_line_is(&editor, "HHeello world");
+}
+
+#[test]
+fn test_editor_complete_command() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec![':', 'n']);
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, ":new");
+    let completion = editor.completion.as_ref().unwrap();
+    assert_eq!(
+        completion.candidates,
+        vec!["new", "noh", "normalize-indent"]
+    );
+    assert_eq!(completion.selected, Some(0));
+
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, ":noh");
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, ":normalize-indent");
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, ":new");
+    editor.process_keystroke(Key::BackTab);
+    assert_eq!(editor.command_buffer, ":normalize-indent");
+    editor.process_keystroke(Key::BackTab);
+    assert_eq!(editor.command_buffer, ":noh");
+
+    // typing anything else ends the completion
+    editor.process_keystroke(Key::Backspace);
+    assert!(editor.completion.is_none());
+    assert_eq!(editor.command_buffer, ":no");
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, ":noh");
+
+    // a Tab without any candidate is ignored
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec![':', 'x', '\t']);
+    assert_eq!(editor.command_buffer, ":x");
+    assert!(editor.completion.is_none());
+}
+
+#[test]
+fn test_editor_stray_tab_in_command() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['/', 'w', '\t']);
+    assert_eq!(editor.command_buffer, "/w");
+    editor.process_keystroke(Key::Esc);
+
+    // a Tab typed after the command name isn't inserted in the command
+    process_keystrokes(&mut editor, vec![':', 'q', ' ', '\t']);
+    assert_eq!(editor.command_buffer, ":q ");
+    editor.process_keystroke(Key::Char('\n'));
+    assert!(editor.should_quit);
+}
+
+#[test]
+fn test_editor_toggle_wildmenu() {
+    let mut editor = get_test_editor();
+    assert!(editor.config.wildmenu);
+    process_command(&mut editor, ":set nowildmenu");
+    assert!(!editor.config.wildmenu);
+    process_command(&mut editor, ":set wmnu");
+    assert!(editor.config.wildmenu);
+}
+
+#[test]
+fn test_editor_named_registers() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['"', 'a', 'y', 'y', 'j', 'd', 'd']);
+    assert_eq!(
+        editor.registers.get(Some('a')),
+        Some(&Register::linewise("Hello world".to_string()))
+    );
+    assert_eq!(
+        editor.registers.get(Some('1')),
+        Some(&Register::linewise("Hello world!".to_string()))
+    );
+    assert_eq!(editor.selected_register, None);
+
+    // paste from the named register rather than the unnamed one
+    process_keystrokes(&mut editor, vec!['"', 'a', 'P']);
+    assert_eq!(editor.document.line_count(), 3);
+    assert_nth_row_is(&editor, 1, "Hello world");
+    process_keystrokes(&mut editor, vec!['p']);
+    assert_nth_row_is(&editor, 2, "Hello world!");
+}
+
+#[test]
+fn test_editor_display_registers() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['y', 'y']);
+    process_command(&mut editor, ":reg");
+    assert!(editor.alternate_screen);
+    assert_eq!(
+        editor.alternate_screen_text,
+        Some(editor.registers.format())
+    );
+    editor.process_keystroke(Key::Char('q'));
+    assert!(!editor.alternate_screen);
+    process_command(&mut editor, ":registers");
+    assert!(editor.alternate_screen);
+}
+
+#[test]
+fn test_editor_display_marks() {
+    let mut editor = get_test_editor();
+    editor.marks.insert('b', Position { x: 3, y: 2 });
+    editor.marks.insert('a', Position { x: 0, y: 0 });
+    // a mark pointing past the end of the document has no preview
+    editor.marks.insert('c', Position { x: 0, y: 10 });
+    process_command(&mut editor, ":marks");
+    assert!(editor.alternate_screen);
+    assert_eq!(
+        editor.alternate_screen_text,
+        Some(String::from(
+            "mark  line  col text\n a      1    0 Hello world\n b      3    3 Hello world!!\n c     11    0"
+        ))
+    );
+}
+
+#[test]
+fn test_editor_case_insensitive_search() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("Straße STRASSE strasse")],
+        PathBuf::from("test"),
+    );
+    process_command(&mut editor, "/strasse");
+    assert_eq!(editor.search_matches.len(), 1);
+
+    process_command(&mut editor, ":set ic");
+    assert!(editor.config.ignore_case);
+    assert_eq!(editor.message, "Case insensitive search");
+    // the current matches are updated
+    assert_eq!(editor.search_matches.len(), 2);
+    assert_eq!(
+        editor.search_matches[0],
+        (Position { x: 7, y: 0 }, Position { x: 14, y: 0 })
+    );
+
+    process_command(&mut editor, ":set noic");
+    assert!(!editor.config.ignore_case);
+    assert_eq!(editor.message, "Case sensitive search");
+    process_command(&mut editor, "/STRAßE");
+    assert!(editor.search_matches.is_empty());
+    // the \c flag ignores the case of a single search
+    process_command(&mut editor, "/STRAßE\\c");
+    assert_eq!(editor.search_matches.len(), 1);
+    assert_position_is(&editor, 0, 0);
+
+    process_command(&mut editor, ":set ic!");
+    assert!(editor.config.ignore_case);
+}
+
+#[test]
+fn test_editor_substitute() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("a b a"), Row::from("a c"), Row::from("a/b")],
+        PathBuf::from("test"),
+    );
+    process_command(&mut editor, ":s/a/x/");
+    assert_eq!(editor.message, "1 substitutions");
+    assert_nth_row_is(&editor, 0, "x b a");
+    assert_nth_row_is(&editor, 1, "a c");
+    assert_eq!(editor.unsaved_edits, 1);
+
+    process_command(&mut editor, ":%s/a/y/g");
+    assert_eq!(editor.message, "3 substitutions");
+    assert_nth_row_is(&editor, 0, "x b y");
+    assert_nth_row_is(&editor, 1, "y c");
+    assert_nth_row_is(&editor, 2, "y/b");
+
+    process_command(&mut editor, ":%s/y\\/b/a b/");
+    assert_nth_row_is(&editor, 2, "a b");
+    process_command(&mut editor, ":s/z/x/");
+    assert_eq!(editor.message, utils::red("Pattern not found: z"));
+
+    // the whole substitution is undone at once
+    process_command(&mut editor, ":%s/[a-z]/_/g");
+    assert_nth_row_is(&editor, 0, "_ _ _");
+    assert_nth_row_is(&editor, 2, "_ _");
+    editor.process_keystroke(Key::Char('u'));
+    assert_nth_row_is(&editor, 0, "x b y");
+    assert_nth_row_is(&editor, 2, "a b");
+}
+
+#[test]
+fn test_editor_file_info() {
+    let mut editor = get_test_editor_with_long_document();
+    editor.document.filename = Some(PathBuf::from("not-a-file.txt"));
+    process_command(&mut editor, ":50");
+    editor.process_keystroke(Key::Ctrl('g'));
+    assert_eq!(
+        editor.message,
+        "\"not-a-file.txt\" 200 lines --25%-- [unix]"
+    );
+
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("a")], PathBuf::from("Cargo.toml"));
+    editor.process_keystroke(Key::Char('x'));
+    editor.process_keystroke(Key::Ctrl('g'));
+    let path = fs::canonicalize("Cargo.toml").unwrap();
+    assert_eq!(
+        editor.message,
+        format!("\"{}\" [Modified] 1 line --100%-- [unix]", path.display())
+    );
+}
+
+#[test]
+fn test_editor_jump_list() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":50");
+    editor.process_keystroke(Key::Char('G'));
+    process_keystrokes(&mut editor, vec!['g', 'g']);
+    assert_eq!(editor.jump_list.len(), 3);
+
+    editor.process_keystroke(Key::Ctrl('o'));
+    assert_eq!(editor.current_line_number(), 200);
+    editor.process_keystroke(Key::Ctrl('o'));
+    assert_eq!(editor.current_line_number(), 50);
+    // we're at the oldest jump, the jump from the first line having been replaced
+    // by the jump back to it
+    editor.process_keystroke(Key::Ctrl('o'));
+    assert_eq!(editor.current_line_number(), 50);
+
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.current_line_number(), 200);
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.current_line_number(), 1);
+    // we're back at the newest position
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.current_line_number(), 1);
+}
+
+#[test]
+fn test_editor_display_jumps() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['l', 'G', 'g', 'g']);
+    editor.process_keystroke(Key::Ctrl('o'));
+    process_command(&mut editor, ":jumps");
+    assert_eq!(
+        editor.alternate_screen_text,
+        Some(String::from(
+            " jump line  col text\n>   0    3    0 Hello world!!\n    1    1    0 Hello world"
+        ))
+    );
+    editor.process_keystroke(Key::Char('q'));
+
+    // jumps to deleted lines are skipped
+    process_keystrokes(&mut editor, vec!['d', 'd']);
+    process_command(&mut editor, ":jumps");
+    assert_eq!(
+        editor.alternate_screen_text,
+        Some(String::from(
+            " jump line  col text\n    1    1    0 Hello world"
+        ))
+    );
+}
+
+#[test]
+fn test_editor_change_inner_brackets_from_before_the_pair() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("foo(bar, baz) + 1"), Row::from("[1, 2]")],
+        PathBuf::from("test"),
+    );
+    process_keystrokes(&mut editor, vec!['c', 'i', '(', 'x']);
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_nth_row_is(&editor, 0, "foo(x) + 1");
+    assert_eq!(editor.registers.get(None).unwrap().text, "bar, baz");
+
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['j', '0']);
+    process_keystrokes(&mut editor, vec!['d', 'i', ']']);
+    assert_nth_row_is(&editor, 1, "[]");
+    assert_position_is(&editor, 1, 1);
+}
+
+#[test]
+fn test_editor_delete_around_brackets() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(vec![Row::from("f(a, (b))")], PathBuf::from("test"));
+    editor.goto_x_y(6, 0);
+    process_keystrokes(&mut editor, vec!['d', 'a', 'b']);
+    assert_nth_row_is(&editor, 0, "f(a, )");
+    // nothing happens when no pair can be found
+    process_keystrokes(&mut editor, vec!['$', 'd', 'i', '{']);
+    assert_nth_row_is(&editor, 0, "f(a, )");
+}
+
+#[test]
+fn test_source_config_file() {
+    let mut editor = get_test_editor();
+    let mut f = NamedTempFile::new().unwrap();
+    f.write_all(b"display_line_numbers = true\ntab_width = 2\n")
+        .unwrap();
+    let path = f.path().to_str().unwrap().to_string();
+    process_command(&mut editor, &format!(":source {path}"));
+    assert_eq!(editor.message, format!("\"{path}\" sourced"));
+    assert!(editor.config.display_line_numbers);
+    assert_eq!(editor.config.tab_width, 2);
+    assert_eq!(editor.row_prefix_length, super::START_X);
+
+    // the current config is kept when the file can't be parsed
+    f.write_all(b"tab_width = ").unwrap();
+    process_command(&mut editor, &format!(":source {path}"));
+    assert!(editor
+        .message
+        .starts_with(&format!("{}Couldn't parse {path}", color::Fg(color::Red))));
+    assert!(editor.config.display_line_numbers);
+    assert_eq!(editor.config.tab_width, 2);
+}
+
+fn temp_file_with_content(content: &str) -> (NamedTempFile, String) {
+    let mut f = NamedTempFile::new().unwrap();
+    f.write_all(content.as_bytes()).unwrap();
+    let path = f.path().to_str().unwrap().to_string();
+    (f, path)
+}
+
+#[test]
+fn test_buffers_switching_restores_cursor_position() {
+    let (_f1, path1) = temp_file_with_content("a\nb\nc\n");
+    let (_f2, path2) = temp_file_with_content("x\ny\n");
+    let mut editor = new_test_editor(Some(path1.clone()), Box::new(MockConsole::default()));
+    process_keystrokes(&mut editor, vec!['j', 'j']);
+    process_command(&mut editor, &format!(":open {path2}"));
+    assert_current_line_is(&editor, "x");
+    assert_position_is(&editor, 0, 0);
+    process_keystrokes(&mut editor, vec!['j']);
+
+    process_command(&mut editor, ":bn");
+    assert_eq!(editor.document.filename, Some(PathBuf::from(&path1)));
+    assert_eq!(editor.message, format!("\"{path1}\" 1/2"));
+    assert_current_line_is(&editor, "c");
+    process_command(&mut editor, ":bp");
+    assert_current_line_is(&editor, "y");
+    // opening a file twice switches to its buffer
+    process_command(&mut editor, &format!(":o {path1}"));
+    assert_current_line_is(&editor, "c");
+    assert_eq!(editor.buffer_count(), 2);
+}
+
+#[test]
+fn test_marks_are_kept_with_their_buffer() {
+    let (_f1, path1) = temp_file_with_content("a\nb\nc\n");
+    let (_f2, path2) = temp_file_with_content("x\ny\n");
+    let mut editor = new_test_editor(Some(path1), Box::new(MockConsole::default()));
+    process_keystrokes(&mut editor, vec!['j', 'j', 'm', 'a']);
+    process_command(&mut editor, &format!(":open {path2}"));
+    process_keystrokes(&mut editor, vec!['`', 'a']);
+    assert!(editor.message.contains("Mark a is not set"));
+    process_command(&mut editor, ":marks");
+    assert_eq!(
+        editor.alternate_screen_text.as_deref(),
+        Some("mark  line  col text")
+    );
+    editor.process_keystroke(Key::Char('q'));
+
+    // the lines added to the second buffer don't shift the marks of the first one
+    process_keystrokes(&mut editor, vec!['j', 'm', 'a', 'g', 'g', 'O', 'z']);
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(editor.marks.get(&'a'), Some(&Position { x: 0, y: 2 }));
+    process_command(&mut editor, ":bn");
+    process_keystrokes(&mut editor, vec!['g', 'g', '`', 'a']);
+    assert_current_line_is(&editor, "c");
+    process_command(&mut editor, ":bp");
+    process_keystrokes(&mut editor, vec!['`', 'a']);
+    assert_current_line_is(&editor, "y");
+}
+
+#[test]
+fn test_buffers_list_and_quit() {
+    let (_f1, path1) = temp_file_with_content("a\n");
+    let (_f2, path2) = temp_file_with_content("b\n");
+    let mut editor = new_test_editor(Some(path1.clone()), Box::new(MockConsole::default()));
+    process_command(&mut editor, &format!(":open {path2}"));
+    process_keystrokes(&mut editor, vec!['x']);
+    process_command(&mut editor, ":ls");
+    assert_eq!(editor.message, format!("1 \"{path1}\"  2%+ \"{path2}\""));
+
+    process_command(&mut editor, ":bp");
+    process_command(&mut editor, ":qa");
+    assert!(!editor.should_quit);
+    assert_eq!(
+        editor.message,
+        utils::red(&format!("Unsaved changes in {path2}! Run :q! to override"))
+    );
+    // :q closes the current buffer, and quits once it's the last one
+    process_command(&mut editor, ":q");
+    assert!(!editor.should_quit);
+    assert_eq!(editor.document.filename, Some(PathBuf::from(&path2)));
+    process_command(&mut editor, ":q");
+    assert!(!editor.should_quit);
+    process_command(&mut editor, ":q!");
+    assert!(editor.should_quit);
+}
+
+#[test]
+fn test_start_in_insert_mode() {
+    let mut editor = get_test_editor();
+    assert_eq!(editor.mode, Mode::Normal);
+    editor.start_in_insert_mode();
+    editor.start_in_insert_mode();
+    assert_eq!(editor.mode, Mode::Insert);
+    process_keystrokes(&mut editor, vec!['a', 'b']);
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['u']);
+    assert_nth_row_is(&editor, 0, "Hello world");
+}
+
+#[test]
+fn test_split_screen_panes_share_the_buffer() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":60");
+    process_command(&mut editor, ":split");
+    assert_eq!(editor.view_height(), 40);
+    // the cursor stays visible in the smaller pane
+    assert_eq!(editor.current_line_number(), 60);
+    assert!(editor.cursor_position.y < 40);
+
+    process_keystrokes(&mut editor, vec!['d', 'd', 'g', 'g']);
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['j']);
+    assert!(!editor.top_pane_focused);
+    assert_eq!(editor.view_height(), 39);
+    assert_eq!(editor.focused_pane_top(), 41);
+    // the bottom pane kept its own cursor, and sees the edit made in the top one
+    assert_eq!(editor.current_line_number(), 60);
+    assert_eq!(editor.document.line_count(), 199);
+    assert!(editor
+        .generate_pane_status(editor.split.as_ref().unwrap())
+        .contains("Ln 1, Col 1"));
+
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['k']);
+    assert!(editor.top_pane_focused);
+    assert_eq!(editor.current_line_number(), 1);
+}
+
+#[test]
+fn test_split_screen_with_two_buffers() {
+    let (_f1, path1) = temp_file_with_content("a\n");
+    let (_f2, path2) = temp_file_with_content("b\n");
+    let mut editor = new_test_editor(Some(path1.clone()), Box::new(MockConsole::default()));
+    process_command(&mut editor, ":sp");
+    process_command(&mut editor, &format!(":open {path2}"));
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['w']);
+    assert_eq!(editor.document.filename, Some(PathBuf::from(&path1)));
+
+    process_command(&mut editor, ":split");
+    assert_eq!(editor.message, utils::red("The screen is already split"));
+    // :q closes the focused pane, the other one taking the whole screen
+    process_command(&mut editor, ":q");
+    assert!(!editor.should_quit);
+    assert!(editor.split.is_none());
+    assert_eq!(editor.document.filename, Some(PathBuf::from(&path2)));
+    assert_eq!(editor.view_height(), 80);
+}
+
+#[test]
+fn test_commands_without_argument() {
+    let mut editor = get_test_editor();
+    for command in [":open", ":o ", ":open  "] {
+        process_command(&mut editor, command);
+        assert_eq!(editor.message, utils::red("No file name"));
+        assert_eq!(editor.document.filename, Some(PathBuf::from("test")));
+    }
+
+    for command in [":new", ":new "] {
+        editor.process_keystroke(Key::Esc);
+        process_command(&mut editor, command);
+        assert_eq!(editor.mode, Mode::Insert);
+        assert_eq!(editor.document.filename, None);
+        assert_current_line_is(&editor, "");
+    }
+    // the first unnamed buffer was replaced by the second one
+    assert_eq!(editor.buffer_count(), 2);
+
+    editor.process_keystroke(Key::Esc);
+    for command in [":w", ":w ", ":wq "] {
+        process_command(&mut editor, command);
+        assert_eq!(editor.message, utils::red("No file name"));
+    }
+    assert!(!editor.should_quit);
+}
+
+#[test]
+fn test_yank_and_paste_with_the_clipboard_register() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['"', '+', 'y', 'y', 'j']);
+    assert_eq!(
+        editor.registers.get(Some('+')).unwrap().to_clipboard(),
+        "Hello world\n"
+    );
+    // without clipboard support, the text last stored in the "+ register is pasted
+    process_keystrokes(&mut editor, vec!['"', '+', 'p']);
+    assert_nth_row_is(&editor, 2, "Hello world");
+    assert_eq!(editor.document.line_count(), 4);
+}
+
+#[test]
+fn test_quit_unnamed_buffer_with_unsaved_changes() {
+    let mut editor = get_test_editor();
+    editor.document = Document::default();
+    editor.document.ensure_has_a_row();
+    editor.last_saved_hash = editor.document.hashed();
+    process_keystrokes(&mut editor, vec!['i', 'a']);
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":q");
+    assert!(!editor.should_quit);
+    assert_eq!(
+        editor.message,
+        utils::red("No file name! Run :w <name> to save the changes, or :q! to discard them")
+    );
+}
+
+#[test]
+fn test_insert_date() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":date %Y");
+    let year = chrono::Local::now().format("%Y").to_string();
+    assert_current_line_is(&editor, &format!("{year}Hello world"));
+    assert_position_is(&editor, 3, 0);
+
+    process_command(&mut editor, ":date %Q");
+    assert_eq!(editor.message, utils::red("Invalid date format '%Q'"));
+    editor.config.date_format = String::from("%m");
+    process_keystrokes(&mut editor, vec!['j', '0', 'g', 's']);
+    assert_nth_row_is(
+        &editor,
+        1,
+        &format!("{}Hello world!", chrono::Local::now().format("%m")),
+    );
+}
+
+#[test]
+fn test_scroll_half_page() {
+    let mut editor = get_test_editor_with_long_document();
+    editor.process_keystroke(Key::Ctrl('d'));
+    assert_eq!(editor.current_line_number(), 41);
+    assert_eq!(editor.offset.rows, 40);
+    assert_position_is(&editor, 0, 0);
+    editor.process_keystroke(Key::Ctrl('u'));
+    assert_eq!(editor.current_line_number(), 1);
+    assert_eq!(editor.offset.rows, 0);
+    editor.process_keystroke(Key::Ctrl('u'));
+    assert_eq!(editor.current_line_number(), 1);
+}
+
+#[test]
+fn test_scroll_custom_amount() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":set scroll=10");
+    editor.process_keystroke(Key::Ctrl('d'));
+    assert_eq!(editor.current_line_number(), 11);
+    assert_eq!(editor.offset.rows, 10);
+
+    // a count scrolls by that many lines, and becomes the new scroll amount
+    process_keystrokes(&mut editor, vec!['2', '5']);
+    editor.process_keystroke(Key::Ctrl('d'));
+    assert_eq!(editor.current_line_number(), 36);
+    assert_eq!(editor.config.scroll, 25);
+    editor.process_keystroke(Key::Ctrl('u'));
+    assert_eq!(editor.current_line_number(), 11);
+    assert_eq!(editor.offset.rows, 10);
+
+    // 0 goes back to scrolling by half of the view
+    process_command(&mut editor, ":set scroll=0");
+    editor.process_keystroke(Key::Ctrl('d'));
+    assert_eq!(editor.current_line_number(), 51);
+}
+
+#[test]
+fn test_scroll_full_page() {
+    let mut editor = get_test_editor_with_long_document();
+    editor.process_keystroke(Key::Ctrl('f'));
+    assert_eq!(editor.current_line_number(), 81);
+    assert_eq!(editor.offset.rows, 80);
+    editor.process_keystroke(Key::Ctrl('f'));
+    assert_eq!(editor.current_line_number(), 161);
+    // the view doesn't go past the end of the document
+    assert_eq!(editor.offset.rows, 120);
+    editor.process_keystroke(Key::Ctrl('f'));
+    assert_eq!(editor.current_line_number(), 200);
+    assert_eq!(editor.offset.rows, 120);
+    editor.process_keystroke(Key::Ctrl('b'));
+    assert_eq!(editor.current_line_number(), 120);
+    assert_eq!(editor.offset.rows, 40);
+    editor.process_keystroke(Key::Ctrl('b'));
+    assert_eq!(editor.current_line_number(), 40);
+    assert_eq!(editor.offset.rows, 0);
+}
+
+#[test]
+fn test_scroll_keeps_cursor_column() {
+    let mut editor = get_test_editor_with_long_document();
+    editor.document.insert_string(" that is longer", 9, 40);
+    process_keystrokes(&mut editor, vec!['w']);
+    assert_position_is(&editor, 5, 0);
+    editor.process_keystroke(Key::Ctrl('d'));
+    assert_current_line_is(&editor, "Some line that is longer");
+    assert_eq!(editor.current_x_position(), 5);
+    process_keystrokes(&mut editor, vec!['$']);
+    editor.process_keystroke(Key::Ctrl('d'));
+    // the cursor is clamped to the end of the shorter line
+    assert_eq!(editor.current_line_number(), 81);
+    assert_eq!(editor.current_x_position(), 8);
+}
+
+fn get_test_editor_with_lines(lines: &[&str]) -> Editor {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        lines.iter().map(|line| Row::from(*line)).collect(),
+        PathBuf::from("test"),
+    );
+    editor
+}
+
+#[test]
+fn test_range_delete_whole_document() {
+    let mut editor = get_test_editor_with_long_document();
+    process_command(&mut editor, ":%d");
+    assert_eq!(editor.document.line_count(), 1);
+    assert_nth_row_is(&editor, 0, "");
+    assert_position_is(&editor, 0, 0);
+    assert_eq!(editor.message, "200 fewer lines");
+    editor.process_keystroke(Key::Char('u'));
+    assert_eq!(editor.document.line_count(), 200);
+}
+
+#[test]
+fn test_range_delete_lines() {
+    let mut editor = get_test_editor_with_lines(&["a", "b", "c", "d", "e"]);
+    process_command(&mut editor, ":2,3d");
+    assert_eq!(editor.document.line_count(), 3);
+    assert_current_line_is(&editor, "d");
+    process_keystrokes(&mut editor, vec!['p']);
+    assert_nth_row_is(&editor, 2, "b");
+    assert_nth_row_is(&editor, 3, "c");
+    assert_current_line_is(&editor, "b");
+
+    process_command(&mut editor, ":.,$d");
+    assert_eq!(editor.document.line_count(), 2);
+    assert_current_line_is(&editor, "d");
+    process_command(&mut editor, ":d");
+    assert_eq!(editor.document.line_count(), 1);
+    assert_current_line_is(&editor, "a");
+}
+
+#[test]
+fn test_range_filter_through_shell_command() {
+    let mut editor = get_test_editor_with_lines(&["pear", "apple", "fig"]);
+    process_command(&mut editor, ":%!sort");
+    assert_nth_row_is(&editor, 0, "apple");
+    assert_nth_row_is(&editor, 1, "fig");
+    assert_nth_row_is(&editor, 2, "pear");
+    assert_eq!(editor.message, "3 lines filtered");
+
+    process_command(&mut editor, ":2,$!tr a-z A-Z");
+    assert_nth_row_is(&editor, 0, "apple");
+    assert_nth_row_is(&editor, 1, "FIG");
+    assert_nth_row_is(&editor, 2, "PEAR");
+    editor.process_keystroke(Key::Char('u'));
+    assert_nth_row_is(&editor, 2, "pear");
+
+    process_command(&mut editor, ":%!false");
+    assert!(editor.message.contains("exited with"));
+    assert_eq!(editor.document.line_count(), 3);
+}
+
+#[test]
+fn test_range_sort() {
+    let mut editor = get_test_editor_with_lines(&["c", "b", "a", "0"]);
+    process_command(&mut editor, ":1,3sort");
+    assert_nth_row_is(&editor, 0, "a");
+    assert_nth_row_is(&editor, 2, "c");
+    assert_nth_row_is(&editor, 3, "0");
+    process_command(&mut editor, ":sort");
+    assert_nth_row_is(&editor, 0, "0");
+    assert_nth_row_is(&editor, 3, "c");
+}
+
+#[test]
+fn test_range_substitute_and_goto() {
+    let mut editor = get_test_editor_with_lines(&["a", "a", "a", "a"]);
+    process_command(&mut editor, ":2,3s/a/b/");
+    assert_eq!(editor.message, "2 substitutions");
+    assert_nth_row_is(&editor, 0, "a");
+    assert_nth_row_is(&editor, 1, "b");
+    assert_nth_row_is(&editor, 2, "b");
+    assert_nth_row_is(&editor, 3, "a");
+    process_command(&mut editor, ":$");
+    assert_eq!(editor.current_line_number(), 4);
+    process_command(&mut editor, ":%x");
+    assert_eq!(editor.message, utils::red("Unknown command 'x'"));
+}
+
+#[test]
+fn test_session_save_and_restore() {
+    let (_f1, path1) = temp_file_with_content("a\nb\nc\n");
+    let (_f2, path2) = temp_file_with_content("xyz\ny\n");
+    let mut editor = new_test_editor(Some(path1.clone()), Box::new(MockConsole::default()));
+    process_keystrokes(&mut editor, vec!['j', 'j']);
+    process_command(&mut editor, &format!(":open {path2}"));
+    process_keystrokes(&mut editor, vec!['l', 'l']);
+    process_command(&mut editor, ":sp");
+    process_command(&mut editor, ":bp");
+    process_command(&mut editor, ":new");
+    let session = editor.session();
+    // the unnamed buffer is left out
+    assert_eq!(session.buffers.len(), 2);
+    assert_eq!(
+        session.buffers[0].filename,
+        fs::canonicalize(&path1).unwrap()
+    );
+    assert_eq!((session.buffers[0].line, session.buffers[0].column), (2, 0));
+    assert_eq!((session.buffers[1].line, session.buffers[1].column), (0, 2));
+    assert_eq!(session.split.unwrap().buffer, 1);
+
+    let mut editor = get_test_editor();
+    editor.restore_session(&session);
+    assert_eq!(editor.buffer_count(), 2);
+    assert!(editor.split.is_some());
+    assert!(editor.top_pane_focused);
+    assert_eq!(editor.split.as_ref().unwrap().buffer, 1);
+    assert_current_line_is(&editor, "c");
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['j']);
+    assert_current_line_is(&editor, "xyz");
+    assert_eq!(editor.current_x_position(), 2);
+}
+
+#[test]
+fn test_session_restore_skips_missing_files() {
+    let (_f, path) = temp_file_with_content("a\nb\n");
+    let session = Session {
+        buffers: vec![
+            SessionBuffer {
+                filename: PathBuf::from("/does/not/exist"),
+                line: 0,
+                column: 0,
+            },
+            SessionBuffer {
+                filename: PathBuf::from(&path),
+                line: 10,
+                column: 3,
+            },
+        ],
+        current_buffer: 1,
+        split: Some(SessionPane {
+            buffer: 0,
+            line: 0,
+            column: 0,
+        }),
+        top_pane_focused: true,
+    };
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['x']);
+    editor.restore_session(&session);
+    assert_eq!(
+        editor.message,
+        utils::red("Unsaved changes! Save them before loading a session")
+    );
+
+    let mut editor = get_test_editor();
+    editor.restore_session(&session);
+    assert_eq!(
+        editor.message,
+        utils::red("Skipped the missing files: /does/not/exist")
+    );
+    assert_eq!(editor.buffer_count(), 1);
+    assert!(editor.split.is_none());
+    // the cursor position is kept within the document
+    assert_current_line_is(&editor, "b");
+    assert_eq!(editor.current_x_position(), 0);
+
+    editor.restore_session(&Session::default());
+    assert_eq!(
+        editor.message,
+        utils::red("None of the session files exist anymore")
+    );
+    process_command(&mut editor, ":mksession");
+    assert_eq!(editor.message, utils::red("No session name"));
+}
+
+#[test]
+fn test_browse_command_history() {
+    let mut editor = get_test_editor();
+    process_command(&mut editor, ":set ic");
+    process_command(&mut editor, ":stats");
+    process_command(&mut editor, ":set noic");
+    process_command(&mut editor, "/world");
+
+    process_keystrokes(&mut editor, vec![':']);
+    editor.process_keystroke(Key::Up);
+    assert_eq!(editor.command_buffer, ":set noic");
+    editor.process_keystroke(Key::Up);
+    assert_eq!(editor.command_buffer, ":stats");
+    editor.process_keystroke(Key::Down);
+    assert_eq!(editor.command_buffer, ":set noic");
+    // going down past the most recent entry restores the typed text
+    editor.process_keystroke(Key::Down);
+    assert_eq!(editor.command_buffer, ":");
+
+    // only the entries starting with the typed text are browsed
+    process_keystrokes(&mut editor, vec!['s', 'e']);
+    editor.process_keystroke(Key::Up);
+    editor.process_keystroke(Key::Up);
+    assert_eq!(editor.command_buffer, ":set ic");
+    editor.process_keystroke(Key::Up);
+    assert_eq!(editor.command_buffer, ":set ic");
+    editor.process_keystroke(Key::Esc);
+
+    // the search prompt browses the search history
+    process_keystrokes(&mut editor, vec!['/']);
+    editor.process_keystroke(Key::Up);
+    assert_eq!(editor.command_buffer, "/world");
+}
+
+#[test]
+fn test_goto_matching_tag() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![
+            Row::from("<ul>"),
+            Row::from("  <li>(a)</li>"),
+            Row::from("</ul>"),
+        ],
+        PathBuf::from("test.html"),
+    );
+    process_keystrokes(&mut editor, vec!['%']);
+    assert_position_is(&editor, 0, 2);
+    process_keystrokes(&mut editor, vec!['%']);
+    assert_position_is(&editor, 0, 0);
+    // the brackets are still matched outside of the tags
+    process_keystrokes(&mut editor, vec!['j', 'f', '(', '%']);
+    assert_position_is(&editor, 8, 1);
+
+    // tags are only matched in HTML and XML files
+    editor.document.filename = Some(PathBuf::from("test.txt"));
+    process_keystrokes(&mut editor, vec!['g', 'g', '%']);
+    assert_position_is(&editor, 0, 0);
+}
+
+#[test]
+fn test_editor_complete_filename() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src").join("editor.rs"), "").unwrap();
+    fs::write(dir.path().join("src").join("editor_test.rs"), "").unwrap();
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, format!(":open {path}/s").chars().collect());
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, format!(":open {path}/src/"));
+
+    // Tab goes on with the content of the directory, completing the common prefix first
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, format!(":open {path}/src/editor"));
+    assert_eq!(editor.completion.as_ref().unwrap().candidates.len(), 2);
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(editor.command_buffer, format!(":open {path}/src/editor.rs"));
+    editor.process_keystroke(Key::Char('\t'));
+    assert_eq!(
+        editor.command_buffer,
+        format!(":open {path}/src/editor_test.rs")
+    );
+    editor.process_keystroke(Key::Char('\n'));
+    assert_eq!(
+        editor.document.filename,
+        Some(dir.path().join("src").join("editor_test.rs"))
+    );
+
+    // the commands not taking a file name aren't completed
+    process_keystrokes(&mut editor, format!(":set {path}/s\t").chars().collect());
+    assert_eq!(editor.command_buffer, format!(":set {path}/s"));
+}
+
+#[test]
+fn test_repeat_last_change() {
+    let mut editor = get_test_editor_with_lines(&["abcdef", "ghijkl", "mnopqr", "stuvwx"]);
+    process_keystrokes(&mut editor, vec!['x', '.']);
+    assert_current_line_is(&editor, "cdef");
+    // motions aren't changes
+    process_keystrokes(&mut editor, vec!['j', 'l', '.']);
+    assert_current_line_is(&editor, "gijkl");
+    process_keystrokes(&mut editor, vec!['g', 'g', 'd', 'd', 'j', '.']);
+    assert_eq!(editor.document.line_count(), 2);
+    assert_nth_row_is(&editor, 0, "gijkl");
+    assert_nth_row_is(&editor, 1, "stuvwx");
+}
+
+#[test]
+fn test_repeat_last_change_with_count() {
+    let mut editor = get_test_editor_with_long_document();
+    process_keystrokes(&mut editor, vec!['y', 'y', '2', 'p', '.']);
+    assert_eq!(editor.document.line_count(), 204);
+    // a count replaces the one of the change
+    process_keystrokes(&mut editor, vec!['3', '.']);
+    assert_eq!(editor.document.line_count(), 207);
+    process_keystrokes(&mut editor, vec!['.']);
+    assert_eq!(editor.document.line_count(), 209);
+}
+
+#[test]
+fn test_repeat_last_insert() {
+    let mut editor = get_test_editor_with_lines(&["a", "b"]);
+    process_keystrokes(&mut editor, vec!['i', 'x', 'y']);
+    editor.process_keystroke(Key::Esc);
+    assert_current_line_is(&editor, "xya");
+    process_keystrokes(&mut editor, vec!['j', '.']);
+    assert_current_line_is(&editor, "xyb");
+    process_keystrokes(&mut editor, vec!['2', '.']);
+    assert_current_line_is(&editor, "xyxyxyb");
+
+    process_keystrokes(&mut editor, vec!['o', 'c']);
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['.']);
+    assert_eq!(editor.document.line_count(), 4);
+    assert_nth_row_is(&editor, 3, "c");
+    // leaving insert mode without typing anything isn't a change
+    process_keystrokes(&mut editor, vec!['i']);
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['.']);
+    assert_eq!(editor.document.line_count(), 5);
+}
+
+#[test]
+fn test_only_changed_rows_are_redrawn_in_insert_mode() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['j', 'i', 'x']);
+    assert_eq!(editor.changed_rows, Some(1..2));
+    // the rows located below a split line are shifted
+    editor.process_keystroke(Key::Char('\n'));
+    assert_eq!(editor.changed_rows, Some(1..editor.view_height()));
+    editor.process_keystroke(Key::Backspace);
+    assert_eq!(editor.changed_rows, Some(1..editor.view_height()));
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(editor.changed_rows, None);
+    process_keystrokes(&mut editor, vec!['x']);
+    assert_eq!(editor.changed_rows, None);
+
+    let mut editor = get_test_editor_with_long_document();
+    process_keystrokes(&mut editor, vec!['G', 'o']);
+    assert_eq!(editor.changed_rows, None);
+    process_keystrokes(&mut editor, vec!['a']);
+    assert_eq!(editor.changed_rows, Some(200..201));
+}
+
+/// Time the screen refreshes following a key typed in insert mode, with and without
+/// redrawing the whole screen, on a 200 lines document in an 80 rows terminal. Run it
+/// with `cargo test --release bench_ -- --ignored --nocapture > /dev/null`
+#[test]
+#[ignore = "timing benchmark, run it in release mode"]
+fn bench_refresh_screen_in_insert_mode() {
+    let console = Box::new(MockConsole {
+        size: Size {
+            height: 80,
+            width: 80,
+        },
+        ..MockConsole::default()
+    });
+    let mut editor = new_test_editor(None, console);
+    editor.document = get_long_document();
+    process_keystrokes(&mut editor, vec!['2', '0', 'G', 'A']);
+    let iterations: u32 = 1000;
+
+    let mut time_refreshes = |full_redraw: bool| {
+        let start = Instant::now();
+        for i in 0..iterations {
+            if i % 2 == 0 {
+                editor.process_keystroke(Key::Char('x'));
+            } else {
+                editor.process_keystroke(Key::Backspace);
+            }
+            if full_redraw {
+                editor.changed_rows = None;
+            }
+            editor.refresh_screen().unwrap();
+        }
+        start.elapsed() / iterations
+    };
+    let changed_rows = time_refreshes(false);
+    let whole_screen = time_refreshes(true);
+
+    eprintln!("refresh: {changed_rows:?} (changed rows), {whole_screen:?} (whole screen)");
+    assert_nth_row_is(&editor, 19, "Some line");
+    assert!(changed_rows < whole_screen);
+}
+
+#[test]
+fn test_smart_home() {
+    let mut editor = get_test_editor_with_lines(&["    let a = 1;"]);
+    process_keystrokes(&mut editor, vec!['$', '0']);
+    assert_position_is(&editor, 0, 0);
+    process_keystrokes(&mut editor, vec!['$']);
+    editor.process_keystroke(Key::Home);
+    assert_position_is(&editor, 0, 0);
+
+    editor.config.smart_home = true;
+    process_keystrokes(&mut editor, vec!['$']);
+    editor.process_keystroke(Key::Home);
+    assert_position_is(&editor, 4, 0);
+    editor.process_keystroke(Key::Home);
+    assert_position_is(&editor, 0, 0);
+    process_keystrokes(&mut editor, vec!['0']);
+    assert_position_is(&editor, 4, 0);
+    process_keystrokes(&mut editor, vec!['0']);
+    assert_position_is(&editor, 0, 0);
+    // Home also toggles in insert mode
+    process_keystrokes(&mut editor, vec!['A']);
+    editor.process_keystroke(Key::Home);
+    assert_position_is(&editor, 4, 0);
+}
+
+#[test]
+fn test_marks() {
+    let mut editor = get_test_editor_with_lines(&["a", "bcdef", "g", "h"]);
+    process_keystrokes(&mut editor, vec!['j', '$', 'm', 'a', 'g', 'g', '`', 'a']);
+    assert_position_is(&editor, 4, 1);
+    assert_eq!(editor.marks.get(&'a'), Some(&Position { x: 4, y: 1 }));
+
+    // marks follow their line when lines are added or removed above it
+    process_keystrokes(&mut editor, vec!['g', 'g', 'O', 'z']);
+    editor.process_keystroke(Key::Esc);
+    assert_eq!(editor.marks.get(&'a'), Some(&Position { x: 4, y: 2 }));
+    process_keystrokes(&mut editor, vec!['d', 'd', 'd', 'd']);
+    assert_eq!(editor.marks.get(&'a'), Some(&Position { x: 4, y: 0 }));
+    process_keystrokes(&mut editor, vec!['u']);
+    assert_eq!(editor.marks.get(&'a'), Some(&Position { x: 4, y: 1 }));
+    // lines added or removed below the mark don't move it
+    process_keystrokes(&mut editor, vec!['G', 'd', 'd']);
+    assert_eq!(editor.marks.get(&'a'), Some(&Position { x: 4, y: 1 }));
+
+    // the cursor gets as close as possible to the mark when its line got shorter
+    process_keystrokes(&mut editor, vec!['`', 'a', 'A']);
+    for _ in 0..3 {
+        editor.process_keystroke(Key::Backspace);
+    }
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['g', 'g', '`', 'a']);
+    assert_nth_row_is(&editor, 1, "bc");
+    assert_position_is(&editor, 1, 1);
+
+    process_keystrokes(&mut editor, vec!['`', 'b']);
+    assert!(editor.message.contains("Mark b is not set"));
+    process_keystrokes(&mut editor, vec!['m', 'B']);
+    assert!(editor
+        .message
+        .contains("Marks are named with a letter from a to z"));
+    assert_eq!(editor.marks.len(), 1);
+}
+
+#[test]
+fn test_window_commands() {
+    let (_f1, path1) = temp_file_with_content("a\n");
+    let (_f2, path2) = temp_file_with_content("b\n");
+    let mut editor = new_test_editor(Some(path1.clone()), Box::new(MockConsole::default()));
+    process_command(&mut editor, &format!(":open {path2}"));
+    // without a split screen, Ctrl-W w switches to the next buffer
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['w']);
+    assert_eq!(editor.document.filename, Some(PathBuf::from(&path1)));
+
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['v']);
+    assert!(editor
+        .message
+        .contains("Vertical splits aren't supported yet"));
+    assert!(editor.split.is_none());
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['s']);
+    assert!(editor.split.is_some());
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['l']);
+    assert!(editor
+        .message
+        .contains("There is no pane on the left or right"));
+    assert!(editor.top_pane_focused);
+    editor.process_keystroke(Key::Ctrl('w'));
+    process_keystrokes(&mut editor, vec!['q']);
+    assert!(editor.split.is_none());
+    assert!(!editor.should_quit);
+}
+
+#[test]
+fn test_delete_with_motions() {
+    let mut editor = get_test_editor_with_lines(&["one two three four", "a", "b", "c", "d"]);
+    process_keystrokes(&mut editor, vec!['d', 'w']);
+    assert_current_line_is(&editor, "two three four");
+    process_keystrokes(&mut editor, vec!['w', 'd', 'b']);
+    assert_current_line_is(&editor, "three four");
+    process_keystrokes(&mut editor, vec!['w', 'd', '0']);
+    assert_current_line_is(&editor, "four");
+    process_keystrokes(&mut editor, vec!['l', 'd', '$']);
+    assert_current_line_is(&editor, "f");
+    assert_eq!(editor.registers.get(None).unwrap().text, "our");
+    // the last word of the line is deleted up to its end
+    process_keystrokes(&mut editor, vec!['u', 'u', 'u', 'u', '0', 'd', '3', 'w']);
+    assert_current_line_is(&editor, "four");
+    process_keystrokes(&mut editor, vec!['u', '2', 'd', 'w']);
+    assert_current_line_is(&editor, "three four");
+    process_keystrokes(&mut editor, vec!['d', '5', 'w']);
+    assert_current_line_is(&editor, "");
+
+    // dd accepts a count as well
+    process_keystrokes(&mut editor, vec!['j', '3', 'd', 'd']);
+    assert_eq!(editor.document.line_count(), 2);
+    assert_nth_row_is(&editor, 1, "d");
+    process_keystrokes(&mut editor, vec!['j', '9', 'd', 'd']);
+    assert_eq!(editor.document.line_count(), 1);
+}
+
+#[test]
+fn test_move_lines() {
+    let mut editor = get_test_editor_with_lines(&["a", "b", "c", "d"]);
+    // the first line can't be moved up
+    editor.process_keystroke(Key::Alt('k'));
+    assert_nth_row_is(&editor, 0, "a");
+    editor.process_keystroke(Key::Alt('j'));
+    assert_nth_row_is(&editor, 0, "b");
+    assert_nth_row_is(&editor, 1, "a");
+    assert_current_line_is(&editor, "a");
+    process_keystrokes(&mut editor, vec!['5']);
+    editor.process_keystroke(Key::Alt('j'));
+    assert_nth_row_is(&editor, 3, "a");
+    assert_current_line_is(&editor, "a");
+    // the last line can't be moved down
+    editor.process_keystroke(Key::Alt('j'));
+    assert_nth_row_is(&editor, 3, "a");
+    // a move is undone in a single step
+    process_keystrokes(&mut editor, vec!['u']);
+    assert_nth_row_is(&editor, 1, "a");
+    assert_nth_row_is(&editor, 3, "d");
+
+    // the selected lines are moved together
+    process_keystrokes(&mut editor, vec!['g', 'g', 'v', 'j']);
+    editor.process_keystroke(Key::Alt('j'));
+    assert_nth_row_is(&editor, 0, "c");
+    assert_nth_row_is(&editor, 1, "b");
+    assert_nth_row_is(&editor, 2, "a");
+    assert_eq!(editor.mode, Mode::Visual);
+    assert_eq!(
+        editor
+            .selection_bounds()
+            .map(|(start, end)| (start.y, end.y)),
+        Some((1, 2))
+    );
+    editor.process_keystroke(Key::Alt('k'));
+    assert_nth_row_is(&editor, 0, "b");
+    assert_nth_row_is(&editor, 2, "c");
+}
+
+#[test]
+fn test_change_operators() {
+    let mut editor = get_test_editor_with_lines(&["    one two three", "a", "b"]);
+    process_keystrokes(&mut editor, vec!['w', 'c', 'w', 'x']);
+    editor.process_keystroke(Key::Esc);
+    assert_current_line_is(&editor, "    x two three");
+    process_keystrokes(&mut editor, vec!['w', 'w', 'C', 'y']);
+    editor.process_keystroke(Key::Esc);
+    assert_current_line_is(&editor, "    x two y");
+    // the change is undone in a single step
+    process_keystrokes(&mut editor, vec!['u']);
+    assert_current_line_is(&editor, "    x two three");
+
+    // cc keeps the indentation of the line
+    process_keystrokes(&mut editor, vec!['c', 'c']);
+    assert_eq!(editor.mode, Mode::Insert);
+    assert_current_line_is(&editor, "    ");
+    assert_position_is(&editor, 4, 0);
+    process_keystrokes(&mut editor, vec!['z']);
+    editor.process_keystroke(Key::Esc);
+    assert_current_line_is(&editor, "    z");
+    assert_eq!(editor.document.line_count(), 3);
+
+    editor.config.autoindent = false;
+    process_keystrokes(&mut editor, vec!['2', 'c', 'c']);
+    assert_current_line_is(&editor, "");
+    assert_eq!(editor.document.line_count(), 2);
+    assert_eq!(editor.registers.get(None).unwrap().text, "    z\na");
+}
+
+#[test]
+fn test_replace_graphemes() {
+    let mut editor = get_test_editor_with_lines(&["abcdef"]);
+    process_keystrokes(&mut editor, vec!['r', 'x']);
+    assert_current_line_is(&editor, "xbcdef");
+    assert_eq!(editor.mode, Mode::Normal);
+    process_keystrokes(&mut editor, vec!['l', '3', 'r', 'y']);
+    assert_current_line_is(&editor, "xyyyef");
+    assert_position_is(&editor, 3, 0);
+    // the replacement stops at the end of the line
+    process_keystrokes(&mut editor, vec!['9', 'r', 'z']);
+    assert_current_line_is(&editor, "xyyzzz");
+    // all the replacements are undone together
+    process_keystrokes(&mut editor, vec!['u']);
+    assert_current_line_is(&editor, "xyyyef");
+    // Esc cancels the replacement
+    process_keystrokes(&mut editor, vec!['r']);
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['l']);
+    assert_current_line_is(&editor, "xyyyef");
+
+    process_keystrokes(&mut editor, vec!['0', 'l', '2', 'r', '\n']);
+    assert_eq!(editor.document.line_count(), 2);
+    assert_nth_row_is(&editor, 0, "x");
+    assert_nth_row_is(&editor, 1, "yef");
+    assert_position_is(&editor, 0, 1);
+}
+
+#[test]
+fn test_comment_continuation() {
+    let mut editor = get_test_editor();
+    editor.document = Document::new(
+        vec![Row::from("    // first"), Row::from("let a = 1;")],
+        PathBuf::from("test.rs"),
+    );
+    // off by default
+    process_keystrokes(&mut editor, vec!['o', 'x']);
+    editor.process_keystroke(Key::Esc);
+    assert_nth_row_is(&editor, 1, "    x");
+    process_keystrokes(&mut editor, vec!['u']);
+
+    editor.config.comment_continuation = true;
+    process_keystrokes(&mut editor, vec!['g', 'g', 'o', 'x']);
+    editor.process_keystroke(Key::Esc);
+    assert_nth_row_is(&editor, 1, "    // x");
+    process_keystrokes(&mut editor, vec!['O', 'y']);
+    assert_nth_row_is(&editor, 1, "    // y");
+    editor.process_keystroke(Key::Char('\n'));
+    assert_current_line_is(&editor, "    // ");
+    editor.process_keystroke(Key::Esc);
+    // lines which aren't comments aren't continued
+    process_keystrokes(&mut editor, vec!['G', 'o', 'z']);
+    editor.process_keystroke(Key::Esc);
+    assert_current_line_is(&editor, "z");
+
+    editor.document = Document::new(vec![Row::from("# comment")], PathBuf::from("test.sh"));
+    process_keystrokes(&mut editor, vec!['g', 'g', 'A']);
+    editor.process_keystroke(Key::Char('\n'));
+    assert_current_line_is(&editor, "# ");
+    // splitting the line before the comment doesn't continue it
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['g', 'g', 'i']);
+    editor.process_keystroke(Key::Char('\n'));
+    assert_current_line_is(&editor, "# comment");
+}
+
+#[test]
+fn test_delete_rest_of_line() {
+    let mut editor = get_test_editor();
+    process_keystrokes(&mut editor, vec!['w', 'D']);
+    assert_current_line_is(&editor, "Hello ");
+    // the cursor stays on the last character of the line
+    assert_position_is(&editor, 5, 0);
+    process_keystrokes(&mut editor, vec!['u']);
+    assert_current_line_is(&editor, "Hello world");
+    process_keystrokes(&mut editor, vec!['0', 'D']);
+    assert_current_line_is(&editor, "");
+    assert_eq!(editor.registers.get(None).unwrap().text, "Hello world");
+}
+
+#[test]
+fn test_operators_with_matching_symbol() {
+    let mut editor = get_test_editor_with_lines(&["fn a() {", "    b();", "}", "c"]);
+    process_keystrokes(&mut editor, vec!['f', '{', 'd', '%']);
+    assert_eq!(editor.document.line_count(), 2);
+    assert_nth_row_is(&editor, 0, "fn a() ");
+    assert_nth_row_is(&editor, 1, "c");
+    assert_eq!(editor.registers.get(None).unwrap().text, "{\n    b();\n}");
+
+    // from the closing bracket, backward
+    process_keystrokes(&mut editor, vec!['u', 'g', 'g', 'f', ')', 'y', '%']);
+    assert_eq!(editor.registers.get(None).unwrap().text, "()");
+    assert_position_is(&editor, 4, 0);
+    process_keystrokes(&mut editor, vec!['f', ')', 'c', '%', 'x']);
+    editor.process_keystroke(Key::Esc);
+    assert_nth_row_is(&editor, 0, "fn ax {");
+
+    // nothing happens when the cursor isn't on a bracket
+    process_keystrokes(&mut editor, vec!['0', 'd', '%']);
+    assert_nth_row_is(&editor, 0, "fn ax {");
+    assert_eq!(editor.document.line_count(), 4);
+}
+
+#[test]
+fn test_colorcolumn() {
+    let mut editor = get_test_editor();
+    let row = Row::from("0123456789");
+    assert_eq!(editor.colorcolumn_ranges_in_row(&row), None);
+    assert_eq!(editor.colorcolumn_padding(&row, 0, 120), "");
+
+    editor.config.colorcolumn = vec![ColorColumn::Absolute(6)];
+    assert_eq!(
+        editor.colorcolumn_ranges_in_row(&row),
+        Some((slice::from_ref(&(5..6)).to_vec(), COLORCOLUMN_BG_COLOR))
+    );
+    editor.config.colorcolumn = vec![ColorColumn::Absolute(13)];
+    assert_eq!(editor.colorcolumn_ranges_in_row(&row), None);
+    assert_eq!(
+        editor.colorcolumn_padding(&row, 0, 120),
+        format!(
+            "  {} {}",
+            color::Bg(COLORCOLUMN_BG_COLOR),
+            color::Bg(color::Reset)
+        )
+    );
+    // the guide isn't drawn when it's scrolled out of view
+    assert_eq!(editor.colorcolumn_padding(&row, 0, 10), "");
+    assert_eq!(editor.colorcolumn_padding(&row, 14, 120), "");
+
+    // in overflow mode, only the part of the lines reaching the column is highlighted
+    editor.config.colorcolumn_overflow = true;
+    assert_eq!(editor.colorcolumn_padding(&row, 0, 120), "");
+    editor.config.colorcolumn = vec![ColorColumn::Absolute(9)];
+    assert_eq!(
+        editor.colorcolumn_ranges_in_row(&row),
+        Some((
+            slice::from_ref(&(8..10)).to_vec(),
+            COLORCOLUMN_OVERFLOW_BG_COLOR
+        ))
+    );
+}
+
+#[test]
+fn test_multiple_and_relative_colorcolumns() {
+    let mut editor = get_test_editor();
+    let row = Row::from("0123456789");
+    let guide = format!(
+        "{} {}",
+        color::Bg(COLORCOLUMN_BG_COLOR),
+        color::Bg(color::Reset)
+    );
+    process_command(&mut editor, ":set cc=3,+1,14,-1");
+    assert_eq!(editor.message, "");
+    assert_eq!(
+        editor.colorcolumn_ranges_in_row(&row),
+        Some((slice::from_ref(&(2..3)).to_vec(), COLORCOLUMN_BG_COLOR))
+    );
+    assert_eq!(
+        editor.colorcolumn_padding(&row, 0, 120),
+        format!("   {guide}")
+    );
+
+    // the relative columns follow the textwidth
+    process_command(&mut editor, ":set tw=5");
+    assert_eq!(
+        editor.colorcolumn_ranges_in_row(&row),
+        Some((vec![2..3, 3..4, 5..6], COLORCOLUMN_BG_COLOR))
+    );
+    process_command(&mut editor, ":set tw=12");
+    assert_eq!(
+        editor.colorcolumn_padding(&row, 0, 120),
+        format!("{guide} {guide}{guide}")
+    );
+    // the guides scrolled out of view aren't drawn
+    assert_eq!(
+        editor.colorcolumn_padding(&row, 0, 13),
+        format!("{guide} {guide}")
+    );
+
+    process_command(&mut editor, ":set cc");
+    assert_eq!(editor.message, "colorcolumn=3,+1,14,-1");
+    process_command(&mut editor, ":set cc=80,x");
+    assert_eq!(editor.message, utils::red("Invalid value for 'cc'"));
+    process_command(&mut editor, ":set cc=");
+    assert_eq!(editor.colorcolumn_ranges_in_row(&row), None);
+}
+
+#[test]
+fn test_operators_with_quotes_text_objects() {
+    let mut editor = get_test_editor_with_lines(&[r#"say("hello", 'x')"#]);
+    process_keystrokes(&mut editor, vec!['c', 'i', '"', 'b', 'y', 'e']);
+    editor.process_keystroke(Key::Esc);
+    assert_current_line_is(&editor, r#"say("bye", 'x')"#);
+    process_keystrokes(&mut editor, vec!['0', 'd', 'a', '\'']);
+    assert_current_line_is(&editor, r#"say("bye", )"#);
+    process_keystrokes(&mut editor, vec!['0', 'y', 'i', '"']);
+    assert_eq!(editor.registers.get(None).unwrap().text, "bye");
+
+    // nothing happens when no pair is found
+    process_keystrokes(&mut editor, vec!['d', 'i', '`']);
+    assert_current_line_is(&editor, r#"say("bye", )"#);
+    assert_eq!(editor.message, utils::red("No `` pair found on the line"));
+    process_keystrokes(&mut editor, vec!['d', 'i', '[']);
+    assert_eq!(
+        editor.message,
+        utils::red("No [] pair found around the cursor")
+    );
+}
+
+#[test]
+fn test_normalize_indentation() {
+    let mut editor = get_test_editor_with_lines(&["\t  a", "    b", "\tc", "d"]);
+    process_command(&mut editor, ":normalize-indent");
+    assert_nth_row_is(&editor, 0, "      a");
+    assert_nth_row_is(&editor, 1, "    b");
+    assert_nth_row_is(&editor, 2, "    c");
+    assert_eq!(editor.message, "2 lines changed");
+    // the whole change is undone in a single step
+    process_keystrokes(&mut editor, vec!['u']);
+    assert_nth_row_is(&editor, 0, "\t  a");
+    assert_nth_row_is(&editor, 2, "\tc");
+
+    editor.config.expand_tab = false;
+    process_command(&mut editor, ":2,3normalize-indent");
+    assert_nth_row_is(&editor, 0, "\t  a");
+    assert_nth_row_is(&editor, 1, "\tb");
+    assert_eq!(editor.message, "1 line changed");
+}
+
+#[test]
+fn test_jump_to_tag_and_back() {
+    let (_f1, path1) = temp_file_with_content("fn main() {\n    helper();\n}\n");
+    let (_f2, path2) = temp_file_with_content("use std::fs;\n\nfn helper() {}\n");
+    let mut editor = new_test_editor(Some(path1.clone()), Box::new(MockConsole::default()));
+    let tag = |address| Tag {
+        name: String::from("helper"),
+        filename: PathBuf::from(&path2),
+        address,
+    };
+    process_keystrokes(&mut editor, vec!['j', 'w']);
+    editor.goto_tag(&tag(TagAddress::Pattern {
+        text: String::from("fn helper() {}"),
+        anchored_start: true,
+        anchored_end: true,
+    }));
+    assert_eq!(editor.document.filename, Some(PathBuf::from(&path2)));
+    assert_position_is(&editor, 0, 2);
+    // the tag file name may point to the file through another path
+    let path = Path::new(&path2);
+    let other_path = path
+        .parent()
+        .unwrap()
+        .join(".")
+        .join(path.file_name().unwrap());
+    editor.goto_tag(&Tag {
+        filename: other_path,
+        ..tag(TagAddress::Line(1))
+    });
+    assert_position_is(&editor, 0, 0);
+    editor.process_keystroke(Key::Ctrl('t'));
+    assert_position_is(&editor, 0, 2);
+
+    // several matching tags are listed, to be picked by their number
+    editor.display_in_alternate_screen(String::from("tags"));
+    editor.tag_choices = vec![tag(TagAddress::Line(3)), tag(TagAddress::Line(1))];
+    process_keystrokes(&mut editor, vec!['2']);
+    assert!(!editor.alternate_screen);
+    assert_position_is(&editor, 0, 0);
+
+    editor.process_keystroke(Key::Ctrl('t'));
+    assert_eq!(editor.document.filename, Some(PathBuf::from(&path2)));
+    assert_position_is(&editor, 0, 2);
+    editor.process_keystroke(Key::Ctrl('t'));
+    assert_eq!(editor.document.filename, Some(PathBuf::from(&path1)));
+    assert_position_is(&editor, 4, 1);
+    editor.process_keystroke(Key::Ctrl('t'));
+    assert_eq!(editor.message, utils::red("The tag stack is empty"));
+
+    process_command(&mut editor, ":tag");
+    assert_eq!(editor.message, utils::red("No tag name"));
+}
+
+#[test]
+fn test_detect_indent() {
+    let (_f, path) =
+        temp_file_with_content("fn a() {\n  if b {\n    c();\n  }\n}\n\nfn d() {\n  e();\n}\n");
+    let mut editor = new_test_editor(Some(path), Box::new(MockConsole::default()));
+    process_keystrokes(&mut editor, vec!['G', 'o', '\t', 'x']);
+    assert_current_line_is(&editor, "    x");
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['d', 'd']);
+
+    editor.config.detect_indent = true;
+    process_keystrokes(&mut editor, vec!['G', 'o', '\t', 'x']);
+    assert_current_line_is(&editor, "  x");
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['d', 'd']);
+    // a whole detected indentation level is deleted by Backspace
+    process_keystrokes(&mut editor, vec!['G', 'o', '\t', '\t']);
+    editor.process_keystroke(Key::Backspace);
+    assert_current_line_is(&editor, "  ");
+    editor.process_keystroke(Key::Esc);
+
+    let (_f, path) = temp_file_with_content("fn a() {\n\tb();\n\tif c {\n\t\td();\n\t}\n}\n");
+    editor.open_file(&path);
+    process_keystrokes(&mut editor, vec!['G', 'o', '\t']);
+    assert_current_line_is(&editor, "\t");
+}
+
+#[test]
+fn test_cursor_displayed_past_the_end_of_line_in_insert_mode() {
+    let cursor_position = Rc::new(Cell::new(Position::default()));
+    let console = Box::new(MockConsole {
+        size: Size {
+            height: 10,
+            width: 20,
+        },
+        cursor_position: Rc::clone(&cursor_position),
+    });
+    let mut editor = new_test_editor(None, console);
+    editor.document = Document::new(
+        vec![
+            Row::from("Hello world"),
+            Row::from("A line longer than the screen"),
+        ],
+        PathBuf::from("test"),
+    );
+    process_keystrokes(&mut editor, vec!['A']);
+    editor.refresh_screen().unwrap();
+    assert_eq!(cursor_position.get(), Position { x: 11, y: 0 });
+
+    // the view is scrolled to display the cursor after the last character
+    editor.process_keystroke(Key::Esc);
+    process_keystrokes(&mut editor, vec!['j', 'A']);
+    editor.refresh_screen().unwrap();
+    assert_eq!(editor.offset.columns, 10);
+    assert_eq!(cursor_position.get(), Position { x: 19, y: 1 });
+    assert_eq!(
+        editor.offset.columns + cursor_position.get().x,
+        editor.current_row().len()
+    );
+
+    // the line numbers make the text area narrower
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":ln");
+    process_keystrokes(&mut editor, vec!['0', 'A']);
+    editor.refresh_screen().unwrap();
+    assert_eq!(cursor_position.get(), Position { x: 14, y: 1 });
+    assert_eq!(
+        editor.offset.columns + cursor_position.get().x,
+        editor.current_row().len()
+    );
+}
+
+#[test]
+fn test_cursor_over_wide_characters() {
+    let cursor_position = Rc::new(Cell::new(Position::default()));
+    let console = Box::new(MockConsole {
+        size: Size {
+            height: 10,
+            width: 20,
+        },
+        cursor_position: Rc::clone(&cursor_position),
+    });
+    let mut editor = new_test_editor(None, console);
+    editor.document = Document::new(
+        vec![Row::from(
+            "\u{3042}\u{3044}\u{3046}\u{3048}\u{304a}\u{304b}\u{304d}\u{304f}\u{3051}\u{3053}\u{3055}\u{3057}",
+        )],
+        PathBuf::from("test"),
+    );
+    process_keystrokes(&mut editor, vec!['l', 'l']);
+    editor.refresh_screen().unwrap();
+    assert_position_is(&editor, 2, 0);
+    assert_eq!(cursor_position.get(), Position { x: 4, y: 0 });
+
+    // the view is scrolled until the last character is fully displayed
+    editor.process_keystroke(Key::Char('$'));
+    editor.refresh_screen().unwrap();
+    assert_eq!(editor.current_x_position(), 11);
+    assert_eq!(editor.offset.columns, 2);
+    assert_eq!(cursor_position.get(), Position { x: 18, y: 0 });
+
+    // and back to the first one
+    for _ in 0..11 {
+        editor.process_keystroke(Key::Char('h'));
+    }
+    editor.refresh_screen().unwrap();
+    assert_position_is(&editor, 0, 0);
+    assert_eq!(editor.offset.columns, 0);
+    assert_eq!(cursor_position.get(), Position { x: 0, y: 0 });
+}
+
+#[test]
+fn test_swap_file_recovery() {
+    let (_f, path) = temp_file_with_content("saved\n");
+    let swap_filename = Document::swap_filename(Path::new(&path));
+    fs::write(&swap_filename, "unsaved\n").unwrap();
+    let mut editor = new_test_editor(Some(path.clone()), Box::new(MockConsole::default()));
+    assert_current_line_is(&editor, "saved");
+    assert!(editor
+        .message
+        .contains(":recover to load it, :discard to delete it"));
+    assert!(!editor.is_dirty());
+
+    process_command(&mut editor, ":recover");
+    assert_current_line_is(&editor, "unsaved");
+    assert!(editor.is_dirty());
+    process_keystrokes(&mut editor, vec!['u']);
+    assert_current_line_is(&editor, "saved");
+
+    process_command(&mut editor, ":discard");
+    assert_eq!(editor.message, "Swap file deleted");
+    assert!(!swap_filename.exists());
+    let editor = new_test_editor(Some(path), Box::new(MockConsole::default()));
+    assert!(!editor.message.contains(":recover"));
+}
+
+#[test]
+fn test_pending_swap_file_kept_until_recovered_or_discarded() {
+    let (_f, path) = temp_file_with_content("saved\n");
+    let swap_filename = Document::swap_filename(Path::new(&path));
+    fs::write(&swap_filename, "unsaved\n").unwrap();
+    let mut editor = new_test_editor(Some(path), Box::new(MockConsole::default()));
+    editor.process_keystroke(Key::Char('i'));
+    for _ in 0..100 {
+        editor.process_keystroke(Key::Char('a'));
+    }
+    editor.process_keystroke(Key::Esc);
+    process_command(&mut editor, ":w");
+    assert_eq!(fs::read_to_string(&swap_filename).unwrap(), "unsaved\n");
+
+    // once recovered, the swap file is written to and deleted as usual
+    process_command(&mut editor, ":recover");
+    assert_current_line_is(&editor, "unsaved");
+    editor.save_to_swap_file();
+    assert_eq!(fs::read_to_string(&swap_filename).unwrap(), "unsaved\n");
+    process_keystrokes(&mut editor, vec!['x']);
+    editor.save_to_swap_file();
+    assert_eq!(fs::read_to_string(&swap_filename).unwrap(), "nsaved\n");
+    process_command(&mut editor, ":w");
+    assert!(!swap_filename.exists());
+}
+
+#[test]
+fn test_swap_file_not_rewritten_when_unchanged() {
+    let (_f, path) = temp_file_with_content("saved\n");
+    let swap_filename = Document::swap_filename(Path::new(&path));
+    let mut editor = new_test_editor(Some(path), Box::new(MockConsole::default()));
+    // typing and deleting characters leaves the document as it was on disk
+    editor.process_keystroke(Key::Char('i'));
+    for _ in 0..SWAP_SAVE_EVERY {
+        editor.process_keystroke(Key::Char('a'));
+        editor.process_keystroke(Key::Backspace);
+    }
+    editor.process_keystroke(Key::Esc);
+    assert_current_line_is(&editor, "saved");
+    assert!(!swap_filename.exists());
+
+    process_keystrokes(&mut editor, vec!['x']);
+    editor.save_to_swap_file();
+    assert_eq!(fs::read_to_string(&swap_filename).unwrap(), "aved\n");
+
+    // undoing and redoing the edit doesn't write the same content again
+    fs::remove_file(&swap_filename).unwrap();
+    process_keystrokes(&mut editor, vec!['u']);
+    editor.process_keystroke(Key::Ctrl('r'));
+    editor.save_to_swap_file();
+    assert!(!swap_filename.exists());
+    process_keystrokes(&mut editor, vec!['x']);
+    editor.save_to_swap_file();
+    assert_eq!(fs::read_to_string(&swap_filename).unwrap(), "ved\n");
+    fs::remove_file(&swap_filename).unwrap();
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+use std::fmt;
+
+/// The line ending convention of a document
+#[derive(Debug, Default, Clone, Copy, PartialEq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    #[default]
+    Unix,
+    Dos,
+}
+
+impl fmt::Display for FileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FileFormat::Unix => write!(f, "unix"),
+            FileFormat::Dos => write!(f, "dos"),
+        }
+    }
+}
+
+impl FileFormat {
+    /// Return the file format associated with the provided name (eg: `:set ff=dos`)
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unix" => Some(Self::Unix),
+            "dos" => Some(Self::Dos),
+            _ => None,
+        }
+    }
+
+    /// Detect the file format of the provided file contents, based on its first line ending
+    #[must_use]
+    pub fn detect(contents: &str) -> Self {
+        match contents.find('\n') {
+            Some(index) if contents[..index].ends_with('\r') => Self::Dos,
+            _ => Self::Unix,
+        }
+    }
+
+    #[must_use]
+    pub fn line_ending(self) -> &'static str {
+        match self {
+            Self::Unix => "\n",
+            Self::Dos => "\r\n",
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./file_format_test.rs"]
+mod file_format_test;
@@ -0,0 +1,22 @@
+use crate::FileFormat;
+
+#[test]
+fn test_file_format_display() {
+    assert_eq!(FileFormat::Unix.to_string(), "unix");
+    assert_eq!(FileFormat::Dos.to_string(), "dos");
+}
+
+#[test]
+fn test_file_format_from_name() {
+    assert_eq!(FileFormat::from_name("unix"), Some(FileFormat::Unix));
+    assert_eq!(FileFormat::from_name("dos"), Some(FileFormat::Dos));
+    assert_eq!(FileFormat::from_name("mac"), None);
+}
+
+#[test]
+fn test_file_format_detect() {
+    assert_eq!(FileFormat::detect("Hello\nworld\n"), FileFormat::Unix);
+    assert_eq!(FileFormat::detect("Hello\r\nworld\r\n"), FileFormat::Dos);
+    assert_eq!(FileFormat::detect("Hello"), FileFormat::Unix);
+    assert_eq!(FileFormat::detect(""), FileFormat::Unix);
+}
@@ -1,6 +1,394 @@
 use crate::utils;
 use std::collections::HashMap;
 
+const NORMAL_COMMANDS: &[(&str, &str)] = &[
+    ("j", "move cursor down one row (<n>j moves it by n rows)"),
+    ("k", "move cursor up one row (<n>k moves it by n rows)"),
+    ("h", "move cursor left (<n>h moves it n times)"),
+    ("l", "move cursor right (<n>l moves it n times)"),
+    (
+        "}",
+        "move to the end of the current paragraph (<n>} moves n times)",
+    ),
+    (
+        "{",
+        "move to the start of the current paragraph (<n>{ moves n times)",
+    ),
+    (
+        "w",
+        "move to the end of the current word (<n>w moves n times)",
+    ),
+    (
+        "b",
+        "move to the start of the current word (<n>b moves n times)",
+    ),
+    ("i", "switch to insert mode"),
+    (
+        "R",
+        "switch to replace mode (typed characters overwrite the existing ones)",
+    ),
+    (
+        "v",
+        "switch to visual mode (d/x delete the selection, y yanks it)",
+    ),
+    ("gg", "go to beginining of document"),
+    (
+        "ge",
+        "move to the end of the previous word (<n>ge moves n times)",
+    ),
+    (
+        "gE",
+        "move to the end of the previous WORD (<n>gE moves n times)",
+    ),
+    (
+        "gi",
+        "go back to where insert mode was last left, in insert mode",
+    ),
+    ("G", "go to end of document"),
+    (
+        "0/Home",
+        "go to first character in line (alternating with the first non-whitespace one with smart_home)",
+    ),
+    ("^", "go to first non-whitespace character in line"),
+    ("$", "go to end of line"),
+    (
+        "H",
+        "go to first line in screen (<n>H goes to the nth line)",
+    ),
+    ("M", "go to line in the middle of the screen"),
+    (
+        "L",
+        "go to last line in screen (<n>L goes to the nth line from the bottom)",
+    ),
+    ("n%", "move to n% in the file"),
+    (
+        "%",
+        "go to the matching bracket or quote (or tag in HTML/XML files)",
+    ),
+    ("m<a-z>", "set the mark with the given name at the cursor position"),
+    ("`<a-z>", "go to the mark with the given name"),
+    ("/", "open search prompt (the pattern is a regex)"),
+    ("n", "go to next search match"),
+    ("N", "go to previous search match"),
+    ("dd", "delete current line (<n>dd deletes n lines)"),
+    (
+        "Alt-j/Alt-k",
+        "move the current line (or the selected ones) down/up (<n>Alt-j moves it n lines)",
+    ),
+    (
+        "r<c>",
+        "replace the character under the cursor (<n>r<c> replaces n characters)",
+    ),
+    (
+        "cw/cc/C",
+        "change the rest of the word, the whole line (keeping its indentation) or the rest of the line",
+    ),
+    ("D", "delete the rest of the line"),
+    (
+        "d%/c%/y%",
+        "delete, change or yank up to the matching bracket or quote (both included)",
+    ),
+    (
+        "dw/db/d$/d0",
+        "delete to the next/previous word, or the end/start of the line (d<n>w deletes n words)",
+    ),
+    (
+        "dis/das",
+        "delete the sentence under the cursor (das: with whitespace)",
+    ),
+    ("cis/cas", "change the sentence under the cursor"),
+    ("yis/yas", "yank the sentence under the cursor"),
+    (
+        "di(/da(",
+        "delete inside/around the () pair under or after the cursor (also [, {, <, c and y)",
+    ),
+    (
+        "di\"/da\"",
+        "delete inside/around the \"\" pair of the line under or after the cursor (also ', `, c and y)",
+    ),
+    ("yy", "yank the current line (<n>yy yanks n lines)"),
+    (
+        "f<c>/F<c>",
+        "go to the next/previous c in the line (<n>f<c> goes to the nth one)",
+    ),
+    (
+        "t<c>/T<c>",
+        "go right before/after the next/previous c in the line",
+    ),
+    (
+        ";/,",
+        "repeat the last f, F, t or T motion, in the same/opposite direction",
+    ),
+    (
+        "df<c>/dt<c>",
+        "delete up to the next c, included/excluded (also dF, dT, c and y)",
+    ),
+    (
+        "\"<r>",
+        "use register r (a-z, 0-9, + for the clipboard) for the next yank, delete or paste",
+    ),
+    ("p", "paste after the cursor (<n>p pastes n times)"),
+    ("P", "paste before the cursor (<n>P pastes n times)"),
+    ("x", "delete current character"),
+    ("Ctrl-O", "go back to the position the cursor jumped from"),
+    ("Ctrl-I", "go forward in the jump list"),
+    (
+        "Ctrl-]",
+        "jump to the definition of the word under the cursor, found in the tags file",
+    ),
+    ("Ctrl-T", "go back to where the cursor jumped to a tag from"),
+    ("u", "undo the last change"),
+    ("Ctrl-R", "redo the last undone change"),
+    (
+        "g-/g+",
+        "go to the previous/next undo state in time, across the undo branches",
+    ),
+    (
+        "Ctrl-G",
+        "display the file path, line count and cursor position percentage",
+    ),
+    ("o", "insert newline after current line & enter insert mode"),
+    (
+        "O",
+        "insert newline before current line & enter insert mode",
+    ),
+    ("A", "go to end of line & enter insert mode"),
+    (
+        "[n]J",
+        "join the current line with the next one (n lines with a count), separated by a space",
+    ),
+    (
+        ".",
+        "repeat the last change (<n>. repeats it with a count of n)",
+    ),
+    ("K", "look up the word under the cursor with `man`"),
+    ("gs", "insert the current date at the cursor"),
+    (
+        "zz",
+        "scroll to display the current line in the middle of the screen",
+    ),
+    (
+        "zt",
+        "scroll to display the current line at the top of the screen",
+    ),
+    (
+        "zb",
+        "scroll to display the current line at the bottom of the screen",
+    ),
+    (
+        "Ctrl-D/Ctrl-U",
+        "scroll down/up by half a screen (or n lines with a count)",
+    ),
+    ("Ctrl-F/Ctrl-B", "scroll down/up by a full screen"),
+    ("(", "move to the start of the previous sentence"),
+    (")", "move to the start of the next sentence"),
+    (
+        "Ctrl-W j/k",
+        "focus the bottom/top pane of the split screen",
+    ),
+    (
+        "Ctrl-W w",
+        "focus the other pane of the split screen, or switch to the next buffer",
+    ),
+    ("Ctrl-W s", "split the screen in two panes"),
+    ("Ctrl-W q", "close the focused pane, or the current buffer"),
+    (":", "open command prompt"),
+];
+
+const PROMPT_COMMANDS: &[(&str, &str)] = &[
+    (
+        "Tab/Shift-Tab",
+        "complete the command name, or the file name after open/o/new/w/wq/source",
+    ),
+    (
+        "Up/Down",
+        "browse the history of the commands (or searches) starting like the typed one",
+    ),
+    ("bn/bp", "switch to the next/previous buffer"),
+    (
+        "date [<format>]",
+        "insert the current date (iso, datetime, time or strftime format)",
+    ),
+    (
+        "discard",
+        "delete the swap file of the current file, left by a crashed session",
+    ),
+    (
+        "earlier/later [<n>|<n>s/m/h/d]",
+        "go back/forward in the undo history by n changes or a time span",
+    ),
+    ("help", "display this help screen"),
+    ("hex", "toggle a read-only hex dump of the file"),
+    ("ln", "toggle line numbers"),
+    ("jumps", "display the jump list"),
+    (
+        "ls",
+        "list the buffers (% for the current one, + if modified)",
+    ),
+    ("marks", "list the marks"),
+    (
+        "tag <name>",
+        "jump to the definition of the tag, found in the tags file of the project",
+    ),
+    (
+        "mksession <name>",
+        "save the buffers and the screen layout to ~/.bo/sessions/<name>",
+    ),
+    ("new <filename>", "open a new file"),
+    ("noh", "stop highlighting the search matches"),
+    ("open/o <filename>", "open a file in a new buffer"),
+    (
+        "q",
+        "close the current pane or buffer, and quit bo if it's the last one",
+    ),
+    ("qa", "quit bo, unless a buffer has unsaved changes"),
+    (
+        "recover",
+        "load the content of the swap file of the current file",
+    ),
+    ("registers/reg", "display the content of the registers"),
+    (
+        "s/old/new/g",
+        "replace old by new in the current line (every match with g)",
+    ),
+    (
+        "<range>s/old/new/g",
+        "replace old by new in the range of lines (eg: %s/old/new/)",
+    ),
+    (
+        "<range>d",
+        "delete the lines of the range (eg: 2,5d, .,$d or %d)",
+    ),
+    (
+        "<range>sort",
+        "sort the lines of the range (all by default)",
+    ),
+    (
+        "<range>normalize-indent",
+        "indent the lines of the range (all by default) with spaces only, or tabs with :set noexpandtab",
+    ),
+    (
+        "<range>!<cmd>",
+        "replace the lines of the range by their output through cmd",
+    ),
+    (
+        "<range>",
+        "% for the whole file, <n>,<m> for lines n to m (. current, $ last)",
+    ),
+    (
+        "set <opt>!/<opt>?",
+        "toggle a boolean option, or display its value",
+    ),
+    (
+        "set ai/noai",
+        "indent the new lines like the previous one (on by default)",
+    ),
+    (
+        "set binary",
+        "edit the file verbatim, without altering its whitespace",
+    ),
+    (
+        "set cc=<n>,+<n>",
+        "highlight the columns (relative ones follow the textwidth) as line length guides",
+    ),
+    (
+        "set detectindent",
+        "indent like the existing lines of the opened files",
+    ),
+    (
+        "set et/noet",
+        "insert spaces or a literal tab when pressing Tab",
+    ),
+    ("set ff=unix/dos", "convert the line endings of the file"),
+    (
+        "set followsymlinks",
+        "save a file opened through a symlink to its target (on by default)",
+    ),
+    (
+        "set findacrosslines",
+        "let ; and , continue the search on the next/previous lines",
+    ),
+    ("set hls/nohls", "keep highlighting the search matches"),
+    (
+        "set ic/noic",
+        "ignore the case when searching (or add \\c to the pattern)",
+    ),
+    (
+        "set list/nolist",
+        "display the tabs as → and the trailing spaces as ·",
+    ),
+    (
+        "set nu/nonu",
+        "display the line numbers",
+    ),
+    (
+        "set scroll=<n>",
+        "scroll n lines with Ctrl-D/Ctrl-U (0 for half of the view)",
+    ),
+    (
+        "set so=<n>",
+        "keep n lines above/below the cursor when scrolling",
+    ),
+    (
+        "set stats/nostats",
+        "display the line/word stats",
+    ),
+    (
+        "set statuslinepath=<kind>",
+        "display the file path in the status bar as relative (default), absolute or basename",
+    ),
+    (
+        "set tabwidth <n>",
+        "insert n spaces for Tab, and display tabs n columns wide",
+    ),
+    (
+        "set tw=<n>",
+        "set the textwidth, from which +n/-n colorcolumns are counted",
+    ),
+    (
+        "set trim/notrim",
+        "remove trailing whitespace on save (on by default)",
+    ),
+    (
+        "set wmnu/nowmnu",
+        "display the Tab completion candidates (on by default)",
+    ),
+    (
+        "source [<file>]",
+        "re-read the config file (~/.config/bo/config.toml by default)",
+    ),
+    (
+        "source-session <name>",
+        "restore a session (also with `bo -S <name>`)",
+    ),
+    ("split/sp", "split the screen in two panes"),
+    ("stats", "toggle line/word stats"),
+    (
+        "undo [<n>]",
+        "undo the last change, or go to the undo state number n (0 being the file as opened)",
+    ),
+    ("w <new_name>", "save"),
+    ("wq", "save and quit"),
+];
+
+const INSERT_COMMANDS: &[(&str, &str)] = &[
+    ("Esc", "go back to normal mode"),
+    (
+        "Ctrl-K <c1><c2>",
+        "insert the character of a digraph (eg: a: for ä, e' for é)",
+    ),
+    (
+        "Ctrl-V u<hex>",
+        "insert a character by code point (U<hex> up to 8 digits, <decimal> for a byte)",
+    ),
+];
+
+fn section_entries(
+    entries: &[(&'static str, &'static str)],
+) -> HashMap<&'static str, &'static str> {
+    entries.iter().copied().collect()
+}
+
 pub struct Section {
     pub title: String,
     pub entries: HashMap<&'static str, &'static str>,
@@ -41,68 +429,15 @@ impl Help {
             sections: vec![
                 Section {
                     title: String::from("Normal commands"),
-                    entries: HashMap::from([
-                        ("j", "move cursor down one row (<n>j moves it by n rows)"),
-                        ("k", "move cursor up one row (<n>k moves it by n rows)"),
-                        ("h", "move cursor left (<n>h moves it n times)"),
-                        ("l", "move cursor right (<n>l moves it n times)"),
-                        (
-                            "}",
-                            "move to the end of the current paragraph (<n>} moves n times)",
-                        ),
-                        (
-                            "{",
-                            "move to the start of the current paragraph (<n>{ moves n times)",
-                        ),
-                        (
-                            "w",
-                            "move to the end of the current word (<n>w moves n times)",
-                        ),
-                        (
-                            "b",
-                            "move to the start of the current word (<n>b moves n times)",
-                        ),
-                        ("i", "switch to insert mode"),
-                        ("g", "go to beginining of document"),
-                        ("G", "go to end of document"),
-                        ("0", "go to first character in line"),
-                        ("^", "go to first non-whitespace character in line"),
-                        ("$", "go to end of line"),
-                        ("H", "go to first line in screen"),
-                        ("M", "go to line in the middle of the screen"),
-                        ("L", "go to last line in screen"),
-                        ("n%", "move to n% in the file"),
-                        ("/", "open search prompt"),
-                        ("n", "go to next search match"),
-                        ("N", "go to previous search match"),
-                        ("d", "delete current line"),
-                        ("x", "delete current character"),
-                        ("o", "insert newline after current line & enter insert mode"),
-                        (
-                            "O",
-                            "insert newline before current line & enter insert mode",
-                        ),
-                        ("A", "go to end of line & enter insert mode"),
-                        ("J", "join the current line with the next one"),
-                        (":", "open command prompt"),
-                    ]),
+                    entries: section_entries(NORMAL_COMMANDS),
                 },
                 Section {
                     title: String::from("Prompt commands"),
-                    entries: HashMap::from([
-                        ("help", "display this help screen"),
-                        ("ln", "toggle line numbers"),
-                        ("new <filename>", "open a new file"),
-                        ("open/o <filename>", "open a file"),
-                        ("q", "quit bo"),
-                        ("stats", "toggle line/word stats"),
-                        ("w <new_name>", "save"),
-                        ("wq", "save and quit"),
-                    ]),
+                    entries: section_entries(PROMPT_COMMANDS),
                 },
                 Section {
                     title: String::from("Insert commands"),
-                    entries: HashMap::from([("Esc", "go back to normal mode")]),
+                    entries: section_entries(INSERT_COMMANDS),
                 },
             ],
         }
@@ -0,0 +1,125 @@
+use crate::Row;
+use std::ops::Range;
+use termion::color;
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Keyword,
+    String,
+    Comment,
+    Number,
+}
+
+impl Style {
+    #[must_use]
+    pub fn to_color(self) -> color::Rgb {
+        match self {
+            Self::Keyword => color::Rgb(230, 140, 80),
+            Self::String => color::Rgb(150, 200, 100),
+            Self::Comment => color::Rgb(130, 130, 130),
+            Self::Number => color::Rgb(200, 130, 220),
+        }
+    }
+}
+
+/// Graphemes of a row (indices `range`) to be displayed with the given style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub style: Style,
+}
+
+/// Syntax rules of a language, used to style the rows of the files having its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Highlighter {
+    keywords: &'static [&'static str],
+    line_comment: &'static str,
+}
+
+impl Highlighter {
+    /// Return the highlighter associated with the file extension, if the language is supported
+    #[must_use]
+    pub fn for_extension(extension: Option<&str>) -> Option<Self> {
+        match extension? {
+            "rs" => Some(Self {
+                keywords: RUST_KEYWORDS,
+                line_comment: "//",
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return the styled spans of the row, sorted by position. Each row is highlighted
+    /// on its own, so strings and comments spanning over several lines aren't detected.
+    #[must_use]
+    pub fn highlight(&self, row: &Row) -> Vec<Span> {
+        let graphemes: Vec<&str> = row.graphemes().collect();
+        let mut spans = vec![];
+        let mut index = 0;
+        while index < graphemes.len() {
+            let grapheme = graphemes[index];
+            let (end, style) = if self.is_line_comment_start(&graphemes[index..]) {
+                (graphemes.len(), Some(Style::Comment))
+            } else if grapheme == "\"" {
+                (Self::end_of_string(&graphemes, index), Some(Style::String))
+            } else if is_word_grapheme(grapheme) {
+                let end = graphemes[index..]
+                    .iter()
+                    .position(|g| !is_word_grapheme(g))
+                    .map_or(graphemes.len(), |length| index + length);
+                let word = graphemes[index..end].concat();
+                if grapheme.starts_with(|c: char| c.is_ascii_digit()) {
+                    (end, Some(Style::Number))
+                } else if self.keywords.contains(&word.as_str()) {
+                    (end, Some(Style::Keyword))
+                } else {
+                    (end, None)
+                }
+            } else {
+                (index.saturating_add(1), None)
+            };
+            if let Some(style) = style {
+                spans.push(Span {
+                    range: index..end,
+                    style,
+                });
+            }
+            index = end;
+        }
+        spans
+    }
+
+    fn is_line_comment_start(&self, graphemes: &[&str]) -> bool {
+        let length = self.line_comment.chars().count();
+        graphemes.len() >= length && graphemes[..length].concat() == self.line_comment
+    }
+
+    /// Return the index following the quote closing the string opened at the provided
+    /// index, or the length of the row if the string isn't closed on it.
+    fn end_of_string(graphemes: &[&str], start: usize) -> usize {
+        let mut index = start.saturating_add(1);
+        while index < graphemes.len() {
+            match graphemes[index] {
+                "\\" => index = index.saturating_add(2),
+                "\"" => return index.saturating_add(1),
+                _ => index = index.saturating_add(1),
+            }
+        }
+        graphemes.len()
+    }
+}
+
+fn is_word_grapheme(grapheme: &str) -> bool {
+    grapheme.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+#[path = "./highlight_test.rs"]
+mod highlight_test;
@@ -0,0 +1,46 @@
+use crate::{Highlighter, Row, Span, Style};
+
+fn spans(row: &str) -> Vec<(usize, usize, Style)> {
+    Highlighter::for_extension(Some("rs"))
+        .unwrap()
+        .highlight(&Row::from(row))
+        .into_iter()
+        .map(|Span { range, style }| (range.start, range.end, style))
+        .collect()
+}
+
+#[test]
+fn test_highlighter_for_extension() {
+    assert!(Highlighter::for_extension(Some("rs")).is_some());
+    assert!(Highlighter::for_extension(Some("txt")).is_none());
+    assert!(Highlighter::for_extension(None).is_none());
+}
+
+#[test]
+fn test_highlight_rust_keywords_and_numbers() {
+    assert_eq!(
+        spans("pub fn main() { let x1 = 42; }"),
+        vec![
+            (0, 3, Style::Keyword),
+            (4, 6, Style::Keyword),
+            (16, 19, Style::Keyword),
+            (25, 27, Style::Number),
+        ]
+    );
+    // keywords are only highlighted as whole words
+    assert_eq!(spans("letter fnord"), vec![]);
+}
+
+#[test]
+fn test_highlight_rust_strings_and_comments() {
+    assert_eq!(
+        spans(r#"let s = "a \" // b"; // fn"#),
+        vec![
+            (0, 3, Style::Keyword),
+            (8, 19, Style::String),
+            (21, 26, Style::Comment),
+        ]
+    );
+    // unterminated string
+    assert_eq!(spans("\"abc"), vec![(0, 4, Style::String)]);
+}
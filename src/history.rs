@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Error;
+use std::path::Path;
+
+pub const HISTORY_FILE: &str = "~/.bo/history";
+const HISTORY_MAX_LENGTH: usize = 100;
+
+/// History of the commands and search patterns entered in the prompt,
+/// persisted across sessions.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct History {
+    pub commands: Vec<String>,
+    pub searches: Vec<String>,
+}
+
+impl History {
+    /// Load the history from the provided file, falling back to an empty history
+    /// if the file doesn't exist or can't be parsed.
+    #[must_use]
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// # Errors
+    /// Returns an error if the history file (or its parent directory) can't be written to.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    pub fn push_command(&mut self, command: &str) {
+        Self::push_deduplicated(&mut self.commands, command);
+    }
+
+    pub fn push_search(&mut self, pattern: &str) {
+        Self::push_deduplicated(&mut self.searches, pattern);
+    }
+
+    /// Return the index of the last entry located before the provided index (or the first
+    /// one located after it) that starts with the typed text, to browse the entries
+    /// with Up and Down.
+    #[must_use]
+    pub fn find_entry(
+        entries: &[String],
+        from: usize,
+        typed: &str,
+        backward: bool,
+    ) -> Option<usize> {
+        if backward {
+            entries
+                .get(..from)
+                .unwrap_or(entries)
+                .iter()
+                .rposition(|entry| entry.starts_with(typed))
+        } else {
+            let start = from.saturating_add(1);
+            entries
+                .get(start..)?
+                .iter()
+                .position(|entry| entry.starts_with(typed))
+                .map(|index| index.saturating_add(start))
+        }
+    }
+
+    /// Push the entry at the end of the history, removing any previous occurrence of it,
+    /// and dropping the oldest entries if the history gets too long.
+    fn push_deduplicated(entries: &mut Vec<String>, entry: &str) {
+        if entry.is_empty() {
+            return;
+        }
+        entries.retain(|e| e != entry);
+        entries.push(entry.to_string());
+        if entries.len() > HISTORY_MAX_LENGTH {
+            entries.drain(..entries.len() - HISTORY_MAX_LENGTH);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./history_test.rs"]
+mod history_test;
@@ -0,0 +1,60 @@
+use crate::History;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn test_history_push_deduplicates() {
+    let mut history = History::default();
+    history.push_command("w");
+    history.push_command("q");
+    history.push_command("w");
+    history.push_command("");
+    assert_eq!(history.commands, vec!["q", "w"]);
+    history.push_search("world");
+    assert_eq!(history.searches, vec!["world"]);
+}
+
+#[test]
+fn test_history_is_capped() {
+    let mut history = History::default();
+    for i in 0..150 {
+        history.push_command(&i.to_string());
+    }
+    assert_eq!(history.commands.len(), 100);
+    assert_eq!(history.commands.first().unwrap(), "50");
+    assert_eq!(history.commands.last().unwrap(), "149");
+}
+
+#[test]
+fn test_history_save_and_load() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("nested").join("history");
+    assert_eq!(History::load(&path), History::default());
+
+    let mut history = History::default();
+    history.push_command("stats");
+    history.push_search("hello");
+    history.save(&path).unwrap();
+    assert_eq!(History::load(&path), history);
+
+    fs::write(&path, "not json").unwrap();
+    assert_eq!(History::load(&path), History::default());
+}
+
+#[test]
+fn test_history_find_entry() {
+    let entries: Vec<String> = ["w", "set ic", "q", "set hls"]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    assert_eq!(History::find_entry(&entries, 4, "", true), Some(3));
+    assert_eq!(History::find_entry(&entries, 3, "", true), Some(2));
+    assert_eq!(History::find_entry(&entries, 0, "", true), None);
+    assert_eq!(History::find_entry(&entries, 4, "set", true), Some(3));
+    assert_eq!(History::find_entry(&entries, 3, "set", true), Some(1));
+    assert_eq!(History::find_entry(&entries, 1, "set", true), None);
+    assert_eq!(History::find_entry(&entries, 1, "set", false), Some(3));
+    assert_eq!(History::find_entry(&entries, 3, "set", false), None);
+    assert_eq!(History::find_entry(&entries, 4, "", false), None);
+    assert_eq!(History::find_entry(&[], 0, "", true), None);
+}
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+/// Number of lines of a file scanned to detect its indentation
+const DETECTION_MAX_LINES: usize = 1000;
+/// Minimal number of indented lines for the detection to be conclusive
+const DETECTION_MIN_SAMPLES: usize = 3;
+const MAX_INDENT_WIDTH: usize = 8;
+
+/// The unit of indentation of a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indentation {
+    /// The provided number of spaces
+    Spaces(usize),
+    Tabs,
+}
+
+impl Indentation {
+    /// Detect the indentation used by the provided lines: tabs when most indented lines
+    /// start with one, or else the most common increase of the number of leading spaces
+    /// from a line to the next one. Return `None` if there are too few indented lines.
+    #[must_use]
+    pub fn detect<'a>(lines: impl Iterator<Item = &'a str>) -> Option<Self> {
+        let mut num_tab_lines: usize = 0;
+        let mut num_space_lines: usize = 0;
+        let mut increments: HashMap<usize, usize> = HashMap::new();
+        let mut previous_spaces: usize = 0;
+        for line in lines
+            .take(DETECTION_MAX_LINES)
+            .filter(|line| !line.trim().is_empty())
+        {
+            if line.starts_with('\t') {
+                num_tab_lines = num_tab_lines.saturating_add(1);
+                previous_spaces = 0;
+                continue;
+            }
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            if spaces > 0 {
+                num_space_lines = num_space_lines.saturating_add(1);
+            }
+            // increments of a single space are mostly alignments (eg: ` * ` in comments)
+            let increment = spaces.saturating_sub(previous_spaces);
+            if (2..=MAX_INDENT_WIDTH).contains(&increment) {
+                *increments.entry(increment).or_default() += 1;
+            }
+            previous_spaces = spaces;
+        }
+        if num_tab_lines > num_space_lines && num_tab_lines >= DETECTION_MIN_SAMPLES {
+            return Some(Self::Tabs);
+        }
+        increments
+            .into_iter()
+            .filter(|(_, count)| *count >= DETECTION_MIN_SAMPLES)
+            // the smallest width wins a tie
+            .max_by_key(|(width, count)| (*count, usize::MAX - width))
+            .map(|(width, _)| Self::Spaces(width))
+    }
+}
+
+#[cfg(test)]
+#[path = "./indentation_test.rs"]
+mod indentation_test;
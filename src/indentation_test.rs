@@ -0,0 +1,30 @@
+use crate::Indentation;
+
+fn detect(contents: &str) -> Option<Indentation> {
+    Indentation::detect(contents.lines())
+}
+
+#[test]
+fn test_indentation_detect_spaces() {
+    let two_spaces = "fn a() {\n  if b {\n    c();\n  }\n}\n\nfn d() {\n  e();\n}\n";
+    assert_eq!(detect(two_spaces), Some(Indentation::Spaces(2)));
+    let four_spaces = "def a():\n    if b:\n        c()\n\n    d()\n\ndef e():\n    f()\n";
+    assert_eq!(detect(four_spaces), Some(Indentation::Spaces(4)));
+    // the alignments of a single space are ignored
+    let aligned =
+        "/*\n * a\n * b\n */\nint c() {\n    d();\n    if (e) {\n        f();\n    }\n}\nint g() {\n    h();\n}\n";
+    assert_eq!(detect(aligned), Some(Indentation::Spaces(4)));
+}
+
+#[test]
+fn test_indentation_detect_tabs() {
+    let tabs = "func a() {\n\tif b {\n\t\tc()\n\t}\n\td()\n}\n";
+    assert_eq!(detect(tabs), Some(Indentation::Tabs));
+}
+
+#[test]
+fn test_indentation_detect_inconclusive() {
+    assert_eq!(detect(""), None);
+    assert_eq!(detect("a\nb\n\nc\n"), None);
+    assert_eq!(detect("a {\n  b\n}\n"), None);
+}
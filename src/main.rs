@@ -1,29 +1,53 @@
 #![warn(clippy::all, clippy::pedantic)]
 
+mod clipboard;
 mod commands;
+mod completion;
 mod config;
 mod console;
+mod digraph;
 mod document;
 mod editor;
+mod file_format;
 mod help;
+mod highlight;
+mod history;
+mod indentation;
 mod mode;
 mod navigator;
+mod range;
+mod register;
 mod row;
+mod session;
+mod substitution;
+mod tags;
 mod terminal;
+mod undo;
 mod utils;
 
 use editor::Editor;
 use structopt::StructOpt;
 
-pub use config::Config;
+pub use completion::Completion;
+pub use config::{ColorColumn, Config, StatuslinePath};
 pub use console::{Console, Size};
-pub use document::Document;
+pub use document::{Document, RowShift};
 pub use editor::{Position, ViewportOffset};
+pub use file_format::FileFormat;
 pub use help::{Help, Section};
+pub use highlight::{Highlighter, Span, Style};
+pub use history::History;
+pub use indentation::Indentation;
 pub use mode::Mode;
 pub use navigator::{Boundary, Navigator};
+pub use range::LineRange;
+pub use register::{Register, Registers};
 pub use row::Row;
+pub use session::{Session, SessionBuffer, SessionPane};
+pub use substitution::Substitution;
+pub use tags::{Tag, TagAddress};
 pub use terminal::{AnsiPosition, Terminal};
+pub use undo::{EditOp, UndoDistance, UndoHistory, UndoStep};
 pub use utils::{bo_version, log};
 
 #[derive(Debug, StructOpt)]
@@ -33,9 +57,17 @@ struct Opt {
     #[structopt(long)]
     version: bool,
 
-    /// File name
+    /// Start in insert mode
+    #[structopt(short = "i", long = "insert")]
+    insert: bool,
+
+    /// Restore the session saved with :mksession <name>
+    #[structopt(short = "S", long = "session")]
+    session: Option<String>,
+
+    /// File name, optionally preceded by +N to open the file at line N (+ for the last line)
     #[structopt(name = "FILE")]
-    file_name: Option<String>,
+    args: Vec<String>,
 }
 
 fn main() {
@@ -43,7 +75,25 @@ fn main() {
     if opt.version {
         println!("{}", bo_version());
     } else {
+        let mut start_line: Option<usize> = None;
+        let mut file_name: Option<String> = None;
+        for arg in opt.args {
+            match utils::parse_start_line_argument(&arg) {
+                Some(line_number) if start_line.is_none() => start_line = Some(line_number),
+                _ => file_name = file_name.or(Some(arg)),
+            }
+        }
         let term = Box::new(Terminal::default().unwrap());
-        Editor::new(opt.file_name, term).run();
+        let mut editor = Editor::new(file_name, term);
+        if let Some(name) = opt.session {
+            editor.load_session(&name);
+        }
+        if let Some(line_number) = start_line {
+            editor.goto_start_line(line_number);
+        }
+        if opt.insert {
+            editor.start_in_insert_mode();
+        }
+        editor.run();
     }
 }
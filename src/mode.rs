@@ -4,6 +4,8 @@ use std::fmt;
 pub enum Mode {
     Insert,
     Normal,
+    Visual,
+    Replace,
 }
 
 impl fmt::Display for Mode {
@@ -11,6 +13,8 @@ impl fmt::Display for Mode {
         match *self {
             Mode::Insert => write!(f, "INSERT"),
             Mode::Normal => write!(f, "NORMAL"),
+            Mode::Visual => write!(f, "VISUAL"),
+            Mode::Replace => write!(f, "REPLACE"),
         }
     }
 }
@@ -4,4 +4,6 @@ use crate::Mode;
 fn test_mode_display() {
     assert_eq!(format!("{}", Mode::Normal), "NORMAL");
     assert_eq!(format!("{}", Mode::Insert), "INSERT");
+    assert_eq!(format!("{}", Mode::Visual), "VISUAL");
+    assert_eq!(format!("{}", Mode::Replace), "REPLACE");
 }
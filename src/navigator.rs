@@ -15,12 +15,84 @@ fn matching_opening_symbols() -> HashMap<&'static str, &'static str> {
         .copied()
         .collect()
 }
+
+/// Return whether the grapheme only contains whitespace
+fn is_whitespace(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
 #[derive(PartialEq)]
 pub enum Boundary {
     Start,
     End,
 }
 
+/// An opening or closing HTML/XML tag, located on a single row
+struct Tag {
+    name: String,
+    closing: bool,
+    y: usize,
+    /// Indices of the `<` and `>` graphemes delimiting the tag
+    start: usize,
+    end: usize,
+}
+
+/// Return the opening and closing tags of the row, ignoring their attributes. Self-closing
+/// tags, comments (on a single row), doctypes and processing instructions don't need to be
+/// matched, and are left out.
+fn find_tags_in_row(row: &Row, y: usize) -> Vec<Tag> {
+    let graphemes: Vec<&str> = row.graphemes().collect();
+    let mut tags = vec![];
+    let mut index = 0;
+    while index < graphemes.len() {
+        if graphemes[index] != "<" {
+            index = index.saturating_add(1);
+            continue;
+        }
+        if graphemes[index..].concat().starts_with("<!--") {
+            // skip the comment, which can contain tags
+            let Some(length) = graphemes[index..]
+                .windows(3)
+                .position(|window| window.concat() == "-->")
+            else {
+                break;
+            };
+            index = index.saturating_add(length).saturating_add(3);
+            continue;
+        }
+        let Some(length) = graphemes[index.saturating_add(1)..]
+            .iter()
+            .position(|g| *g == ">" || *g == "<")
+        else {
+            break;
+        };
+        let end = index.saturating_add(length).saturating_add(1);
+        if graphemes[end] == "<" {
+            // the first `<` wasn't opening a tag (eg: `a < b`)
+            index = end;
+            continue;
+        }
+        let content = graphemes[index.saturating_add(1)..end].concat();
+        let closing = content.starts_with('/');
+        let name: String = content
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '.'))
+            .collect();
+        if !name.is_empty() && !content.ends_with('/') {
+            tags.push(Tag {
+                name,
+                closing,
+                y,
+                start: index,
+                end,
+            });
+        }
+        index = end.saturating_add(1);
+    }
+    tags
+}
+
 #[derive(Debug)]
 pub struct Navigator {}
 
@@ -35,6 +107,102 @@ impl Navigator {
         None
     }
 
+    /// Return the index of the nth occurrence of the character located after (or before)
+    /// the provided x index in the row, as found by the `f` and `F` motions.
+    #[must_use]
+    pub fn find_char_in_row(
+        row: &Row,
+        x: usize,
+        c: char,
+        forward: bool,
+        times: usize,
+    ) -> Option<usize> {
+        let target = c.to_string();
+        let graphemes: Vec<&str> = row.graphemes().collect();
+        let is_target = |index: &usize| graphemes[*index] == target;
+        let nth = times.saturating_sub(1);
+        if forward {
+            (x.saturating_add(1)..graphemes.len())
+                .filter(is_target)
+                .nth(nth)
+        } else {
+            (0..cmp::min(x, graphemes.len()))
+                .rev()
+                .filter(is_target)
+                .nth(nth)
+        }
+    }
+
+    /// Return the position of the nth occurrence of the character located after (or
+    /// before) the provided position, the search continuing on the following (or
+    /// preceding) rows of the document.
+    #[must_use]
+    pub fn find_char_in_document(
+        document: &Document,
+        position: &Position,
+        c: char,
+        forward: bool,
+        times: usize,
+    ) -> Option<Position> {
+        let target = c.to_string();
+        let occurrences_in_row = |y: usize| -> Vec<Position> {
+            let Some(row) = document.get_row(y) else {
+                return vec![];
+            };
+            let mut occurrences: Vec<Position> = row
+                .graphemes()
+                .enumerate()
+                .filter(|(x, grapheme)| {
+                    *grapheme == target
+                        && (y != position.y
+                            || (forward && *x > position.x)
+                            || (!forward && *x < position.x))
+                })
+                .map(|(x, _)| Position { x, y })
+                .collect();
+            if !forward {
+                occurrences.reverse();
+            }
+            occurrences
+        };
+        let nth = times.saturating_sub(1);
+        if forward {
+            (position.y..document.line_count())
+                .flat_map(occurrences_in_row)
+                .nth(nth)
+        } else {
+            (0..=position.y).rev().flat_map(occurrences_in_row).nth(nth)
+        }
+    }
+
+    /// Return the indices of the first and last graphemes of the word located
+    /// under the provided x index, or None if that grapheme isn't part of a word.
+    #[must_use]
+    pub fn find_boundaries_of_word_under_cursor(row: &Row, x: usize) -> Option<(usize, usize)> {
+        // a grapheme is classified by its first character (eg: the letter of an accented letter)
+        let is_word_grapheme = |grapheme: &str| {
+            grapheme
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        };
+        let graphemes: Vec<&str> = row.graphemes().collect();
+        if !is_word_grapheme(graphemes.get(x)?) {
+            return None;
+        }
+        let mut start = x;
+        while start > 0 && is_word_grapheme(graphemes[start.saturating_sub(1)]) {
+            start = start.saturating_sub(1);
+        }
+        let mut end = x;
+        while end.saturating_add(1) < graphemes.len()
+            && is_word_grapheme(graphemes[end.saturating_add(1)])
+        {
+            end = end.saturating_add(1);
+        }
+        Some((start, end))
+    }
+
     /// Return the index of the matching closing symbol (eg } for {, etc)
     /// # Panics
     /// TODO
@@ -53,7 +221,7 @@ impl Navigator {
         let mut stack = vec![symbol];
         let mut current_opening_symbol = symbol;
         matching_closing_symbols().get(&symbol)?;
-        for y in initial_row_position..document.num_rows() {
+        for y in initial_row_position..document.line_count() {
             let current_row = document.get_row(y).unwrap();
             let start_x = if y == initial_row_position {
                 initial_col_position.saturating_add(1)
@@ -125,6 +293,41 @@ impl Navigator {
         None
     }
 
+    /// Return the position of the `<` of the tag matching the HTML/XML tag under the
+    /// cursor (eg: `</div>` for `<div class="a">`), nested tags bearing the same name
+    /// being skipped.
+    #[must_use]
+    pub fn find_matching_tag(document: &Document, position: &Position) -> Option<Position> {
+        let tags: Vec<Tag> = document
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| find_tags_in_row(row, y))
+            .collect();
+        let index = tags.iter().position(|tag| {
+            tag.y == position.y && tag.start <= position.x && position.x <= tag.end
+        })?;
+        let tag = &tags[index];
+        let candidates: Box<dyn Iterator<Item = &Tag>> = if tag.closing {
+            Box::new(tags[..index].iter().rev())
+        } else {
+            Box::new(tags[index.saturating_add(1)..].iter())
+        };
+        let mut depth: usize = 0;
+        for other in candidates.filter(|other| other.name == tag.name) {
+            if other.closing == tag.closing {
+                depth = depth.saturating_add(1);
+            } else if depth == 0 {
+                return Some(Position {
+                    x: other.start,
+                    y: other.y,
+                });
+            } else {
+                depth = depth.saturating_sub(1);
+            }
+        }
+        None
+    }
+
     #[must_use]
     pub fn find_line_number_of_start_or_end_of_paragraph(
         document: &Document,
@@ -135,14 +338,12 @@ impl Navigator {
         loop {
             current_line_number = match boundary {
                 Boundary::Start => cmp::max(1, current_line_number.saturating_sub(1)),
-                Boundary::End => cmp::min(
-                    document.last_line_number(),
-                    current_line_number.saturating_add(1),
-                ),
+                Boundary::End => {
+                    cmp::min(document.line_count(), current_line_number.saturating_add(1))
+                }
             };
             if (current_line_number == 1 && boundary == &Boundary::Start)
-                || (current_line_number == document.last_line_number()
-                    && boundary == &Boundary::End)
+                || (current_line_number == document.line_count() && boundary == &Boundary::End)
             {
                 return current_line_number;
             }
@@ -162,6 +363,379 @@ impl Navigator {
         }
     }
 
+    /// Return the position of the end of the word (or WORD, being a sequence of
+    /// non-blank characters) preceding the provided document position, possibly
+    /// located on a previous line. Empty lines are considered to be words.
+    #[must_use]
+    pub fn find_end_of_previous_word(
+        document: &Document,
+        position: &Position,
+        big_word: bool,
+    ) -> Position {
+        // 0: whitespace, 1: punctuation, 2: word characters, a grapheme being classified
+        // by its first character (eg: the letter of an accented letter)
+        let grapheme_class = |grapheme: &str| {
+            let c = grapheme.chars().next().unwrap_or(' ');
+            if is_whitespace(grapheme) {
+                0
+            } else if big_word || !(c.is_alphanumeric() || c == '_') {
+                1
+            } else {
+                2
+            }
+        };
+        for y in (0..=position.y).rev() {
+            let Some(row) = document.get_row(y) else {
+                continue;
+            };
+            let graphemes: Vec<&str> = row.graphemes().collect();
+            if graphemes.is_empty() && y < position.y {
+                return Position { x: 0, y };
+            }
+            let end_x = if y == position.y {
+                cmp::min(position.x, graphemes.len())
+            } else {
+                graphemes.len()
+            };
+            for x in (0..end_x).rev() {
+                let class = grapheme_class(graphemes[x]);
+                let next_class = graphemes
+                    .get(x.saturating_add(1))
+                    .map_or(0, |grapheme| grapheme_class(grapheme));
+                if class != 0 && class != next_class {
+                    return Position { x, y };
+                }
+            }
+        }
+        Position::default()
+    }
+
+    /// Return the position of the start of the next sentence (`Boundary::End`) or of the
+    /// previous one (`Boundary::Start`), the provided position being expressed in document
+    /// coordinates. A sentence ends with a `.`, `!` or `?` (optionally followed by closing
+    /// quotes or brackets) followed by whitespace or the end of the line. Sentences can span
+    /// over multiple lines, and an empty line is considered to be its own sentence.
+    #[must_use]
+    pub fn find_sentence_boundary(
+        document: &Document,
+        position: &Position,
+        boundary: &Boundary,
+    ) -> Position {
+        // the rows are walked from the position, an empty row having a single position at x=0
+        match boundary {
+            Boundary::End => {
+                for y in position.y..document.line_count() {
+                    let graphemes = Self::row_graphemes(document, y);
+                    let from_x = if y == position.y {
+                        position.x.saturating_add(1)
+                    } else {
+                        0
+                    };
+                    if let Some(x) = (from_x..cmp::max(graphemes.len(), 1)).find(|&x| {
+                        Self::is_sentence_start(document, &graphemes, &Position { x, y })
+                    }) {
+                        return Position { x, y };
+                    }
+                }
+                let last_y = document.line_count().saturating_sub(1);
+                Position {
+                    x: document
+                        .get_row(last_y)
+                        .map_or(0, |row| row.len().saturating_sub(1)),
+                    y: last_y,
+                }
+            }
+            Boundary::Start => {
+                for y in (0..=position.y).rev() {
+                    let graphemes = Self::row_graphemes(document, y);
+                    let to_x = if y == position.y {
+                        cmp::min(position.x, cmp::max(graphemes.len(), 1))
+                    } else {
+                        cmp::max(graphemes.len(), 1)
+                    };
+                    if let Some(x) = (0..to_x).rev().find(|&x| {
+                        Self::is_sentence_start(document, &graphemes, &Position { x, y })
+                    }) {
+                        return Position { x, y };
+                    }
+                }
+                Position::default()
+            }
+        }
+    }
+
+    /// Return the graphemes of the row located at the provided index
+    fn row_graphemes(document: &Document, y: usize) -> Vec<&str> {
+        document
+            .get_row(y)
+            .map_or_else(Vec::new, |row| row.graphemes().collect())
+    }
+
+    /// Return the opening and closing brackets selected by a text object key
+    /// (eg: `(`, `)` or `b` for parentheses, as in `di(`).
+    #[must_use]
+    pub fn brackets_of_text_object(c: char) -> Option<(&'static str, &'static str)> {
+        match c {
+            '(' | ')' | 'b' => Some(("(", ")")),
+            '[' | ']' => Some(("[", "]")),
+            '{' | '}' | 'B' => Some(("{", "}")),
+            '<' | '>' => Some(("<", ">")),
+            _ => None,
+        }
+    }
+
+    /// Return the start (inclusive) and end (exclusive) positions of the text located
+    /// between the brackets of the innermost pair enclosing the provided position, the
+    /// brackets being included in the "around" variant (eg: `a(`). When the position
+    /// isn't enclosed in such a pair, the next pair opened on the same line is used instead.
+    #[must_use]
+    pub fn find_boundaries_of_brackets_around_cursor(
+        document: &Document,
+        position: &Position,
+        brackets: (&str, &str),
+        around: bool,
+    ) -> Option<(Position, Position)> {
+        let opening_position = Self::find_enclosing_opening_bracket(document, position, brackets)
+            .or_else(|| {
+            Self::find_next_opening_bracket_in_row(document, position, brackets.0)
+        })?;
+        let closing_position = Self::find_closing_bracket(document, &opening_position, brackets)?;
+        if around {
+            Some((
+                opening_position,
+                Position {
+                    x: closing_position.x.saturating_add(1),
+                    y: closing_position.y,
+                },
+            ))
+        } else {
+            Some((
+                Position {
+                    x: opening_position.x.saturating_add(1),
+                    y: opening_position.y,
+                },
+                closing_position,
+            ))
+        }
+    }
+
+    /// Return the start (inclusive) and end (exclusive) indices of the text located
+    /// between the quotes of the pair enclosing the provided index in the row, the
+    /// quotes being included in the "around" variant (eg: `a"`). The quotes are paired
+    /// from the start of the row, the escaped ones being skipped. When the index isn't
+    /// enclosed in such a pair, the next pair of the row is used instead.
+    #[must_use]
+    pub fn find_boundaries_of_quotes_in_row(
+        row: &Row,
+        x: usize,
+        quote: &str,
+        around: bool,
+    ) -> Option<(usize, usize)> {
+        let graphemes: Vec<&str> = row.graphemes().collect();
+        let quotes: Vec<usize> = (0..graphemes.len())
+            .filter(|index| {
+                graphemes[*index] == quote && (*index == 0 || graphemes[index - 1] != "\\")
+            })
+            .collect();
+        let (start, end) = quotes
+            .chunks_exact(2)
+            .map(|pair| (pair[0], pair[1]))
+            .find(|(_, end)| x <= *end)?;
+        if around {
+            Some((start, end.saturating_add(1)))
+        } else {
+            Some((start.saturating_add(1), end))
+        }
+    }
+
+    /// Return the position of the opening bracket of the innermost pair enclosing the
+    /// provided position, which can be located on one of its brackets.
+    fn find_enclosing_opening_bracket(
+        document: &Document,
+        position: &Position,
+        (opening, closing): (&str, &str),
+    ) -> Option<Position> {
+        let mut depth: usize = 0;
+        for y in (0..=position.y).rev() {
+            let graphemes: Vec<&str> = document.get_row(y)?.graphemes().collect();
+            let end = if y == position.y {
+                cmp::min(position.x.saturating_add(1), graphemes.len())
+            } else {
+                graphemes.len()
+            };
+            for x in (0..end).rev() {
+                if graphemes[x] == closing && (x, y) != (position.x, position.y) {
+                    depth = depth.saturating_add(1);
+                } else if graphemes[x] == opening {
+                    if depth == 0 {
+                        return Some(Position { x, y });
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+            }
+        }
+        None
+    }
+
+    fn find_next_opening_bracket_in_row(
+        document: &Document,
+        position: &Position,
+        opening: &str,
+    ) -> Option<Position> {
+        document
+            .get_row(position.y)?
+            .graphemes()
+            .enumerate()
+            .skip(position.x.saturating_add(1))
+            .find(|(_, grapheme)| *grapheme == opening)
+            .map(|(x, _)| Position { x, y: position.y })
+    }
+
+    /// Return the position of the bracket closing the one located at the provided position
+    fn find_closing_bracket(
+        document: &Document,
+        opening_position: &Position,
+        (opening, closing): (&str, &str),
+    ) -> Option<Position> {
+        let mut depth: usize = 0;
+        for y in opening_position.y..document.line_count() {
+            let start = if y == opening_position.y {
+                opening_position.x.saturating_add(1)
+            } else {
+                0
+            };
+            for (x, grapheme) in document.get_row(y)?.graphemes().enumerate().skip(start) {
+                if grapheme == opening {
+                    depth = depth.saturating_add(1);
+                } else if grapheme == closing {
+                    if depth == 0 {
+                        return Some(Position { x, y });
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+            }
+        }
+        None
+    }
+
+    /// Return the start (inclusive) and end (exclusive) positions of the sentence located
+    /// under the provided position. The "around" variant (`as`) also includes the whitespace
+    /// following the sentence on the same line or, if there is none, the whitespace preceding it.
+    #[must_use]
+    pub fn find_boundaries_of_sentence_under_cursor(
+        document: &Document,
+        position: &Position,
+        around: bool,
+    ) -> (Position, Position) {
+        let mut start = if Self::is_sentence_start(
+            document,
+            &Self::row_graphemes(document, position.y),
+            position,
+        ) {
+            *position
+        } else {
+            Self::find_sentence_boundary(document, position, &Boundary::Start)
+        };
+        let next_start = Self::find_sentence_boundary(document, position, &Boundary::End);
+        let next_is_sentence_start = Self::is_sentence_start(
+            document,
+            &Self::row_graphemes(document, next_start.y),
+            &next_start,
+        );
+
+        // the sentence ends on the last non-whitespace grapheme preceding the next one
+        let mut end = start;
+        for (y, row) in document.iter().enumerate().skip(start.y) {
+            let from_x = if y == start.y { start.x } else { 0 };
+            for (x, grapheme) in row.graphemes().enumerate().skip(from_x) {
+                if next_is_sentence_start && (y, x) >= (next_start.y, next_start.x) {
+                    break;
+                }
+                if !is_whitespace(grapheme) {
+                    end = Position {
+                        x: x.saturating_add(1),
+                        y,
+                    };
+                }
+            }
+            if (next_is_sentence_start && y >= next_start.y) || row.is_empty() {
+                break;
+            }
+        }
+        if around {
+            let trailing_end = if next_is_sentence_start && next_start.y == end.y {
+                next_start.x
+            } else {
+                document.get_row(end.y).map_or(0, Row::len)
+            };
+            if trailing_end > end.x {
+                end.x = trailing_end;
+            } else {
+                let leading_whitespace = document.get_row(start.y).map_or(0, |row| {
+                    let preceding: Vec<&str> = row.graphemes().take(start.x).collect();
+                    preceding
+                        .iter()
+                        .rev()
+                        .take_while(|grapheme| is_whitespace(grapheme))
+                        .count()
+                });
+                start.x = start.x.saturating_sub(leading_whitespace);
+            }
+        }
+        (start, end)
+    }
+
+    /// Return whether the provided position is located at the start of a sentence, the
+    /// graphemes of its row being provided. The preceding rows are only looked at when
+    /// there is nothing but whitespace before the position on its row.
+    fn is_sentence_start(document: &Document, graphemes: &[&str], position: &Position) -> bool {
+        let is_blank = |y: usize| document.get_row(y).is_some_and(Row::is_whitespace);
+        if document.get_row(position.y).is_none() {
+            return false;
+        }
+        if is_blank(position.y) {
+            // only the first one of consecutive empty lines is a sentence start
+            return position.x == 0 && (position.y == 0 || !is_blank(position.y.saturating_sub(1)));
+        }
+        match graphemes.get(position.x) {
+            Some(grapheme) if !is_whitespace(grapheme) => (),
+            _ => return false,
+        }
+        // look for the last non-whitespace grapheme preceding the position
+        let mut preceding = &graphemes[..position.x];
+        let mut previous_row: Vec<&str>;
+        let mut y = position.y;
+        let mut separated = false;
+        loop {
+            let trimmed_len = preceding.len()
+                - preceding
+                    .iter()
+                    .rev()
+                    .take_while(|grapheme| is_whitespace(grapheme))
+                    .count();
+            separated = separated || trimmed_len < preceding.len();
+            preceding = &preceding[..trimmed_len];
+            if !preceding.is_empty() {
+                break;
+            }
+            if y == 0 {
+                return true; // start of the document
+            }
+            y = y.saturating_sub(1);
+            if is_blank(y) {
+                return true; // start of a paragraph
+            }
+            previous_row = Self::row_graphemes(document, y);
+            preceding = &previous_row;
+            separated = true;
+        }
+        let end_of_sentence = preceding
+            .iter()
+            .rev()
+            .find(|grapheme| !matches!(**grapheme, ")" | "]" | "\"" | "'"));
+        separated && matches!(end_of_sentence, Some(&("." | "!" | "?")))
+    }
+
     #[allow(clippy::suspicious_operation_groupings)]
     #[must_use]
     // mirrorred over the look and feel of vim
@@ -28,6 +28,47 @@ fn test_find_index_of_first_non_whitespace() {
     );
 }
 
+#[test]
+fn test_find_char_in_row() {
+    let row = Row::from("f(a, b), g(c)");
+    assert_eq!(Navigator::find_char_in_row(&row, 0, ')', true, 1), Some(6));
+    assert_eq!(Navigator::find_char_in_row(&row, 0, ')', true, 2), Some(12));
+    assert_eq!(Navigator::find_char_in_row(&row, 0, ')', true, 3), None);
+    // the character under the cursor is skipped
+    assert_eq!(Navigator::find_char_in_row(&row, 6, ')', true, 1), Some(12));
+    assert_eq!(
+        Navigator::find_char_in_row(&row, 12, '(', false, 1),
+        Some(10)
+    );
+    assert_eq!(
+        Navigator::find_char_in_row(&row, 12, '(', false, 2),
+        Some(1)
+    );
+    assert_eq!(Navigator::find_char_in_row(&row, 1, '(', false, 1), None);
+    assert_eq!(
+        Navigator::find_char_in_row(&Row::from("é, à"), 0, 'à', true, 1),
+        Some(3)
+    );
+}
+
+#[test]
+fn test_find_char_in_document() {
+    let doc = Document::new(
+        vec![Row::from("a, b"), Row::from("c"), Row::from("d, e, f")],
+        PathBuf::from("test"),
+    );
+    let find = |x, y, forward, times| {
+        Navigator::find_char_in_document(&doc, &Position { x, y }, ',', forward, times)
+    };
+    assert_eq!(find(0, 0, true, 1), Some(Position { x: 1, y: 0 }));
+    assert_eq!(find(1, 0, true, 1), Some(Position { x: 1, y: 2 }));
+    assert_eq!(find(1, 0, true, 2), Some(Position { x: 4, y: 2 }));
+    assert_eq!(find(1, 0, true, 3), None);
+    assert_eq!(find(4, 2, false, 1), Some(Position { x: 1, y: 2 }));
+    assert_eq!(find(1, 2, false, 1), Some(Position { x: 1, y: 0 }));
+    assert_eq!(find(0, 1, false, 2), None);
+}
+
 #[test]
 fn test_find_matching_closing_symbol() {
     let doc = Document::new(vec![Row::from("fn test() {}")], PathBuf::from("test.txt"));
@@ -319,3 +360,288 @@ fn test_find_index_of_previous_word_with_unicode() {
         );
     }
 }
+
+#[test]
+fn test_find_boundaries_of_word_under_cursor() {
+    let row = test_row_word_nav();
+    assert_eq!(
+        Navigator::find_boundaries_of_word_under_cursor(&row, 8),
+        Some((6, 20))
+    );
+    assert_eq!(
+        Navigator::find_boundaries_of_word_under_cursor(&row, 0),
+        Some((0, 4))
+    );
+    assert_eq!(
+        Navigator::find_boundaries_of_word_under_cursor(&row, 5),
+        None
+    );
+    assert_eq!(
+        Navigator::find_boundaries_of_word_under_cursor(&row, 200),
+        None
+    );
+    // "é" is written as an "e" followed by a combining accent
+    let row = Row::from("un cafe\u{301} noir");
+    assert_eq!(
+        Navigator::find_boundaries_of_word_under_cursor(&row, 6),
+        Some((3, 6))
+    );
+    assert_eq!(
+        Navigator::find_boundaries_of_word_under_cursor(&row, 8),
+        Some((8, 11))
+    );
+}
+
+fn test_prose_document() -> Document {
+    Document::new(
+        vec![
+            Row::from("Hello world. How are you? I am"),
+            Row::from("fine, thanks! (Really.) Bye"),
+            Row::from(""),
+            Row::from("  Version 1.2 is out."),
+        ],
+        PathBuf::from("test.txt"),
+    )
+}
+
+#[test]
+fn test_find_start_of_next_sentence() {
+    let doc = test_prose_document();
+    let next = |x, y| Navigator::find_sentence_boundary(&doc, &Position { x, y }, &Boundary::End);
+    assert_eq!(next(0, 0), Position { x: 13, y: 0 });
+    assert_eq!(next(13, 0), Position { x: 26, y: 0 });
+    // the sentence spans over two lines
+    assert_eq!(next(26, 0), Position { x: 14, y: 1 });
+    assert_eq!(next(14, 1), Position { x: 24, y: 1 });
+    // empty lines are sentence boundaries
+    assert_eq!(next(24, 1), Position { x: 0, y: 2 });
+    assert_eq!(next(0, 2), Position { x: 2, y: 3 });
+    // "1.2" does not end a sentence, and we stop at the end of the document
+    assert_eq!(next(2, 3), Position { x: 20, y: 3 });
+}
+
+#[test]
+fn test_find_start_of_previous_sentence() {
+    let doc = test_prose_document();
+    let previous =
+        |x, y| Navigator::find_sentence_boundary(&doc, &Position { x, y }, &Boundary::Start);
+    assert_eq!(previous(10, 3), Position { x: 2, y: 3 });
+    assert_eq!(previous(2, 3), Position { x: 0, y: 2 });
+    assert_eq!(previous(0, 2), Position { x: 24, y: 1 });
+    assert_eq!(previous(24, 1), Position { x: 14, y: 1 });
+    assert_eq!(previous(14, 1), Position { x: 26, y: 0 });
+    assert_eq!(previous(3, 1), Position { x: 26, y: 0 });
+    assert_eq!(previous(20, 0), Position { x: 13, y: 0 });
+    assert_eq!(previous(13, 0), Position { x: 0, y: 0 });
+    assert_eq!(previous(0, 0), Position { x: 0, y: 0 });
+}
+
+#[test]
+fn test_find_sentence_boundary_with_multi_char_graphemes() {
+    // "é" is written as an "e" followed by a combining accent
+    let doc = Document::new(
+        vec![Row::from("Cafe\u{301} noir. Ole\u{301}! Fin.")],
+        PathBuf::from("test.txt"),
+    );
+    let next = |x, y| Navigator::find_sentence_boundary(&doc, &Position { x, y }, &Boundary::End);
+    let previous =
+        |x, y| Navigator::find_sentence_boundary(&doc, &Position { x, y }, &Boundary::Start);
+    assert_eq!(next(0, 0), Position { x: 11, y: 0 });
+    assert_eq!(next(11, 0), Position { x: 16, y: 0 });
+    assert_eq!(previous(16, 0), Position { x: 11, y: 0 });
+
+    let around =
+        |x, y| Navigator::find_boundaries_of_sentence_under_cursor(&doc, &Position { x, y }, true);
+    assert_eq!(
+        around(12, 0),
+        (Position { x: 11, y: 0 }, Position { x: 16, y: 0 })
+    );
+    // last sentence of the line: the preceding whitespace is included
+    assert_eq!(
+        around(17, 0),
+        (Position { x: 15, y: 0 }, Position { x: 20, y: 0 })
+    );
+}
+
+#[test]
+fn test_find_boundaries_of_sentence_under_cursor() {
+    let doc = test_prose_document();
+    let inner =
+        |x, y| Navigator::find_boundaries_of_sentence_under_cursor(&doc, &Position { x, y }, false);
+    let around =
+        |x, y| Navigator::find_boundaries_of_sentence_under_cursor(&doc, &Position { x, y }, true);
+    assert_eq!(
+        inner(16, 0),
+        (Position { x: 13, y: 0 }, Position { x: 25, y: 0 })
+    );
+    assert_eq!(
+        around(16, 0),
+        (Position { x: 13, y: 0 }, Position { x: 26, y: 0 })
+    );
+    // sentence spanning over two lines
+    assert_eq!(
+        inner(28, 0),
+        (Position { x: 26, y: 0 }, Position { x: 13, y: 1 })
+    );
+    // last sentence of a paragraph: the preceding whitespace is included
+    assert_eq!(
+        around(25, 1),
+        (Position { x: 23, y: 1 }, Position { x: 27, y: 1 })
+    );
+}
+
+#[test]
+fn test_find_end_of_previous_word() {
+    let doc = test_document();
+    let previous_end =
+        |x, y, big_word| Navigator::find_end_of_previous_word(&doc, &Position { x, y }, big_word);
+    assert_eq!(previous_end(5, 0, false), Position { x: 3, y: 0 });
+    // the empty line is a word
+    assert_eq!(previous_end(0, 2, false), Position { x: 0, y: 1 });
+    assert_eq!(previous_end(0, 1, true), Position { x: 10, y: 0 });
+    assert_eq!(previous_end(2, 0, false), Position { x: 0, y: 0 });
+}
+
+#[test]
+fn test_find_end_of_previous_word_with_multi_char_graphemes() {
+    // "é" is written as an "e" followed by a combining accent
+    let doc = Document::new(
+        vec![Row::from("cafe\u{301} bar")],
+        PathBuf::from("test.txt"),
+    );
+    let previous_end =
+        |x, y, big_word| Navigator::find_end_of_previous_word(&doc, &Position { x, y }, big_word);
+    assert_eq!(previous_end(6, 0, false), Position { x: 3, y: 0 });
+    assert_eq!(previous_end(6, 0, true), Position { x: 3, y: 0 });
+}
+
+#[test]
+fn test_find_boundaries_of_brackets_around_cursor() {
+    let doc = Document::new(
+        vec![
+            Row::from("let a = f(x, (y + 1));"),
+            Row::from("if a {"),
+            Row::from("    b();"),
+            Row::from("}"),
+        ],
+        PathBuf::from("test.rs"),
+    );
+    let boundaries = |x, y, brackets, around| {
+        Navigator::find_boundaries_of_brackets_around_cursor(
+            &doc,
+            &Position { x, y },
+            brackets,
+            around,
+        )
+    };
+    // the cursor is located before the pair on the line
+    assert_eq!(
+        boundaries(0, 0, ("(", ")"), false),
+        Some((Position { x: 10, y: 0 }, Position { x: 20, y: 0 }))
+    );
+    assert_eq!(
+        boundaries(0, 0, ("(", ")"), true),
+        Some((Position { x: 9, y: 0 }, Position { x: 21, y: 0 }))
+    );
+    // innermost pair enclosing the cursor, including when it's on a bracket
+    assert_eq!(
+        boundaries(15, 0, ("(", ")"), false),
+        Some((Position { x: 14, y: 0 }, Position { x: 19, y: 0 }))
+    );
+    assert_eq!(
+        boundaries(20, 0, ("(", ")"), false),
+        Some((Position { x: 10, y: 0 }, Position { x: 20, y: 0 }))
+    );
+    // pair spanning over several lines
+    assert_eq!(
+        boundaries(4, 2, ("{", "}"), false),
+        Some((Position { x: 6, y: 1 }, Position { x: 0, y: 3 }))
+    );
+    assert_eq!(boundaries(0, 1, ("[", "]"), false), None);
+    assert_eq!(boundaries(21, 0, ("(", ")"), false), None);
+}
+
+#[test]
+fn test_find_matching_tag() {
+    let document = Document::new(
+        vec![
+            Row::from("<div class=\"a\">"),
+            Row::from("  <div><p>a < b</p></div>"),
+            Row::from("</div>"),
+        ],
+        PathBuf::from("test.html"),
+    );
+    let position = |x, y| Position { x, y };
+    // the attributes are ignored, and the nested tags bearing the same name are skipped
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(0, 0)),
+        Some(position(0, 2))
+    );
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(14, 0)),
+        Some(position(0, 2))
+    );
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(3, 2)),
+        Some(position(0, 0))
+    );
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(2, 1)),
+        Some(position(19, 1))
+    );
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(19, 1)),
+        Some(position(2, 1))
+    );
+    // a lone `<` doesn't prevent the next tag from being matched
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(16, 1)),
+        Some(position(7, 1))
+    );
+    // outside of a tag
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(11, 1)),
+        None
+    );
+}
+
+#[test]
+fn test_find_matching_tag_skips_self_closing_tags() {
+    let document = Document::new(
+        vec![
+            Row::from("<svg><path d=\"M0\"/><!-- <svg> -->"),
+            Row::from("<svg/></svg>"),
+        ],
+        PathBuf::from("test.svg"),
+    );
+    let position = |x, y| Position { x, y };
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(0, 0)),
+        Some(position(6, 1))
+    );
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(0, 1)),
+        None
+    );
+    assert_eq!(
+        Navigator::find_matching_tag(&document, &position(8, 0)),
+        None
+    );
+}
+
+#[test]
+fn test_find_boundaries_of_quotes_in_row() {
+    let row = Row::from(r#"let s = "a \"b\"" + "c";"#);
+    let boundaries = |x, around| Navigator::find_boundaries_of_quotes_in_row(&row, x, "\"", around);
+    // the cursor is located before the first pair, or inside it (escaped quotes included)
+    assert_eq!(boundaries(0, false), Some((9, 16)));
+    assert_eq!(boundaries(12, false), Some((9, 16)));
+    assert_eq!(boundaries(16, true), Some((8, 17)));
+    // between two pairs, the next one is used
+    assert_eq!(boundaries(18, false), Some((21, 22)));
+    assert_eq!(boundaries(23, false), None);
+    assert_eq!(
+        Navigator::find_boundaries_of_quotes_in_row(&row, 0, "'", false),
+        None
+    );
+}
@@ -0,0 +1,68 @@
+/// The lines (row indices, both inclusive) a command applies to, as given before its
+/// name (eg: `:1,10d`, `:.,$s/a/b/`, or `:%sort` for the whole document).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    /// Parse the range located at the start of the command (without its leading `:`),
+    /// and return it along with the rest of the command. `%` stands for the whole
+    /// document, `.` for the current line and `$` for the last one. Return `None` if
+    /// the command doesn't start with a range.
+    #[must_use]
+    pub fn parse(
+        command: &str,
+        current_row_index: usize,
+        last_row_index: usize,
+    ) -> Option<(Self, &str)> {
+        if let Some(rest) = command.strip_prefix('%') {
+            let range = Self {
+                start: 0,
+                end: last_row_index,
+            };
+            return Some((range, rest.trim_start()));
+        }
+        let (start, rest) = Self::parse_address(command, current_row_index, last_row_index)?;
+        let (end, rest) = match rest.strip_prefix(',') {
+            Some(rest) => Self::parse_address(rest, current_row_index, last_row_index)?,
+            None => (start, rest),
+        };
+        // a backward range is swapped, instead of being rejected
+        let range = Self {
+            start: start.min(end),
+            end: start.max(end),
+        };
+        Some((range, rest.trim_start()))
+    }
+
+    /// Parse a line number, `.` or `$` and return the row index it refers to (capped
+    /// to the last one), along with the rest of the text.
+    fn parse_address(
+        text: &str,
+        current_row_index: usize,
+        last_row_index: usize,
+    ) -> Option<(usize, &str)> {
+        if let Some(rest) = text.strip_prefix('.') {
+            return Some((current_row_index, rest));
+        }
+        if let Some(rest) = text.strip_prefix('$') {
+            return Some((last_row_index, rest));
+        }
+        let num_digits = text.chars().take_while(char::is_ascii_digit).count();
+        let line_number = text[..num_digits].parse::<usize>().ok()?;
+        let row_index = line_number.saturating_sub(1).min(last_row_index);
+        Some((row_index, &text[num_digits..]))
+    }
+
+    /// Return the number of lines in the range
+    #[must_use]
+    pub fn line_count(&self) -> usize {
+        self.end.saturating_sub(self.start).saturating_add(1)
+    }
+}
+
+#[cfg(test)]
+#[path = "./range_test.rs"]
+mod range_test;
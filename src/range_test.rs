@@ -0,0 +1,60 @@
+use crate::LineRange;
+
+#[test]
+fn test_line_range_parse_whole_document() {
+    assert_eq!(
+        LineRange::parse("%d", 3, 9),
+        Some((LineRange { start: 0, end: 9 }, "d"))
+    );
+    assert_eq!(
+        LineRange::parse("%!sort -r", 3, 9),
+        Some((LineRange { start: 0, end: 9 }, "!sort -r"))
+    );
+    assert_eq!(
+        LineRange::parse("% sort", 3, 9),
+        Some((LineRange { start: 0, end: 9 }, "sort"))
+    );
+}
+
+#[test]
+fn test_line_range_parse_addresses() {
+    assert_eq!(
+        LineRange::parse("2,5d", 3, 9),
+        Some((LineRange { start: 1, end: 4 }, "d"))
+    );
+    assert_eq!(
+        LineRange::parse(".,$s/a/b/", 3, 9),
+        Some((LineRange { start: 3, end: 9 }, "s/a/b/"))
+    );
+    assert_eq!(
+        LineRange::parse("$", 3, 9),
+        Some((LineRange { start: 9, end: 9 }, ""))
+    );
+    assert_eq!(
+        LineRange::parse(".d", 3, 9),
+        Some((LineRange { start: 3, end: 3 }, "d"))
+    );
+    // line numbers past the end of the document refer to the last line
+    assert_eq!(
+        LineRange::parse("5,100sort", 3, 9),
+        Some((LineRange { start: 4, end: 9 }, "sort"))
+    );
+    // backward ranges are swapped
+    assert_eq!(
+        LineRange::parse("$,2d", 3, 9),
+        Some((LineRange { start: 1, end: 9 }, "d"))
+    );
+}
+
+#[test]
+fn test_line_range_parse_no_range() {
+    assert_eq!(LineRange::parse("d", 3, 9), None);
+    assert_eq!(LineRange::parse("s/a/b/", 3, 9), None);
+    assert_eq!(LineRange::parse("2,d", 3, 9), None);
+}
+
+#[test]
+fn test_line_range_line_count() {
+    assert_eq!(LineRange { start: 2, end: 2 }.line_count(), 1);
+    assert_eq!(LineRange { start: 0, end: 9 }.line_count(), 10);
+}
@@ -0,0 +1,164 @@
+use crate::{clipboard, utils};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Text yanked or deleted by an operator, and put back in the document by `p` and `P`
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Register {
+    pub text: String,
+    /// Whether the text is made of whole lines, pasted below or above the current line
+    /// instead of being spliced into it.
+    pub linewise: bool,
+}
+
+impl Register {
+    #[must_use]
+    pub fn charwise(text: String) -> Self {
+        Self {
+            text,
+            linewise: false,
+        }
+    }
+
+    #[must_use]
+    pub fn linewise(text: String) -> Self {
+        Self {
+            text,
+            linewise: true,
+        }
+    }
+
+    /// Create a register from the clipboard content, which is made of whole lines when
+    /// it ends with a newline
+    #[must_use]
+    pub fn from_clipboard(text: &str) -> Self {
+        match text.strip_suffix('\n') {
+            Some(lines) => Self::linewise(lines.to_string()),
+            None => Self::charwise(text.to_string()),
+        }
+    }
+
+    /// Return the text as copied into the clipboard, ending with a newline when linewise
+    #[must_use]
+    pub fn to_clipboard(&self) -> String {
+        if self.linewise {
+            format!("{}\n", self.text)
+        } else {
+            self.text.clone()
+        }
+    }
+}
+
+/// Name of the register used when no register is specified
+pub const UNNAMED_REGISTER: char = '"';
+/// Name of the register backed by the system clipboard
+pub const CLIPBOARD_REGISTER: char = '+';
+/// Name of the register holding the last yanked text
+const YANK_REGISTER: char = '0';
+/// Number of registers holding the last deleted texts, named "1" to "9"
+const NUM_DELETE_REGISTERS: u32 = 9;
+/// Maximum number of characters of a register content displayed by `:registers`
+const PREVIEW_LENGTH: usize = 60;
+
+/// All the registers: the unnamed one, used by default, the numbered ones ("0" holding
+/// the last yanked text, and "1" to "9" the last deleted ones) and the named ones
+/// ("a" to "z").
+#[derive(Debug, Default, Serialize)]
+pub struct Registers {
+    contents: BTreeMap<char, Register>,
+    /// Name of the most recently written register
+    last_used: Option<char>,
+}
+
+impl Registers {
+    #[must_use]
+    pub fn is_valid_name(name: char) -> bool {
+        name == UNNAMED_REGISTER
+            || name == CLIPBOARD_REGISTER
+            || name.is_ascii_digit()
+            || name.is_ascii_lowercase()
+    }
+
+    /// Store yanked text into the provided register (the "0" one by default), as well
+    /// as into the unnamed one.
+    pub fn yank(&mut self, name: Option<char>, register: Register) {
+        self.store(name.unwrap_or(YANK_REGISTER), register);
+    }
+
+    /// Store deleted text into the provided register, as well as into the unnamed one.
+    /// By default, the text goes into the "1" register, the previous deletions being
+    /// shifted into the "2" to "9" ones.
+    pub fn delete(&mut self, name: Option<char>, register: Register) {
+        if name.is_none() {
+            for n in (1..NUM_DELETE_REGISTERS).rev() {
+                if let Some(previous) = self.contents.remove(&Self::numbered(n)) {
+                    self.contents.insert(Self::numbered(n + 1), previous);
+                }
+            }
+        }
+        self.store(name.unwrap_or_else(|| Self::numbered(1)), register);
+    }
+
+    /// Return the provided register (the unnamed one by default), if anything was stored in it
+    #[must_use]
+    pub fn get(&self, name: Option<char>) -> Option<&Register> {
+        self.contents.get(&name.unwrap_or(UNNAMED_REGISTER))
+    }
+
+    /// Return the content of the provided register, the clipboard one being read from
+    /// the system clipboard (falling back to the text last stored in it).
+    #[must_use]
+    pub fn read(&self, name: Option<char>) -> Option<Register> {
+        if name == Some(CLIPBOARD_REGISTER) {
+            if let Some(text) = clipboard::get_clipboard() {
+                return Some(Register::from_clipboard(&text));
+            }
+        }
+        self.get(name).cloned()
+    }
+
+    /// Return a table of the non-empty registers and a preview of their content,
+    /// the most recently written register being displayed in bold.
+    #[must_use]
+    pub fn format(&self) -> String {
+        let mut lines = vec![String::from("Type Name Content")];
+        for (name, register) in &self.contents {
+            let mut preview: String = register
+                .text
+                .replace('\n', "^J")
+                .chars()
+                .take(PREVIEW_LENGTH)
+                .collect();
+            if register.linewise {
+                preview.push_str("^J");
+            }
+            let register_type = if register.linewise { 'l' } else { 'c' };
+            let line = format!("  {register_type}  \"{name}   {preview}");
+            if self.last_used == Some(*name) {
+                lines.push(utils::as_bold(&line));
+            } else {
+                lines.push(line);
+            }
+        }
+        lines.join("\n")
+    }
+
+    fn store(&mut self, name: char, register: Register) {
+        if name == CLIPBOARD_REGISTER {
+            clipboard::set_clipboard(&register.to_clipboard());
+        }
+        if name != UNNAMED_REGISTER {
+            self.contents.insert(name, register.clone());
+        }
+        self.contents.insert(UNNAMED_REGISTER, register);
+        self.last_used = Some(name);
+    }
+
+    fn numbered(n: u32) -> char {
+        char::from_digit(n, 10).unwrap_or(YANK_REGISTER)
+    }
+}
+
+#[cfg(test)]
+#[path = "./register_test.rs"]
+mod register_test;
@@ -0,0 +1,72 @@
+use crate::{utils, Register, Registers};
+
+#[test]
+fn test_registers_yank_and_delete() {
+    let mut registers = Registers::default();
+    assert_eq!(registers.get(None), None);
+    registers.yank(None, Register::linewise(String::from("line")));
+    assert_eq!(registers.get(Some('0')), registers.get(None));
+
+    registers.delete(None, Register::charwise(String::from("first")));
+    registers.delete(None, Register::charwise(String::from("second")));
+    assert_eq!(registers.get(None).unwrap().text, "second");
+    assert_eq!(registers.get(Some('1')).unwrap().text, "second");
+    assert_eq!(registers.get(Some('2')).unwrap().text, "first");
+    assert_eq!(registers.get(Some('0')).unwrap().text, "line");
+
+    // named registers don't shift the numbered ones
+    registers.delete(Some('a'), Register::charwise(String::from("named")));
+    assert_eq!(registers.get(None).unwrap().text, "named");
+    assert_eq!(registers.get(Some('a')).unwrap().text, "named");
+    assert_eq!(registers.get(Some('1')).unwrap().text, "second");
+}
+
+#[test]
+fn test_registers_only_keep_nine_deletions() {
+    let mut registers = Registers::default();
+    for n in 0..12 {
+        registers.delete(None, Register::charwise(n.to_string()));
+    }
+    assert_eq!(registers.get(Some('1')).unwrap().text, "11");
+    assert_eq!(registers.get(Some('9')).unwrap().text, "3");
+    assert_eq!(registers.get(Some('0')), None);
+}
+
+#[test]
+fn test_registers_format() {
+    let mut registers = Registers::default();
+    registers.yank(None, Register::linewise(String::from("Hello\nworld")));
+    registers.yank(Some('a'), Register::charwise("x".repeat(100)));
+    assert_eq!(
+        registers.format(),
+        format!(
+            "Type Name Content\n  c  \"\"   {}\n  l  \"0   Hello^Jworld^J\n{}",
+            "x".repeat(60),
+            utils::as_bold(&format!("  c  \"a   {}", "x".repeat(60)))
+        )
+    );
+}
+
+#[test]
+fn test_registers_valid_names() {
+    assert!(Registers::is_valid_name('"'));
+    assert!(Registers::is_valid_name('4'));
+    assert!(Registers::is_valid_name('q'));
+    assert!(!Registers::is_valid_name('Q'));
+    assert!(Registers::is_valid_name('+'));
+    assert!(!Registers::is_valid_name('*'));
+}
+
+#[test]
+fn test_register_clipboard_text() {
+    assert_eq!(
+        Register::from_clipboard("a\nb"),
+        Register::charwise(String::from("a\nb"))
+    );
+    assert_eq!(
+        Register::from_clipboard("a\nb\n"),
+        Register::linewise(String::from("a\nb"))
+    );
+    assert_eq!(Register::linewise(String::from("a")).to_clipboard(), "a\n");
+    assert_eq!(Register::charwise(String::from("a")).to_clipboard(), "a");
+}
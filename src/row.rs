@@ -1,38 +1,261 @@
-use crate::utils;
+use crate::{utils, Span};
+use regex::Regex;
 use serde::Serialize;
 use std::cmp;
-use std::hash::Hash;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
 use std::str;
+use termion::color;
 use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-#[derive(Debug, Default, Hash, Serialize)]
+/// Color of the glyphs standing for the tabs and trailing spaces, with `:set list`
+const INVISIBLE_FG_COLOR: color::Rgb = color::Rgb(100, 100, 100);
+const TAB_GLYPH: char = '→';
+const TRAILING_SPACE_GLYPH: char = '·';
+
+#[derive(Debug, Serialize)]
 pub struct Row {
+    /// The row content. It should only be mutated through the `Row` methods,
+    /// to keep the cached properties of the row up to date.
     pub string: String,
+    #[serde(skip)]
+    is_ascii: bool,
+    #[serde(skip)]
+    num_words: usize,
 }
 
 impl From<&str> for Row {
     fn from(s: &str) -> Self {
-        Self {
+        let mut row = Self {
             string: String::from(s),
-        }
+            is_ascii: true,
+            num_words: 0,
+        };
+        row.update_cached_properties();
+        row
+    }
+}
+
+impl Default for Row {
+    fn default() -> Self {
+        Self::from("")
+    }
+}
+
+impl Hash for Row {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.string.hash(state);
     }
 }
 
 impl Row {
     #[must_use]
-    pub fn render(&self, start: usize, end: usize, line_number: usize, x_offset: usize) -> String {
-        let end = cmp::min(end, self.string.len()); // either stop at terminal end or string end
-        let start = cmp::min(start, end);
+    pub fn render(
+        &self,
+        start: usize,
+        end: usize,
+        line_number: usize,
+        x_offset: usize,
+        tab_width: usize,
+    ) -> String {
+        self.render_with_highlight(
+            start,
+            end,
+            line_number,
+            x_offset,
+            tab_width,
+            None,
+            &[],
+            false,
+        )
+    }
+
+    /// Render the row, while setting the background color of the graphemes located
+    /// in the provided index ranges (relative to the start of the row). Literal tabs
+    /// are expanded to the next multiple of the tab width. The syntax spans set the
+    /// foreground color of their graphemes, including the ones starting before `start`.
+    /// With `list`, the tabs and trailing spaces are displayed as dimmed glyphs, taking
+    /// the same number of columns.
+    #[must_use]
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_with_highlight(
+        &self,
+        start: usize,
+        end: usize,
+        line_number: usize,
+        x_offset: usize,
+        tab_width: usize,
+        highlight: Option<(&[Range<usize>], color::Rgb)>,
+        syntax: &[Span],
+        list: bool,
+    ) -> String {
+        let mut rendered = String::with_capacity(x_offset + 1 + end.saturating_sub(start));
+        if x_offset > 0 {
+            rendered.push_str(&utils::zfill(&line_number.to_string(), " ", x_offset));
+            rendered.push(' ');
+        }
+        if self.is_ascii
+            && highlight.is_none()
+            && syntax.is_empty()
+            && !self.string.contains('\t')
+            && !(list && self.string.ends_with(' '))
+        {
+            // Fast path: each byte of an ASCII string is a grapheme, so we can
+            // directly copy the visible slice of the string.
+            let end = cmp::min(end, self.string.len()); // either stop at terminal end or string end
+            let start = cmp::min(start, end);
+            rendered.push_str(&self.string[start..end]);
+        } else {
+            rendered.push_str(&self.render_graphemes(
+                start,
+                end.saturating_sub(start),
+                tab_width,
+                highlight,
+                syntax,
+                list,
+            ));
+        }
+        rendered
+    }
+
+    /// Render the graphemes located after the start index, fitting in the provided
+    /// number of columns
+    fn render_graphemes(
+        &self,
+        start: usize,
+        width: usize,
+        tab_width: usize,
+        highlight: Option<(&[Range<usize>], color::Rgb)>,
+        syntax: &[Span],
+        list: bool,
+    ) -> String {
         let mut visible = String::new();
-        for grapheme in self.graphemes().skip(start).take(end - start) {
-            visible.push_str(grapheme);
+        let trailing_whitespace_start = self.string.trim_end().graphemes(true).count();
+        let mut highlighting = false;
+        let mut fg_color: Option<color::Rgb> = None;
+        let mut column = self.display_column(start, tab_width);
+        let last_column = column.saturating_add(width);
+        for (index, grapheme) in self.graphemes().enumerate().skip(start) {
+            if column >= last_column {
+                break;
+            }
+            if let Some((ranges, bg_color)) = highlight {
+                let highlighted = ranges.iter().any(|range| range.contains(&index));
+                if highlighted && !highlighting {
+                    visible.push_str(&color::Bg(bg_color).to_string());
+                    highlighting = true;
+                } else if !highlighted && highlighting {
+                    visible.push_str(&color::Bg(color::Reset).to_string());
+                    highlighting = false;
+                }
+            }
+            let invisible = list
+                && (grapheme == "\t" || (grapheme == " " && index >= trailing_whitespace_start));
+            let grapheme_color = if invisible {
+                Some(INVISIBLE_FG_COLOR)
+            } else {
+                syntax
+                    .iter()
+                    .find(|span| span.range.contains(&index))
+                    .map(|span| span.style.to_color())
+            };
+            if grapheme_color != fg_color {
+                match grapheme_color {
+                    Some(fg) => visible.push_str(&color::Fg(fg).to_string()),
+                    None => visible.push_str(&color::Fg(color::Reset).to_string()),
+                }
+                fg_color = grapheme_color;
+            }
+            let grapheme_width = Self::grapheme_width(grapheme, column, tab_width);
+            if grapheme != "\t" && column.saturating_add(grapheme_width) > last_column {
+                // a wide character that doesn't fit is replaced by padding
+                visible.push_str(&" ".repeat(last_column - column));
+                break;
+            }
+            if grapheme == "\t" {
+                let visible_width = cmp::min(grapheme_width, last_column - column);
+                if list && visible_width > 0 {
+                    visible.push(TAB_GLYPH);
+                    visible.push_str(&" ".repeat(visible_width - 1));
+                } else {
+                    visible.push_str(&" ".repeat(visible_width));
+                }
+            } else if invisible {
+                visible.push(TRAILING_SPACE_GLYPH);
+            } else {
+                visible.push_str(grapheme);
+            }
+            column = column.saturating_add(grapheme_width);
+        }
+        if highlighting {
+            visible.push_str(&color::Bg(color::Reset).to_string());
+        }
+        if fg_color.is_some() {
+            visible.push_str(&color::Fg(color::Reset).to_string());
+        }
+        visible
+    }
+
+    /// Return the whitespace located at the start of the row
+    #[must_use]
+    pub fn leading_whitespace(&self) -> &str {
+        let indentation_length = self.string.len() - self.string.trim_start().len();
+        &self.string[..indentation_length]
+    }
+
+    /// Return the column at which the grapheme located at the provided index is displayed
+    #[must_use]
+    pub fn display_column(&self, index: usize, tab_width: usize) -> usize {
+        self.graphemes().take(index).fold(0, |column, grapheme| {
+            column.saturating_add(Self::grapheme_width(grapheme, column, tab_width))
+        })
+    }
+
+    /// Return the index of the grapheme displayed at the provided column (or of the first
+    /// one displayed after it), the length of the row if it's displayed before it.
+    #[must_use]
+    pub fn index_at_display_column(&self, column: usize, tab_width: usize) -> usize {
+        let mut current_column: usize = 0;
+        for (index, grapheme) in self.graphemes().enumerate() {
+            current_column = current_column.saturating_add(Self::grapheme_width(
+                grapheme,
+                current_column,
+                tab_width,
+            ));
+            if current_column > column {
+                return index;
+            }
         }
-        let prefix = if x_offset == 0 {
-            "".to_string()
+        self.len()
+    }
+
+    /// Return the number of columns the row is displayed on
+    #[must_use]
+    pub fn width(&self, tab_width: usize) -> usize {
+        self.display_column(self.len(), tab_width)
+    }
+
+    /// Return the number of columns taken by the grapheme located at the provided index,
+    /// 0 past the end of the row
+    #[must_use]
+    pub fn grapheme_width_at(&self, index: usize, tab_width: usize) -> usize {
+        if index >= self.len() {
+            return 0;
+        }
+        let column = self.display_column(index, tab_width);
+        Self::grapheme_width(self.nth_grapheme(index), column, tab_width)
+    }
+
+    /// Return the number of columns taken by the grapheme displayed at the provided column:
+    /// a literal tab spans until the next multiple of the tab width, and the wide characters
+    /// (eg: CJK ideographs, most emojis) over 2 columns.
+    fn grapheme_width(grapheme: &str, column: usize, tab_width: usize) -> usize {
+        if grapheme == "\t" {
+            tab_width.saturating_sub(column % cmp::max(tab_width, 1))
         } else {
-            format!("{} ", utils::zfill(&line_number.to_string(), " ", x_offset))
-        };
-        format!("{}{}", prefix, visible)
+            cmp::max(grapheme.width(), 1)
+        }
     }
 
     pub fn chars(&self) -> std::str::Chars {
@@ -71,7 +294,7 @@ impl Row {
 
     #[must_use]
     pub fn num_words(&self) -> usize {
-        self.string.unicode_words().count()
+        self.num_words
     }
 
     #[must_use]
@@ -84,13 +307,53 @@ impl Row {
         self.string.find(pattern)
     }
 
+    /// Return the start (inclusive) and end (exclusive) grapheme indices of all the
+    /// non-overlapping matches of the provided regex in the row.
+    #[must_use]
+    pub fn find_all(&self, regex: &Regex) -> Vec<(usize, usize)> {
+        regex
+            .find_iter(&self.string)
+            .map(|m| (self.grapheme_index(m.start()), self.grapheme_index(m.end())))
+            .collect()
+    }
+
+    /// Return the index of the grapheme starting at the provided byte index
+    fn grapheme_index(&self, byte_index: usize) -> usize {
+        if self.is_ascii {
+            return byte_index;
+        }
+        self.string
+            .grapheme_indices(true)
+            .take_while(|(index, _)| *index < byte_index)
+            .count()
+    }
+
     #[must_use]
     pub fn as_bytes(&self) -> &[u8] {
         self.string.as_bytes()
     }
 
+    /// Recompute the properties derived from the row content, after a mutation
+    fn update_cached_properties(&mut self) {
+        self.is_ascii = self.string.is_ascii();
+        // a word is a run of non whitespace characters, hyphens and apostrophes included,
+        // with at least a letter or a digit (eg: a lone `-` isn't one)
+        self.num_words = self
+            .string
+            .split_whitespace()
+            .filter(|token| token.chars().any(char::is_alphanumeric))
+            .count();
+    }
+
+    /// Replace the content of the row
+    pub fn set_string(&mut self, s: &str) {
+        self.string = String::from(s);
+        self.update_cached_properties();
+    }
+
     pub fn trim_end_inplace(&mut self) {
-        self.string = String::from(self.string.trim_end());
+        let trimmed = self.string.trim_end().to_string();
+        self.set_string(&trimmed);
     }
 
     /// Insert a character in the provided x index
@@ -105,6 +368,24 @@ impl Row {
             before.push_str(&after);
             self.string = before;
         }
+        self.update_cached_properties();
+    }
+
+    /// Replace the grapheme located at the provided index, and return it. The grapheme
+    /// is appended at the end of the row if the index is out of its boundaries.
+    pub fn replace(&mut self, index: usize, grapheme: &str) -> Option<String> {
+        if index >= self.len() {
+            self.string.push_str(grapheme);
+            self.update_cached_properties();
+            return None;
+        }
+        let replaced = self.nth_grapheme(index).to_string();
+        let mut before: String = self.graphemes().take(index).collect();
+        let after: String = self.graphemes().skip(index.saturating_add(1)).collect();
+        before.push_str(grapheme);
+        before.push_str(&after);
+        self.set_string(&before);
+        Some(replaced)
     }
 
     /// Delete the character located at provided index
@@ -115,19 +396,20 @@ impl Row {
         let mut before: String = self.graphemes().take(index).collect();
         let after: String = self.graphemes().skip(index.saturating_add(1)).collect();
         before.push_str(&after);
-        self.string = before;
+        self.set_string(&before);
     }
 
     /// Append a string at the end of the current one
     pub fn append(&mut self, other: &Self) {
-        self.string = format!("{}{}", self.string, other.string);
+        self.string.push_str(&other.string);
+        self.update_cached_properties();
     }
 
     #[must_use]
     pub fn split(&mut self, at: usize) -> Self {
         let before: String = self.graphemes().take(at).collect();
         let after: String = self.graphemes().skip(at).collect();
-        self.string = before;
+        self.set_string(&before);
         Self::from(&after[..])
     }
 }
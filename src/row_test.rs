@@ -1,13 +1,187 @@
-use crate::Row;
+use crate::{Row, Span, Style};
+use regex::Regex;
+use std::slice;
+use std::time::Instant;
+use termion::color;
 
 #[test]
 fn test_row_render() {
-    // fn render(&self, start: usize, end: usize, line_number: usize, x_offset: usize)
-    assert_eq!(Row::from("Test").render(0, 50, 1, 0), "Test");
-    assert_eq!(Row::from("Test").render(0, 50, 1, 4), "   1 Test");
-    assert_eq!(Row::from("Test").render(0, 50, 11, 4), "  11 Test");
-    assert_eq!(Row::from("Test").render(10, 60, 11, 4), "  11 ");
-    assert_eq!(Row::from("\u{2764}").render(0, 50, 11, 4), "  11 \u{2764}");
+    // fn render(&self, start: usize, end: usize, line_number: usize, x_offset: usize, tab_width: usize)
+    assert_eq!(Row::from("Test").render(0, 50, 1, 0, 4), "Test");
+    assert_eq!(Row::from("Test").render(0, 50, 1, 4, 4), "   1 Test");
+    assert_eq!(Row::from("Test").render(0, 50, 11, 4, 4), "  11 Test");
+    assert_eq!(Row::from("Test").render(10, 60, 11, 4, 4), "  11 ");
+    assert_eq!(
+        Row::from("\u{2764}").render(0, 50, 11, 4, 4),
+        "  11 \u{2764}"
+    );
+}
+
+#[test]
+fn test_row_render_tabs() {
+    let row = Row::from("\tab\tc");
+    assert_eq!(row.render(0, 50, 1, 0, 4), "    ab  c");
+    assert_eq!(row.render(0, 50, 1, 0, 2), "  ab  c");
+    // the tab stops don't depend on the first rendered grapheme
+    assert_eq!(row.render(2, 50, 1, 0, 4), "b  c");
+    // the rendered tabs fit in the visible columns
+    assert_eq!(row.render(0, 6, 1, 0, 8), " ".repeat(6));
+    assert_eq!(row.render(0, 6, 1, 4, 8), format!("   1 {}", " ".repeat(6)));
+}
+
+#[test]
+fn test_row_wide_characters() {
+    let row = Row::from("a\u{3042}\u{3044}b");
+    assert_eq!(row.width(4), 6);
+    assert_eq!(row.grapheme_width_at(0, 4), 1);
+    assert_eq!(row.grapheme_width_at(1, 4), 2);
+    assert_eq!(row.grapheme_width_at(4, 4), 0);
+    assert_eq!(row.display_column(2, 4), 3);
+    assert_eq!(row.index_at_display_column(2, 4), 1);
+    assert_eq!(row.index_at_display_column(3, 4), 2);
+    assert_eq!(Row::from("\tb").grapheme_width_at(0, 4), 4);
+
+    assert_eq!(row.render(0, 50, 1, 0, 4), "a\u{3042}\u{3044}b");
+    // a wide character that doesn't fit in the visible columns is replaced by padding
+    assert_eq!(row.render(0, 4, 1, 0, 4), "a\u{3042} ");
+    assert_eq!(row.render(1, 5, 1, 0, 4), "\u{3042}\u{3044}");
+}
+
+#[test]
+fn test_row_leading_whitespace() {
+    assert_eq!(Row::from("    Hello").leading_whitespace(), "    ");
+    assert_eq!(Row::from("\t Hello ").leading_whitespace(), "\t ");
+    assert_eq!(Row::from("Hello").leading_whitespace(), "");
+    assert_eq!(Row::from("   ").leading_whitespace(), "   ");
+}
+
+#[test]
+fn test_row_display_column() {
+    let row = Row::from("\tab\tc");
+    assert_eq!(row.display_column(0, 4), 0);
+    assert_eq!(row.display_column(1, 4), 4);
+    assert_eq!(row.display_column(3, 4), 6);
+    assert_eq!(row.display_column(4, 4), 8);
+    assert_eq!(row.display_column(4, 8), 16);
+    assert_eq!(row.display_column(10, 4), 9);
+    assert_eq!(Row::from("abc").display_column(2, 4), 2);
+}
+
+#[test]
+fn test_row_index_at_display_column() {
+    let row = Row::from("\tab\tc");
+    assert_eq!(row.index_at_display_column(0, 4), 0);
+    assert_eq!(row.index_at_display_column(3, 4), 0);
+    assert_eq!(row.index_at_display_column(4, 4), 1);
+    assert_eq!(row.index_at_display_column(7, 4), 3);
+    assert_eq!(row.index_at_display_column(8, 4), 4);
+    assert_eq!(row.index_at_display_column(9, 4), 5);
+    assert_eq!(Row::from("abc").index_at_display_column(80, 4), 3);
+}
+
+#[test]
+fn test_row_render_with_highlight() {
+    let bg = color::Rgb(1, 2, 3);
+    assert_eq!(
+        Row::from("Test").render_with_highlight(
+            0,
+            50,
+            1,
+            0,
+            4,
+            Some((slice::from_ref(&(1..3)), bg)),
+            &[],
+            false
+        ),
+        format!("T{}es{}t", color::Bg(bg), color::Bg(color::Reset))
+    );
+    assert_eq!(
+        Row::from("Test").render_with_highlight(
+            0,
+            50,
+            1,
+            0,
+            4,
+            Some((slice::from_ref(&(2..10)), bg)),
+            &[],
+            false
+        ),
+        format!("Te{}st{}", color::Bg(bg), color::Bg(color::Reset))
+    );
+    assert_eq!(
+        Row::from("Test").render_with_highlight(0, 50, 1, 0, 4, None, &[], false),
+        "Test"
+    );
+    assert_eq!(
+        Row::from("Test").render_with_highlight(
+            0,
+            50,
+            1,
+            0,
+            4,
+            Some((&[0..1, 2..3][..], bg)),
+            &[],
+            false
+        ),
+        format!(
+            "{}T{}e{}s{}t",
+            color::Bg(bg),
+            color::Bg(color::Reset),
+            color::Bg(bg),
+            color::Bg(color::Reset)
+        )
+    );
+}
+
+#[test]
+fn test_row_render_with_syntax_spans() {
+    let row = Row::from("let x");
+    let keyword = Span {
+        range: 0..3,
+        style: Style::Keyword,
+    };
+    let fg = color::Fg(Style::Keyword.to_color());
+    assert_eq!(
+        row.render_with_highlight(0, 50, 1, 0, 4, None, slice::from_ref(&keyword), false),
+        format!("{}let{} x", fg, color::Fg(color::Reset))
+    );
+    // the span is clipped to the visible part of the row
+    assert_eq!(
+        row.render_with_highlight(1, 50, 1, 0, 4, None, slice::from_ref(&keyword), false),
+        format!("{}et{} x", fg, color::Fg(color::Reset))
+    );
+    assert_eq!(
+        row.render_with_highlight(0, 2, 1, 0, 4, None, slice::from_ref(&keyword), false),
+        format!("{}le{}", fg, color::Fg(color::Reset))
+    );
+    assert_eq!(
+        row.render_with_highlight(3, 50, 1, 0, 4, None, slice::from_ref(&keyword), false),
+        " x"
+    );
+}
+
+#[test]
+fn test_row_render_invisible_characters() {
+    let dim = color::Fg(color::Rgb(100, 100, 100));
+    let reset = color::Fg(color::Reset);
+    let row = Row::from("\tab\tc  ");
+    assert_eq!(
+        row.render_with_highlight(0, 50, 1, 0, 4, None, &[], true),
+        format!("{dim}→   {reset}ab{dim}→ {reset}c{dim}··{reset}")
+    );
+    // the glyphs take as many columns as the whitespace they stand for
+    assert_eq!(
+        row.render_with_highlight(2, 6, 1, 0, 4, None, &[], true),
+        format!("b{dim}→ {reset}c")
+    );
+    assert_eq!(
+        Row::from("a b ").render_with_highlight(0, 50, 1, 0, 4, None, &[], true),
+        format!("a b{dim}·{reset}")
+    );
+    assert_eq!(
+        Row::from("a b ").render_with_highlight(0, 50, 1, 0, 4, None, &[], false),
+        "a b "
+    );
 }
 
 #[test]
@@ -97,6 +271,17 @@ fn test_row_insert() {
     assert_eq!(row.string, ".Helloo");
 }
 
+#[test]
+fn test_row_replace() {
+    let mut row = Row::from("Hellö");
+    assert_eq!(row.replace(4, "o"), Some(String::from("ö")));
+    assert_eq!(row.string, "Hello");
+    assert_eq!(row.replace(0, "J"), Some(String::from("H")));
+    assert_eq!(row.string, "Jello");
+    assert_eq!(row.replace(5, "w"), None);
+    assert_eq!(row.string, "Jellow");
+}
+
 #[test]
 fn test_row_delete() {
     let mut row = Row::from("Hello!");
@@ -123,3 +308,83 @@ fn test_row_split() {
     assert_eq!(row1.string, "Hello");
     assert_eq!(row2.string, " world!");
 }
+
+#[test]
+fn test_row_is_ascii_tracks_mutations() {
+    let mut row = Row::from("Hello");
+    assert!(row.is_ascii);
+    row.insert(5, 'é');
+    assert!(!row.is_ascii);
+    row.delete(5);
+    assert!(row.is_ascii);
+    row.append(&Row::from("wörld"));
+    assert!(!row.is_ascii);
+    let rest = row.split(5);
+    assert!(row.is_ascii);
+    assert!(!rest.is_ascii);
+    assert!(Row::default().is_ascii);
+}
+
+#[test]
+fn test_row_render_ascii_and_unicode_rows_identically() {
+    let ascii = Row::from("Hello world");
+    let mut unicode = Row::from("Hello world");
+    unicode.is_ascii = false; // force the grapheme-based rendering
+    for (start, end) in &[(0, 5), (3, 11), (6, 100), (20, 30)] {
+        assert_eq!(
+            ascii.render(*start, *end, 7, 4, 4),
+            unicode.render(*start, *end, 7, 4, 4)
+        );
+    }
+}
+
+#[test]
+fn test_row_find_all() {
+    let regex = Regex::new("o.").unwrap();
+    assert_eq!(
+        Row::from("foo bar boz").find_all(&regex),
+        vec![(1, 3), (9, 11)]
+    );
+    // the indices are expressed in graphemes, not bytes
+    assert_eq!(Row::from("éèo!").find_all(&regex), vec![(2, 4)]);
+    assert!(Row::from("").find_all(&regex).is_empty());
+}
+
+/// Time the rendering of a screen of 80 rows of 200 ASCII characters, with the ASCII
+/// fast path and by iterating over the graphemes. Run it with
+/// `cargo test --release bench_ -- --ignored --nocapture`
+#[test]
+#[ignore = "timing benchmark, run it in release mode"]
+fn bench_row_render_ascii_fast_path() {
+    let rows: Vec<Row> = (0..80)
+        .map(|_| Row::from("a".repeat(200).as_str()))
+        .collect();
+    let mut unicode_rows: Vec<Row> = (0..80)
+        .map(|_| Row::from("a".repeat(200).as_str()))
+        .collect();
+    for row in &mut unicode_rows {
+        row.is_ascii = false; // force the grapheme-based rendering
+    }
+    let iterations: u32 = 1000;
+    let mut rendered_len = 0;
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        for row in &rows {
+            rendered_len += row.render(0, 200, 1, 0, 4).len();
+        }
+    }
+    let fast_path = start.elapsed() / iterations;
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        for row in &unicode_rows {
+            rendered_len += row.render(0, 200, 1, 0, 4).len();
+        }
+    }
+    let graphemes = start.elapsed() / iterations;
+
+    eprintln!("80 rows of 200 columns: {fast_path:?} (fast path), {graphemes:?} (graphemes)");
+    assert_eq!(rendered_len, 2 * 80 * 200 * iterations as usize);
+    assert!(fast_path < graphemes);
+}
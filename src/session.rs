@@ -0,0 +1,65 @@
+use crate::utils;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+pub const SESSIONS_DIRECTORY: &str = "~/.bo/sessions";
+
+/// An opened file, and where the cursor was in it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionBuffer {
+    pub filename: PathBuf,
+    /// Index of the row the cursor was on
+    pub line: usize,
+    /// Index of the grapheme the cursor was on
+    pub column: usize,
+}
+
+/// The unfocused pane of a split screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionPane {
+    /// Index of the displayed buffer in the session buffer list
+    pub buffer: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The opened buffers and the screen layout, saved with `:mksession` to be restored
+/// later on with `:source-session` or `bo -S`.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Session {
+    pub buffers: Vec<SessionBuffer>,
+    /// Index of the buffer displayed in the focused pane
+    pub current_buffer: usize,
+    pub split: Option<SessionPane>,
+    pub top_pane_focused: bool,
+}
+
+impl Session {
+    /// Return the path of the session file with the provided name
+    #[must_use]
+    pub fn path(name: &str) -> PathBuf {
+        PathBuf::from(utils::expand_tilde(SESSIONS_DIRECTORY)).join(name)
+    }
+
+    /// # Errors
+    /// Returns an error if the session file can't be read, or isn't a valid session.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// # Errors
+    /// Returns an error if the session file (or its parent directory) can't be written to.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+#[path = "./session_test.rs"]
+mod session_test;
@@ -0,0 +1,43 @@
+use crate::{Session, SessionBuffer, SessionPane};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+#[test]
+fn test_session_path() {
+    assert!(Session::path("work").ends_with(".bo/sessions/work"));
+}
+
+#[test]
+fn test_session_save_and_load() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("sessions").join("work");
+    assert!(Session::load(&path).is_err());
+
+    let session = Session {
+        buffers: vec![
+            SessionBuffer {
+                filename: PathBuf::from("/tmp/a.rs"),
+                line: 12,
+                column: 4,
+            },
+            SessionBuffer {
+                filename: PathBuf::from("/tmp/b.rs"),
+                line: 0,
+                column: 0,
+            },
+        ],
+        current_buffer: 1,
+        split: Some(SessionPane {
+            buffer: 0,
+            line: 3,
+            column: 1,
+        }),
+        top_pane_focused: false,
+    };
+    session.save(&path).unwrap();
+    assert_eq!(Session::load(&path).unwrap(), session);
+
+    fs::write(&path, "not json").unwrap();
+    assert!(Session::load(&path).is_err());
+}
@@ -0,0 +1,54 @@
+/// A `:s/pattern/replacement/flags` command, replacing the matches of the pattern
+/// on the current line, or in the lines of its range (eg: the whole document with `:%s`).
+#[derive(Debug, PartialEq)]
+pub struct Substitution {
+    pub pattern: String,
+    pub replacement: String,
+    /// Replace every match of a line (`g` flag), and not only the first one
+    pub global: bool,
+}
+
+impl Substitution {
+    /// Parse a substitute command (without its leading `:` and range), in which the
+    /// slashes can be escaped with a backslash. Return `None` if the command isn't one.
+    #[must_use]
+    pub fn parse(command: &str) -> Option<Self> {
+        let parts = Self::split_on_slashes(command.strip_prefix("s/")?);
+        let (pattern, replacement, flags) = match parts.as_slice() {
+            [pattern, replacement] => (pattern, replacement, ""),
+            [pattern, replacement, flags] => (pattern, replacement, flags.as_str()),
+            _ => return None,
+        };
+        if pattern.is_empty() || !flags.chars().all(|flag| flag == 'g') {
+            return None;
+        }
+        Some(Self {
+            pattern: pattern.clone(),
+            replacement: replacement.clone(),
+            global: !flags.is_empty(),
+        })
+    }
+
+    /// Split the text on the slashes not preceded by a backslash, unescaping them
+    fn split_on_slashes(text: &str) -> Vec<String> {
+        let mut parts = vec![];
+        let mut current = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&'/') => {
+                    current.push('/');
+                    chars.next();
+                }
+                '/' => parts.push(std::mem::take(&mut current)),
+                _ => current.push(c),
+            }
+        }
+        parts.push(current);
+        parts
+    }
+}
+
+#[cfg(test)]
+#[path = "./substitution_test.rs"]
+mod substitution_test;
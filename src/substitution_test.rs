@@ -0,0 +1,54 @@
+use crate::Substitution;
+
+#[test]
+fn test_substitution_parse() {
+    assert_eq!(
+        Substitution::parse("s/old/new/"),
+        Some(Substitution {
+            pattern: String::from("old"),
+            replacement: String::from("new"),
+            global: false,
+        })
+    );
+    assert_eq!(
+        Substitution::parse("s/old/new/g"),
+        Some(Substitution {
+            pattern: String::from("old"),
+            replacement: String::from("new"),
+            global: true,
+        })
+    );
+    // the trailing slash is optional, and the replacement can be empty
+    assert_eq!(Substitution::parse("s/old"), None);
+    assert_eq!(
+        Substitution::parse("s/old/").map(|s| s.replacement),
+        Some(String::new())
+    );
+    assert_eq!(
+        Substitution::parse("s/old/new").map(|s| s.replacement),
+        Some(String::from("new"))
+    );
+}
+
+#[test]
+fn test_substitution_parse_escaped_slashes() {
+    let substitution = Substitution::parse(r"s/a\/b/c\/d\//g").unwrap();
+    assert_eq!(substitution.pattern, "a/b");
+    assert_eq!(substitution.replacement, "c/d/");
+    assert!(substitution.global);
+    // other escaped characters are kept as is, for the regex
+    assert_eq!(
+        Substitution::parse(r"s/\d+/n/").map(|s| s.pattern),
+        Some(String::from(r"\d+"))
+    );
+}
+
+#[test]
+fn test_substitution_parse_invalid_commands() {
+    assert_eq!(Substitution::parse("set ic"), None);
+    // the range is parsed separately
+    assert_eq!(Substitution::parse("%s/old/new/"), None);
+    assert_eq!(Substitution::parse("s//new/"), None);
+    assert_eq!(Substitution::parse("s/old/new/x"), None);
+    assert_eq!(Substitution::parse("s/old/new/g/"), None);
+}
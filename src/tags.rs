@@ -0,0 +1,149 @@
+use crate::Document;
+use std::fs;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+
+/// Tags file generated by ctags, looked up from a directory up to its project root
+pub const TAGS_FILE: &str = "tags";
+/// Directory marking the root of a project, above which no tags file is looked up
+const PROJECT_ROOT_MARKER: &str = ".git";
+
+/// How the line defining a tag is found in its file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagAddress {
+    /// Line number, starting at 1
+    Line(usize),
+    /// Text of the line, which can be anchored to its start (`^`) or end (`$`)
+    Pattern {
+        text: String,
+        anchored_start: bool,
+        anchored_end: bool,
+    },
+}
+
+/// A definition (eg: a function) listed in a tags file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub name: String,
+    pub filename: PathBuf,
+    pub address: TagAddress,
+}
+
+impl Tag {
+    /// Parse a tags file in the ctags format (`name<Tab>file<Tab>address;"<Tab>fields`).
+    /// The file names are relative to the provided directory, the one of the tags file.
+    /// The metadata (`!_TAG_` lines) and malformed lines are skipped.
+    #[must_use]
+    pub fn parse(contents: &str, directory: &Path) -> Vec<Self> {
+        contents
+            .lines()
+            .filter(|line| !line.starts_with("!_TAG_"))
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let name = fields.next().filter(|name| !name.is_empty())?;
+                let filename = fields.next()?;
+                let address = fields.next()?;
+                // the extension fields follow the address, after a `;"` separator
+                let address = address.split(";\"\t").next().unwrap_or(address);
+                let address = address.strip_suffix(";\"").unwrap_or(address);
+                Some(Self {
+                    name: name.to_string(),
+                    filename: directory.join(filename),
+                    address: Self::parse_address(address)?,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_address(address: &str) -> Option<TagAddress> {
+        if let Ok(line_number) = address.parse() {
+            return Some(TagAddress::Line(line_number));
+        }
+        let pattern = address
+            .strip_prefix('/')
+            .and_then(|pattern| pattern.strip_suffix('/'))
+            .or_else(|| {
+                address
+                    .strip_prefix('?')
+                    .and_then(|pattern| pattern.strip_suffix('?'))
+            })?;
+        let (anchored_start, pattern) = match pattern.strip_prefix('^') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let (anchored_end, pattern) = match pattern.strip_suffix('$') {
+            Some(pattern) if !pattern.ends_with('\\') => (true, pattern),
+            _ => (false, pattern),
+        };
+        // only the delimiters and backslashes are escaped in the patterns
+        let mut text = String::with_capacity(pattern.len());
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                text.extend(chars.next());
+            } else {
+                text.push(c);
+            }
+        }
+        Some(TagAddress::Pattern {
+            text,
+            anchored_start,
+            anchored_end,
+        })
+    }
+
+    /// Return the path of the tags file located in the provided directory or in one of
+    /// its parents, the search stopping at the root of the project (the directory
+    /// containing `.git`).
+    #[must_use]
+    pub fn find_file(directory: &Path) -> Option<PathBuf> {
+        for ancestor in directory.ancestors() {
+            let path = ancestor.join(TAGS_FILE);
+            if path.is_file() {
+                return Some(path);
+            }
+            if ancestor.join(PROJECT_ROOT_MARKER).exists() {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Load the tags of the provided tags file
+    ///
+    /// # Errors
+    /// Returns an error if the tags file can't be read.
+    pub fn load(path: &Path) -> Result<Vec<Self>, Error> {
+        let contents = fs::read_to_string(path)?;
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        Ok(Self::parse(&contents, directory))
+    }
+
+    /// Return the index of the row defining the tag in the document, if found
+    #[must_use]
+    pub fn find_row_index(&self, document: &Document) -> Option<usize> {
+        match &self.address {
+            TagAddress::Line(line_number) => {
+                let row_index = line_number.saturating_sub(1);
+                (row_index < document.line_count()).then_some(row_index)
+            }
+            TagAddress::Pattern {
+                text,
+                anchored_start,
+                anchored_end,
+            } => document.iter().position(|row| {
+                let line = row.string.as_str();
+                match (anchored_start, anchored_end) {
+                    (true, true) => line == text,
+                    (true, false) => line.starts_with(text.as_str()),
+                    (false, true) => line.ends_with(text.as_str()),
+                    (false, false) => line.contains(text.as_str()),
+                }
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./tags_test.rs"]
+mod tags_test;
@@ -0,0 +1,100 @@
+use crate::{Document, Row, Tag, TagAddress};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+#[test]
+fn test_tag_parse() {
+    let contents = "!_TAG_FILE_FORMAT\t2\t/extended format/\n\
+        Editor\tsrc/editor.rs\t/^pub struct Editor {$/;\"\ts\n\
+        main\tsrc/main.rs\t42;\"\tf\n\
+        path\tsrc/a.rs\t/^    let path = \\/tmp\\/a;$/;\"\tv\n\
+        malformed line\n";
+    let tags = Tag::parse(contents, Path::new("/project"));
+    assert_eq!(
+        tags,
+        vec![
+            Tag {
+                name: String::from("Editor"),
+                filename: PathBuf::from("/project/src/editor.rs"),
+                address: TagAddress::Pattern {
+                    text: String::from("pub struct Editor {"),
+                    anchored_start: true,
+                    anchored_end: true,
+                },
+            },
+            Tag {
+                name: String::from("main"),
+                filename: PathBuf::from("/project/src/main.rs"),
+                address: TagAddress::Line(42),
+            },
+            Tag {
+                name: String::from("path"),
+                filename: PathBuf::from("/project/src/a.rs"),
+                address: TagAddress::Pattern {
+                    text: String::from("    let path = /tmp/a;"),
+                    anchored_start: true,
+                    anchored_end: true,
+                },
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_tag_find_row_index() {
+    let doc = Document::new(
+        vec![
+            Row::from("use std::fs;"),
+            Row::from("fn main() {"),
+            Row::from("}"),
+        ],
+        PathBuf::from("test.rs"),
+    );
+    let tag = |address| Tag {
+        name: String::from("main"),
+        filename: PathBuf::from("test.rs"),
+        address,
+    };
+    let pattern = |text: &str, anchored_start, anchored_end| TagAddress::Pattern {
+        text: String::from(text),
+        anchored_start,
+        anchored_end,
+    };
+    assert_eq!(tag(TagAddress::Line(2)).find_row_index(&doc), Some(1));
+    assert_eq!(tag(TagAddress::Line(4)).find_row_index(&doc), None);
+    assert_eq!(
+        tag(pattern("fn main() {", true, true)).find_row_index(&doc),
+        Some(1)
+    );
+    assert_eq!(
+        tag(pattern("fn main", true, false)).find_row_index(&doc),
+        Some(1)
+    );
+    assert_eq!(tag(pattern("main", true, true)).find_row_index(&doc), None);
+    assert_eq!(
+        tag(pattern("fs;", false, true)).find_row_index(&doc),
+        Some(0)
+    );
+}
+
+#[test]
+fn test_tag_find_file() {
+    let dir = tempdir().unwrap();
+    let project = dir.path().join("project");
+    let nested = project.join("src").join("module");
+    fs::create_dir_all(&nested).unwrap();
+    assert_eq!(Tag::find_file(&nested), None);
+
+    // the tags file is looked up in the parent directories
+    fs::write(project.join("tags"), "").unwrap();
+    assert_eq!(Tag::find_file(&nested), Some(project.join("tags")));
+    fs::write(nested.join("tags"), "").unwrap();
+    assert_eq!(Tag::find_file(&nested), Some(nested.join("tags")));
+    fs::remove_file(nested.join("tags")).unwrap();
+
+    // but not above the root of the project
+    fs::create_dir(project.join("src").join(".git")).unwrap();
+    assert_eq!(Tag::find_file(&nested), None);
+    assert_eq!(Tag::find_file(&project), Some(project.join("tags")));
+}
@@ -3,7 +3,7 @@ use std::cmp;
 use std::fmt;
 use std::io::{self, stdout, Write};
 use termion::color;
-use termion::cursor::{SteadyBar, SteadyBlock};
+use termion::cursor::{SteadyBar, SteadyBlock, SteadyUnderline};
 use termion::event::{Event, MouseEvent};
 use termion::input::{MouseTerminal, TermRead};
 use termion::raw::{IntoRawMode, RawTerminal};
@@ -147,19 +147,19 @@ impl Console for Terminal {
         mouse_event: MouseEvent,
         row_prefix_length: u8,
     ) -> Position {
-        if let MouseEvent::Press(_, x, y) = mouse_event {
-            let offset_adjustment: u8 = if row_prefix_length > 0 {
-                row_prefix_length.saturating_add(1)
-            } else {
-                0
-            };
-            let ansi_position = AnsiPosition {
-                x: x.saturating_sub(u16::from(offset_adjustment)),
-                y,
-            };
-            Position::from(ansi_position)
-        } else {
-            Position::top_left()
+        match mouse_event {
+            MouseEvent::Press(_, x, y) | MouseEvent::Hold(x, y) | MouseEvent::Release(x, y) => {
+                let offset_adjustment: u8 = if row_prefix_length > 0 {
+                    row_prefix_length.saturating_add(1)
+                } else {
+                    0
+                };
+                let ansi_position = AnsiPosition {
+                    x: x.saturating_sub(u16::from(offset_adjustment)),
+                    y,
+                };
+                Position::from(ansi_position)
+            }
         }
     }
 
@@ -170,6 +170,10 @@ impl Console for Terminal {
     fn set_cursor_as_steady_block(&self) {
         print!("{}", SteadyBlock);
     }
+
+    fn set_cursor_as_steady_underline(&self) {
+        print!("{SteadyUnderline}");
+    }
 }
 
 impl Terminal {
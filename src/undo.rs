@@ -0,0 +1,238 @@
+use crate::Position;
+use std::cmp;
+use std::time::{Duration, SystemTime};
+
+/// A reversible edit of a document: the `before` rows, located at the `y` row index,
+/// were replaced by the `after` rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditOp {
+    pub y: usize,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    /// Position at which the edit was made, where the cursor goes back on undo/redo
+    pub position: Position,
+}
+
+/// A state of the undo tree, reached by applying a group of edits (eg: all the characters
+/// typed in a single insert session) to its parent state. The states are numbered in the
+/// order they were created, the root one being the document as opened.
+#[derive(Debug, Clone)]
+struct UndoState {
+    parent: usize,
+    /// Child state reached by redoing, the last one created or visited
+    redo_child: Option<usize>,
+    time: SystemTime,
+    edits: Vec<EditOp>,
+}
+
+/// Whether to undo or redo, to get closer to an undo state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoStep {
+    Undo,
+    Redo,
+}
+
+/// How far `:earlier` and `:later` travel in the undo history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UndoDistance {
+    /// A number of undo/redo steps
+    Steps(usize),
+    /// A time span, from the time of the current undo state
+    Time(Duration),
+}
+
+impl UndoDistance {
+    /// Parse a number of steps (1 by default) or a time span in seconds, minutes, hours
+    /// or days (eg: `10s`, `5m`, `1h`, `2d`)
+    #[must_use]
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Some(Self::Steps(1));
+        }
+        if let Ok(steps) = text.parse() {
+            return Some(Self::Steps(steps));
+        }
+        let unit_seconds = match text.chars().last()? {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return None,
+        };
+        let count: u64 = text[..text.len() - 1].parse().ok()?;
+        Some(Self::Time(Duration::from_secs(
+            count.saturating_mul(unit_seconds),
+        )))
+    }
+}
+
+/// The undo tree of a document: undoing changes and making a new one starts a new branch,
+/// the undone changes remaining reachable with `g-`/`g+` (which go through the states in
+/// the order they were created), `:undo {n}` and `:earlier`/`:later`.
+#[derive(Debug)]
+pub struct UndoHistory {
+    /// The states, indexed by their number
+    states: Vec<UndoState>,
+    current: usize,
+    open_group: Option<Vec<EditOp>>,
+    /// Number of edits recorded so far, undoing and redoing them not being edits
+    edit_count: usize,
+}
+
+impl Default for UndoHistory {
+    fn default() -> Self {
+        Self {
+            states: vec![UndoState {
+                parent: 0,
+                redo_child: None,
+                time: SystemTime::now(),
+                edits: vec![],
+            }],
+            current: 0,
+            open_group: None,
+            edit_count: 0,
+        }
+    }
+}
+
+impl UndoHistory {
+    /// Record a new edit, which starts a new branch if changes were undone
+    pub fn record(&mut self, op: EditOp) {
+        match self.open_group.as_mut() {
+            Some(group) => group.push(op),
+            None => self.push_state(vec![op]),
+        }
+        self.edit_count = self.edit_count.wrapping_add(1);
+    }
+
+    fn push_state(&mut self, edits: Vec<EditOp>) {
+        let seq = self.states.len();
+        self.states.push(UndoState {
+            parent: self.current,
+            redo_child: None,
+            time: SystemTime::now(),
+            edits,
+        });
+        self.states[self.current].redo_child = Some(seq);
+        self.current = seq;
+    }
+
+    #[must_use]
+    pub fn edit_count(&self) -> usize {
+        self.edit_count
+    }
+
+    /// Group all the edits recorded until `end_group` is called into a single undo unit
+    pub fn start_group(&mut self) {
+        if self.open_group.is_none() {
+            self.open_group = Some(vec![]);
+        }
+    }
+
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.open_group.take() {
+            if !group.is_empty() {
+                self.push_state(group);
+            }
+        }
+    }
+
+    /// Return the edits leading to the current state to undo, and go back to its parent
+    pub fn undo(&mut self) -> Option<Vec<EditOp>> {
+        self.end_group();
+        if self.current == 0 {
+            return None;
+        }
+        let state = &self.states[self.current];
+        let (parent, edits) = (state.parent, state.edits.clone());
+        self.states[parent].redo_child = Some(self.current);
+        self.current = parent;
+        Some(edits)
+    }
+
+    /// Return the edits leading to the last created (or visited) child state to redo, and
+    /// go to it
+    pub fn redo(&mut self) -> Option<Vec<EditOp>> {
+        self.end_group();
+        let child = self.states[self.current].redo_child?;
+        self.current = child;
+        Some(self.states[child].edits.clone())
+    }
+
+    /// Return the number of the current undo state, 0 before any edit
+    #[must_use]
+    pub fn current_seq(&self) -> usize {
+        self.current
+    }
+
+    /// Return the number of the last undo state created
+    #[must_use]
+    pub fn last_seq(&self) -> usize {
+        self.states.len().saturating_sub(1)
+    }
+
+    /// Return whether the undo state with the provided number exists
+    #[must_use]
+    pub fn has_state(&self, seq: usize) -> bool {
+        seq < self.states.len()
+    }
+
+    /// Return the step getting closer to the provided undo state, making sure that redoing
+    /// follows the branch leading to it, or `None` if already there
+    pub fn next_step_towards(&mut self, seq: usize) -> Option<UndoStep> {
+        self.end_group();
+        if seq == self.current || !self.has_state(seq) {
+            return None;
+        }
+        // the target is redone to when it descends from the current state
+        let mut child = seq;
+        while child != 0 {
+            let parent = self.states[child].parent;
+            if parent == self.current {
+                self.states[parent].redo_child = Some(child);
+                return Some(UndoStep::Redo);
+            }
+            child = parent;
+        }
+        Some(UndoStep::Undo)
+    }
+
+    /// Return the number of the undo state created the provided number of states before
+    /// (or after) the current one, across all the branches
+    #[must_use]
+    pub fn seq_at_step_offset(&self, steps: usize, earlier: bool) -> usize {
+        if earlier {
+            self.current.saturating_sub(steps)
+        } else {
+            cmp::min(self.current.saturating_add(steps), self.last_seq())
+        }
+    }
+
+    /// Return the number of the last undo state created at the provided time, relative to
+    /// the time of the current state (eg: 10 seconds earlier than it)
+    #[must_use]
+    pub fn seq_at_time_offset(&self, offset: Duration, earlier: bool) -> usize {
+        let current_time = self.states[self.current].time;
+        let time = if earlier {
+            current_time.checked_sub(offset)
+        } else {
+            current_time.checked_add(offset)
+        };
+        let Some(time) = time else {
+            return if earlier { 0 } else { self.last_seq() };
+        };
+        self.states
+            .iter()
+            .rposition(|state| state.time <= time)
+            .unwrap_or(0)
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+#[path = "./undo_test.rs"]
+mod undo_test;
@@ -0,0 +1,68 @@
+use crate::{EditOp, Position, UndoDistance, UndoHistory, UndoStep};
+use std::time::Duration;
+
+fn edit(y: usize) -> EditOp {
+    EditOp {
+        y,
+        before: vec![],
+        after: vec![String::from("a")],
+        position: Position { x: 0, y },
+    }
+}
+
+#[test]
+fn test_undo_distance_parse() {
+    assert_eq!(UndoDistance::parse(""), Some(UndoDistance::Steps(1)));
+    assert_eq!(UndoDistance::parse("3"), Some(UndoDistance::Steps(3)));
+    assert_eq!(
+        UndoDistance::parse("10s"),
+        Some(UndoDistance::Time(Duration::from_secs(10)))
+    );
+    assert_eq!(
+        UndoDistance::parse("2h"),
+        Some(UndoDistance::Time(Duration::from_secs(3600 * 2)))
+    );
+    assert_eq!(UndoDistance::parse("1w"), None);
+    assert_eq!(UndoDistance::parse("m"), None);
+}
+
+#[test]
+fn test_undo_history_tree() {
+    let mut history = UndoHistory::default();
+    assert_eq!(history.current_seq(), 0);
+    history.record(edit(0));
+    history.record(edit(1));
+    history.record(edit(2));
+    assert_eq!(history.current_seq(), 3);
+    history.undo();
+    history.undo();
+    assert_eq!(history.current_seq(), 1);
+    assert!(history.has_state(3));
+    assert_eq!(history.seq_at_step_offset(1, false), 2);
+    assert_eq!(history.seq_at_step_offset(5, false), 3);
+    assert_eq!(history.seq_at_step_offset(5, true), 0);
+
+    // a new edit starts a new branch, keeping the undone states
+    history.record(edit(3));
+    assert_eq!(history.current_seq(), 4);
+    assert!(history.has_state(3));
+    assert!(!history.has_state(5));
+    assert_eq!(history.seq_at_step_offset(1, true), 3);
+
+    // redoing follows the branch leading to the target state
+    assert_eq!(history.next_step_towards(3), Some(UndoStep::Undo));
+    history.undo();
+    assert_eq!(history.next_step_towards(3), Some(UndoStep::Redo));
+    assert_eq!(history.redo(), Some(vec![edit(1)]));
+    assert_eq!(history.redo(), Some(vec![edit(2)]));
+    assert_eq!(history.current_seq(), 3);
+    assert_eq!(history.next_step_towards(3), None);
+    assert_eq!(
+        history.seq_at_time_offset(Duration::from_secs(3600), true),
+        0
+    );
+    assert_eq!(
+        history.seq_at_time_offset(Duration::from_secs(3600), false),
+        4
+    );
+}
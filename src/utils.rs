@@ -1,7 +1,11 @@
+use chrono::NaiveDateTime;
+use std::cmp;
+use std::fmt::Write as _;
 use std::fs;
-use std::io::Write;
-use std::process::Command;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
 use std::result::Result::Err;
+use std::thread;
 use termion::{color, style};
 
 /// # Panics
@@ -37,6 +41,153 @@ pub fn expand_tilde(s: &str) -> String {
     s.replace('~', env!("HOME"))
 }
 
+/// Return the paths of the files and directories starting with the typed path, sorted
+/// alphabetically, the directories ending with a `/`. The hidden files are only returned
+/// if the typed file name starts with a dot.
+#[must_use]
+pub fn complete_path(typed: &str) -> Vec<String> {
+    let (directory, file_prefix) = match typed.rfind('/') {
+        Some(index) => typed.split_at(index.saturating_add(1)),
+        None => ("", typed),
+    };
+    let listed_directory = if directory.is_empty() {
+        String::from(".")
+    } else {
+        expand_tilde(directory)
+    };
+    let Ok(entries) = fs::read_dir(listed_directory) else {
+        return vec![];
+    };
+    let mut candidates: Vec<String> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(file_prefix)
+                || (name.starts_with('.') && !file_prefix.starts_with('.'))
+            {
+                return None;
+            }
+            let suffix = if entry.path().is_dir() { "/" } else { "" };
+            Some(format!("{directory}{name}{suffix}"))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// Parse a `+N` command line argument into the line number to open the file at.
+/// A lone `+` means the last line of the file, and anything else returns `None`.
+#[must_use]
+pub fn parse_start_line_argument(arg: &str) -> Option<usize> {
+    let line_number = arg.strip_prefix('+')?;
+    if line_number.is_empty() {
+        Some(usize::MAX)
+    } else {
+        line_number.parse::<usize>().ok()
+    }
+}
+
+/// Parse the code point typed after Ctrl-V in insert mode: `u` or `U` followed by
+/// hexadecimal digits, or a decimal byte value (up to 255).
+#[must_use]
+pub fn parse_code_point(keys: &str) -> Option<char> {
+    let value = match keys.strip_prefix(['u', 'U']) {
+        Some(digits) => u32::from_str_radix(digits, 16).ok()?,
+        None => keys.parse::<u32>().ok().filter(|value| *value <= 255)?,
+    };
+    char::from_u32(value)
+}
+
+/// Run the command described by the provided template, in which `%s` is replaced by `arg`
+/// (`arg` is appended to the command if the template doesn't contain any `%s`).
+/// The command isn't run through a shell, so `arg` is always passed as a single argument.
+///
+/// # Errors
+/// Returns the error message if the command can't be run, or exits unsuccessfully.
+pub fn run_command_template(template: &str, arg: &str) -> Result<String, String> {
+    let mut tokens: Vec<String> = template
+        .split_whitespace()
+        .map(|token| token.replace("%s", arg))
+        .collect();
+    if !template.contains("%s") {
+        tokens.push(arg.to_string());
+    }
+    let (program, args) = tokens
+        .split_first()
+        .ok_or_else(|| String::from("No command to run"))?;
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("{program}: {e}"))?;
+    if output.status.success() {
+        Ok(strip_overstrike(&String::from_utf8_lossy(&output.stdout)))
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = stderr.lines().next().unwrap_or_default();
+        if error.is_empty() {
+            Err(format!("{program} exited with {}", output.status))
+        } else {
+            Err(error.to_string())
+        }
+    }
+}
+
+/// Run the shell command with the provided input written to its standard input,
+/// and return its standard output (eg: to filter lines through `sort`).
+///
+/// # Errors
+/// Returns the error message if the command can't be run, or exits unsuccessfully.
+pub fn filter_through_command(command: &str, input: &str) -> Result<String, String> {
+    let mut child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{command}: {e}"))?;
+    // the input is written from another thread while the output is read, so that a command
+    // streaming its output doesn't block once both pipe buffers are full
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = input.to_string();
+        thread::spawn(move || stdin.write_all(input.as_bytes()))
+    });
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("{command}: {e}"))?;
+    if let Some(Ok(Err(e))) = writer.map(thread::JoinHandle::join) {
+        // a command that doesn't read all its input (eg: `head`) closes the pipe early
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return Err(format!("{command}: {e}"));
+        }
+    }
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let error = stderr.lines().next().unwrap_or_default();
+        if error.is_empty() {
+            Err(format!("{command} exited with {}", output.status))
+        } else {
+            Err(error.to_string())
+        }
+    }
+}
+
+/// Remove the backspace-based overstrike sequences (used by eg `man` to render bold
+/// or underlined text) from the provided string.
+#[must_use]
+pub fn strip_overstrike(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c == '\u{8}' {
+            out.pop();
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
 #[must_use]
 pub fn git_head_short_ref() -> String {
     let git_commit = Command::new("git")
@@ -60,6 +211,110 @@ pub fn bo_version() -> String {
     }
 }
 
+/// Format the provided bytes as a hex dump, following the default `xxd` layout:
+/// the offset, 16 bytes grouped by pairs, and their ASCII representation.
+#[must_use]
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut lines = vec![];
+    for (index, chunk) in bytes.chunks(16).enumerate() {
+        let mut hex = String::new();
+        for (i, byte) in chunk.iter().enumerate() {
+            if i > 0 && i % 2 == 0 {
+                hex.push(' ');
+            }
+            let _ = write!(hex, "{byte:02x}");
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    byte as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        lines.push(format!("{:08x}: {hex:<39}  {ascii}", index * 16));
+    }
+    lines.join("\n")
+}
+
+/// Format the date and time with a strftime format (eg: `%Y-%m-%d %H:%M`), or with one
+/// of the `iso` (date), `datetime` and `time` presets. Return None if the format is invalid.
+#[must_use]
+pub fn format_datetime(datetime: &NaiveDateTime, format: &str) -> Option<String> {
+    let format = match format {
+        "iso" => "%Y-%m-%d",
+        "datetime" => "%Y-%m-%dT%H:%M:%S",
+        "time" => "%H:%M:%S",
+        format => format,
+    };
+    let mut formatted = String::new();
+    write!(formatted, "{}", datetime.format(format)).ok()?;
+    Some(formatted)
+}
+
+/// Rewrite the indentation of the line with spaces only (with `expand_tab`) or tabs
+/// only, completed by spaces when its width isn't a multiple of the tab width. The
+/// width of the tabs mixed with spaces in the indentation is preserved.
+#[must_use]
+pub fn normalize_indentation(line: &str, tab_width: usize, expand_tab: bool) -> String {
+    let tab_width = cmp::max(tab_width, 1);
+    let content = line.trim_start_matches([' ', '\t']);
+    let width = line[..line.len() - content.len()]
+        .chars()
+        .fold(0, |width: usize, c| {
+            if c == '\t' {
+                width.saturating_add(tab_width - width % tab_width)
+            } else {
+                width.saturating_add(1)
+            }
+        });
+    let indentation = if expand_tab {
+        " ".repeat(width)
+    } else {
+        "\t".repeat(width / tab_width) + &" ".repeat(width % tab_width)
+    };
+    indentation + content
+}
+
+/// Return the prefix of the line comments of the language associated with the file
+/// extension, if known
+#[must_use]
+pub fn line_comment_prefix(extension: &str) -> Option<&'static str> {
+    match extension {
+        "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "cs" | "go" | "java" | "js" | "ts" | "kt"
+        | "scala" | "swift" | "php" => Some("//"),
+        "sh" | "bash" | "zsh" | "fish" | "py" | "rb" | "pl" | "r" | "toml" | "yaml" | "yml"
+        | "conf" | "cfg" | "ini" | "mk" => Some("#"),
+        "lua" | "sql" | "hs" | "elm" => Some("--"),
+        "el" | "lisp" | "clj" | "scm" | "asm" => Some(";"),
+        "tex" | "erl" => Some("%"),
+        "vim" => Some("\""),
+        _ => None,
+    }
+}
+
+/// Return the comment leader of the line (eg: `/// ` in `    /// Doc`) if it's a line
+/// comment in the language associated with the file extension: the comment prefix,
+/// repeated characters of the prefix included, followed by a space if there's one.
+#[must_use]
+pub fn comment_leader(line: &str, extension: Option<&str>) -> Option<String> {
+    let prefix = line_comment_prefix(extension?)?;
+    let comment = line.trim_start();
+    if !comment.starts_with(prefix) {
+        return None;
+    }
+    let mut leader: String = comment
+        .chars()
+        .take_while(|c| prefix.contains(*c))
+        .collect();
+    if comment[leader.len()..].starts_with(' ') {
+        leader.push(' ');
+    }
+    Some(leader)
+}
+
 pub fn as_bold(message: &str) -> String {
     format!("{}{}{}", style::Bold, message, style::Reset)
 }
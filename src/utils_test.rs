@@ -1,5 +1,12 @@
-use crate::utils::{expand_tilde, zfill};
+use crate::utils::{
+    comment_leader, complete_path, expand_tilde, filter_through_command, format_datetime, hex_dump,
+    normalize_indentation, parse_code_point, parse_start_line_argument, run_command_template,
+    strip_overstrike, zfill,
+};
+use chrono::NaiveDate;
 use std::env;
+use std::fs;
+use tempfile::tempdir;
 
 #[test]
 fn test_zfill() {
@@ -13,3 +20,151 @@ fn test_expand_tilde() {
     assert_eq!(expand_tilde("~/code"), format!("{}/code", env!("HOME")));
     assert_eq!(expand_tilde("/~code"), "/~code");
 }
+
+#[test]
+fn test_parse_start_line_argument() {
+    assert_eq!(parse_start_line_argument("+120"), Some(120));
+    assert_eq!(parse_start_line_argument("+"), Some(usize::MAX));
+    assert_eq!(parse_start_line_argument("+abc"), None);
+    assert_eq!(parse_start_line_argument("file.rs"), None);
+}
+
+#[test]
+fn test_parse_code_point() {
+    assert_eq!(parse_code_point("u00e9"), Some('é'));
+    assert_eq!(parse_code_point("U0001F600"), Some('😀'));
+    assert_eq!(parse_code_point("65"), Some('A'));
+    assert_eq!(parse_code_point("256"), None);
+    // surrogates aren't valid characters
+    assert_eq!(parse_code_point("ud800"), None);
+    assert_eq!(parse_code_point("u"), None);
+}
+
+#[test]
+fn test_run_command_template() {
+    assert_eq!(
+        run_command_template("echo %s!", "hello"),
+        Ok(String::from("hello!\n"))
+    );
+    assert_eq!(
+        run_command_template("echo", "hello world"),
+        Ok(String::from("hello world\n"))
+    );
+    assert!(run_command_template("false %s", "hello").is_err());
+    assert!(run_command_template("bo-does-not-exist %s", "hello").is_err());
+    assert!(run_command_template("", "hello").is_err());
+}
+
+#[test]
+fn test_filter_through_command() {
+    assert_eq!(
+        filter_through_command("sort", "b\na\n"),
+        Ok(String::from("a\nb\n"))
+    );
+    // an input larger than the pipe buffers is streamed through the command
+    let input = "a line of text\n".repeat(100_000);
+    assert_eq!(filter_through_command("cat", &input), Ok(input.clone()));
+    assert_eq!(
+        filter_through_command("head -n 1", &input),
+        Ok(String::from("a line of text\n"))
+    );
+    assert!(filter_through_command("exit 3", "").is_err());
+}
+
+#[test]
+fn test_strip_overstrike() {
+    assert_eq!(strip_overstrike("N\u{8}NA\u{8}AM\u{8}ME\u{8}E"), "NAME");
+    assert_eq!(strip_overstrike("_\u{8}b_\u{8}o"), "bo");
+    assert_eq!(strip_overstrike("plain"), "plain");
+}
+
+#[test]
+fn test_hex_dump() {
+    // expected output generated with xxd
+    assert_eq!(
+        hex_dump(b"Hello world\nHello world!\x00\xff\n"),
+        "00000000: 4865 6c6c 6f20 776f 726c 640a 4865 6c6c  Hello world.Hell\n\
+         00000010: 6f20 776f 726c 6421 00ff 0a              o world!..."
+    );
+    assert_eq!(hex_dump(b""), "");
+}
+
+#[test]
+fn test_format_datetime() {
+    let datetime = NaiveDate::from_ymd_opt(2022, 4, 24)
+        .unwrap()
+        .and_hms_opt(9, 5, 3)
+        .unwrap();
+    assert_eq!(format_datetime(&datetime, "iso").unwrap(), "2022-04-24");
+    assert_eq!(
+        format_datetime(&datetime, "datetime").unwrap(),
+        "2022-04-24T09:05:03"
+    );
+    assert_eq!(format_datetime(&datetime, "time").unwrap(), "09:05:03");
+    assert_eq!(
+        format_datetime(&datetime, "%d/%m/%Y %H:%M").unwrap(),
+        "24/04/2022 09:05"
+    );
+    assert_eq!(format_datetime(&datetime, "%Q"), None);
+    // the time zone is unknown
+    assert_eq!(format_datetime(&datetime, "%z"), None);
+}
+
+#[test]
+fn test_complete_path() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().to_str().unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("main.rs"), "").unwrap();
+    fs::write(dir.path().join("mode.rs"), "").unwrap();
+    fs::write(dir.path().join(".hidden"), "").unwrap();
+    assert_eq!(
+        complete_path(&format!("{path}/m")),
+        vec![format!("{path}/main.rs"), format!("{path}/mode.rs")]
+    );
+    assert_eq!(
+        complete_path(&format!("{path}/")),
+        vec![
+            format!("{path}/main.rs"),
+            format!("{path}/mode.rs"),
+            format!("{path}/src/")
+        ]
+    );
+    assert_eq!(
+        complete_path(&format!("{path}/.h")),
+        vec![format!("{path}/.hidden")]
+    );
+    assert!(complete_path(&format!("{path}/x")).is_empty());
+    assert!(complete_path(&format!("{path}/nope/")).is_empty());
+}
+
+#[test]
+fn test_comment_leader() {
+    assert_eq!(
+        comment_leader("    // a comment", Some("rs")),
+        Some(String::from("// "))
+    );
+    assert_eq!(
+        comment_leader("/// Doc", Some("rs")),
+        Some(String::from("/// "))
+    );
+    assert_eq!(comment_leader("//", Some("rs")), Some(String::from("//")));
+    assert_eq!(
+        comment_leader("## title", Some("sh")),
+        Some(String::from("## "))
+    );
+    assert_eq!(comment_leader("let a = 1; // b", Some("rs")), None);
+    assert_eq!(comment_leader("# not a comment", Some("rs")), None);
+    assert_eq!(comment_leader("// a comment", Some("txt")), None);
+    assert_eq!(comment_leader("// a comment", None), None);
+}
+
+#[test]
+fn test_normalize_indentation() {
+    assert_eq!(normalize_indentation("\t  a\tb", 4, true), "      a\tb");
+    assert_eq!(normalize_indentation("  \ta", 4, true), "    a");
+    assert_eq!(normalize_indentation("      a", 4, false), "\t  a");
+    assert_eq!(normalize_indentation(" \t \ta", 4, false), "\t\ta");
+    assert_eq!(normalize_indentation("a  ", 4, false), "a  ");
+    assert_eq!(normalize_indentation(" \t", 2, true), "  ");
+}